@@ -44,6 +44,6 @@ async fn main() {
     println!("Notif Count: {:#?}", notification_count);
     let notifications = me.list_notifications(30).await.unwrap();
     // // println!("Notifications\n{:#?}", notifications);
-    // println!("Notifications\n{:#?}", notifications.into_iter().filter(|n| n.reason == "follow").collect::<Vec<Notification<NotificationRecord>>>());
+    // println!("Notifications\n{:#?}", notifications.into_iter().filter(|n| n.reason == NotificationReason::Follow).collect::<Vec<Notification<NotificationRecord>>>());
     me.update_seen().await.unwrap();
 }
@@ -1,12 +1,7 @@
 use bisky::atproto::{Client, ClientBuilder, UserSession};
 use bisky::bluesky::Bluesky;
-use bisky::lexicon::app::bsky::feed::{ImagesEmbed, ReplyRef};
-use bisky::lexicon::app::bsky::embed::{Image};
-use bisky::lexicon::app::bsky::notification::{Notification, NotificationRecord};
-use bisky::lexicon::com::atproto::repo::StrongRef;
+use bisky::lexicon::app::bsky::notification::{Notification, NotificationReason, NotificationRecord};
 use bisky::lexicon::app::bsky::feed::Post;
-use bisky::lexicon::app::bsky::notification::NotificationRecord::Post as NotificationPost;
-use bisky::lexicon::app::bsky::feed::Embeds;
 use bisky::storage::File;
 use clap::Parser;
 use std::path::PathBuf;
@@ -63,16 +58,14 @@ async fn main() {
         let mut me = bsky.me().unwrap();
         let notifications = me.list_notifications(10).await.unwrap();
         me.update_seen().await.unwrap();
-        let mentions =  notifications.into_iter().filter(|n| (n.reason == "mention" && n.is_read == false)).collect::<Vec<Notification<NotificationRecord>>>();
+        let mentions =  notifications.into_iter().filter(|n| (n.reason == NotificationReason::Mention && n.is_read == false)).collect::<Vec<Notification<NotificationRecord>>>();
         if !mentions.is_empty(){
             println!("Mentions\n{:#?}", mentions);
         }
 
         for mention in mentions{
-            let uri = mention.uri;
-            let cid = mention.cid;
-            let  (text, reply) = match mention.record{
-                NotificationRecord::Post(p) => (p.text, p.reply),
+            let text = match &mention.record{
+                NotificationRecord::Post(p) => p.text.clone(),
                 _ => panic!("What are you feeding me Seymore?"),
             };
             println!("POSTText {:#?}",text);
@@ -80,56 +73,21 @@ async fn main() {
             if text.contains("@benwis.bsky.social /honk"){
                 println!("HONK");
 
-                // As I understand this, if there is no parent(it is the root), then both parent and root uri/cid can be set to the ones in the root of the mention
-                // If reply is Some(), then we'll need the uri/cid of the post from the root for parent, and the uri/cid from reply: root for the root
-                let mut resp_reply_ref = ReplyRef{
-                    parent: StrongRef{uri: uri.clone(), cid: cid.clone()}, 
-                    root: StrongRef{uri, cid},
-                };
-
-                match reply{
-                    Some(r) => {
-                        resp_reply_ref.root.uri = r.root.uri;
-                        resp_reply_ref.root.cid = r.root.cid;
-                        },
-                    None => ()
-                };
+                let image = me.upload_image(&img, "HONK").await.unwrap();
+                println!("Image: {:#?}", image);
 
-                let blob_output = me.upload_blob(&img, "image/jpeg").await.unwrap();
-                println!("Blob: {:#?}", blob_output.blob);
-                let image = Image{image:blob_output.blob, alt: "HONK".to_string()};
-                let images_embed = ImagesEmbed{images: vec!(image)};
-                let embed = Embeds::Images(images_embed);
-        
-                me.post(Post {
-                    rust_type: Some("app.bsky.feed.post".to_string()),
-                    text: "HONK".to_string(),
-                    created_at: chrono::Utc::now(),
-                    embed: Some(embed),
-                    reply: Some(resp_reply_ref),
-                })
-                .await
-                .unwrap();
+                let post = Post::builder()
+                    .text("HONK")
+                    .reply_to(&mention)
+                    .images(vec!(image))
+                    .build()
+                    .unwrap();
+                me.post(post).await.unwrap();
 
             }
             else if text.contains("@benwis.bsky.social /d20"){
                 println!("D20");
 
-                // As I understand this, if there is no parent(it is the root), then both parent and root uri/cid can be set to the ones in the root of the mention
-                // If reply is Some(), then we'll need the uri/cid of the post from the root for parent, and the uri/cid from reply: root for the root
-                let mut resp_reply_ref = ReplyRef{
-                    parent: StrongRef{uri: uri.clone(), cid: cid.clone()}, 
-                    root: StrongRef{uri, cid},
-                };
-
-                match reply{
-                    Some(r) => {
-                        resp_reply_ref.root.uri = r.root.uri;
-                        resp_reply_ref.root.cid = r.root.cid;
-                        },
-                    None => ()
-                };
-
                 let roll = rand::thread_rng().gen_range(1..21);
 
                 let msg = match roll{
@@ -143,15 +101,12 @@ async fn main() {
                     21_i32..=i32::MAX => "This cannot be! These acts befit a god more than a mortal",
                 };
         
-                me.post(Post {
-                    rust_type: Some("app.bsky.feed.post".to_string()),
-                    text: format!("You rolled a {roll}.\n{msg}"),
-                    created_at: chrono::Utc::now(),
-                    embed: None,
-                    reply: Some(resp_reply_ref),
-                })
-                .await
-                .unwrap();
+                let post = Post::builder()
+                    .text(format!("You rolled a {roll}.\n{msg}"))
+                    .reply_to(&mention)
+                    .build()
+                    .unwrap();
+                me.post(post).await.unwrap();
 
             }
         }
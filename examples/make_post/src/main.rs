@@ -37,10 +37,7 @@ async fn main() {
         bsky
             .me()
             .unwrap()
-            .post(Post {
-                text: args.post_text,
-                created_at: chrono::Utc::now(),
-            })
+            .post(Post::builder().text(args.post_text).build().unwrap())
             .await
             .unwrap()
     );
@@ -1,7 +1,6 @@
 use bisky::atproto::{Client, ClientBuilder, UserSession};
 use bisky::bluesky::Bluesky;
-use bisky::lexicon::app::bsky::feed::{Post, Embeds, ImagesEmbed};
-use bisky::lexicon::app::bsky::embed::{Image};
+use bisky::lexicon::app::bsky::feed::Post;
 
 use bisky::storage::{File, Storage as _};
 use clap::Parser;
@@ -39,23 +38,20 @@ async fn main() {
     let mut bsky = Bluesky::new(client);
     let mut me = bsky.me().unwrap();
     
-    let blob_output = me.upload_blob(&image, "image/jpeg").await.unwrap();
-    println!("Blob: {:#?}", blob_output.blob);
-    let image = Image{image:blob_output.blob, alt: "HONK WITH RUST".to_string()};
-    let images_embed = ImagesEmbed{images: vec!(image)};
-    let embed = Embeds::Images(images_embed);
+    let image = me.upload_image(&image, "HONK WITH RUST").await.unwrap();
+    println!("Image: {:#?}", image);
+    let post = Post::builder()
+        .text(args.post_text)
+        .images(vec!(image))
+        .build()
+        .unwrap();
 
     println!(
         "{:#?}",
         bsky
             .me()
             .unwrap()
-            .post(Post {
-                rust_type: Some("app.bsky.feed.post".to_string()),
-                text: args.post_text,
-                created_at: chrono::Utc::now(),
-                embed: Some(embed),
-            })
+            .post(post)
             .await
             .unwrap()
     );
@@ -1,14 +1,128 @@
 use crate::errors::{ApiError, BiskyError};
-use crate::lexicon::com::atproto::repo::{CreateRecord, ListRecordsOutput, Record};
-use crate::lexicon::com::atproto::server::{CreateUserSession, RefreshUserSession};
+use crate::filter::KeywordFilter;
+use crate::lexicon::app::bsky::feed::Post;
+use crate::lexicon::com::atproto::identity::ResolveHandleOutput;
+use crate::lexicon::com::atproto::moderation::{
+    CreateReport, CreateReportOutput, ReasonType, ReportSubject,
+};
+use crate::lexicon::com::atproto::repo::{
+    ApplyWrite, ApplyWrites, ApplyWritesOutput, CreateRecord, DeleteRecord, DeleteRecordOutput,
+    DescribeRepoOutput, GetRecordOutput, ListRecordsOutput, PutRecord, PutRecordOutput, Record,
+};
+use crate::lexicon::com::atproto::server::{
+    CreateUserSession, GetServiceAuthOutput, RefreshUserSession,
+};
 use crate::storage::Storage;
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// An `at://` URI, naming a repository and optionally a collection and record in it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AtUri {
+    /// A DID or handle
+    pub authority: String,
+    pub collection: Option<String>,
+    pub rkey: Option<String>,
+}
+
+impl AtUri {
+    pub fn new(authority: &str, collection: &str, rkey: &str) -> Self {
+        Self {
+            authority: authority.to_string(),
+            collection: Some(collection.to_string()),
+            rkey: Some(rkey.to_string()),
+        }
+    }
+}
+
+impl FromStr for AtUri {
+    type Err = BiskyError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = || BiskyError::InvalidAtUri(uri.to_string());
+
+        let path = uri.strip_prefix("at://").ok_or_else(invalid)?;
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let mut parts = path.split('/');
+
+        let authority = parts.next().filter(|a| !a.is_empty()).ok_or_else(invalid)?;
+        let collection = parts.next();
+        let rkey = parts.next();
+
+        // A single trailing slash is fine, but an empty segment would be lost by `Display`
+        if parts.next().is_some() || collection == Some("") || rkey == Some("") {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            authority: authority.to_string(),
+            collection: collection.map(str::to_string),
+            rkey: rkey.map(str::to_string),
+        })
+    }
+}
+
+impl TryFrom<String> for AtUri {
+    type Error = BiskyError;
+
+    fn try_from(uri: String) -> Result<Self, Self::Error> {
+        uri.parse()
+    }
+}
+
+impl From<AtUri> for String {
+    fn from(uri: AtUri) -> Self {
+        uri.to_string()
+    }
+}
+
+impl fmt::Display for AtUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at://{}", self.authority)?;
+        if let Some(collection) = &self.collection {
+            write!(f, "/{collection}")?;
+            if let Some(rkey) = &self.rkey {
+                write!(f, "/{rkey}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A new record key in the timestamp identifier (TID) format the PDS uses for records it names
+/// itself. Keys made by one process are always increasing
+pub fn new_tid() -> String {
+    const ALPHABET: &[u8; 32] = b"234567abcdefghijklmnopqrstuvwxyz";
+    static LAST_MICROS: AtomicU64 = AtomicU64::new(0);
+
+    let now = Utc::now().timestamp_micros() as u64;
+    let mut last = LAST_MICROS.load(Ordering::Relaxed);
+    let micros = loop {
+        let next = now.max(last + 1);
+        match LAST_MICROS.compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => break next,
+            Err(actual) => last = actual,
+        }
+    };
+
+    let clock_id = u64::from(std::process::id()) & 0x3ff;
+    let tid = (micros << 10) | clock_id;
+    (0..13)
+        .rev()
+        .map(|i| ALPHABET[((tid >> (i * 5)) & 31) as usize] as char)
+        .collect()
+}
+
 #[derive(Debug, Default, Deserialize, Clone, Serialize)]
 pub struct Jwt {
     access: String,
@@ -53,6 +167,16 @@ pub trait StorableSession: Storage<UserSession, Error = BiskyError> + Send + Syn
 pub struct Client {
     #[builder(default = r#"reqwest::Url::parse("https://bsky.social").unwrap()"#)]
     service: reqwest::Url,
+    /// Where videos are uploaded to be processed, see `BlueskyMe::upload_video`
+    #[builder(default = r#"reqwest::Url::parse("https://video.bsky.app").unwrap()"#)]
+    video_service: reqwest::Url,
+    /// The `atproto-proxy` value direct messages are sent through, see `BlueskyMe::send_message`
+    #[builder(default = r#""did:web:api.bsky.chat#bsky_chat".to_string()"#)]
+    chat_proxy: String,
+    /// DIDs of the labelers whose labels the AppView should include in views, see
+    /// `Client::set_labelers`
+    #[builder(default)]
+    labelers: Vec<String>,
     #[builder(default, setter(strip_option))]
     storage: Option<Arc<dyn StorableSession>>,
     #[builder(default, setter(custom))]
@@ -78,6 +202,7 @@ impl ClientBuilder {
 trait GetService {
     fn get_service(&self) -> &reqwest::Url;
     fn access_token(&self) -> Result<&str, BiskyError>;
+    fn labelers(&self) -> &[String];
 }
 
 impl GetService for Client {
@@ -91,6 +216,10 @@ impl GetService for Client {
             None => Err(BiskyError::MissingSession),
         }
     }
+
+    fn labelers(&self) -> &[String] {
+        &self.labelers
+    }
 }
 
 impl Client {
@@ -166,20 +295,100 @@ impl Client {
         Ok(())
     }
 
+    /// Ask for labels from these labelers, along with the AppView's own moderation labels, in
+    /// the views returned from now on. The labelers are usually the ones the account is
+    /// subscribed to, see `ModerationOptions`
+    pub fn set_labelers(&mut self, labelers: Vec<String>) {
+        self.labelers = labelers;
+    }
+
+    pub(crate) fn video_service(&self) -> &reqwest::Url {
+        &self.video_service
+    }
+
+    pub(crate) fn chat_proxy(&self) -> &str {
+        &self.chat_proxy
+    }
+
+    /// Query an XRPC method on a service other than the PDS, authenticating with a service auth
+    /// token (see `server_get_service_auth`) if one is given
+    pub(crate) async fn service_get<D: DeserializeOwned>(
+        &self,
+        service: &reqwest::Url,
+        path: &str,
+        query: &[(&str, &str)],
+        token: Option<&str>,
+    ) -> Result<D, BiskyError> {
+        let mut request = reqwest::Client::new()
+            .get(service.join(&format!("xrpc/{path}")).unwrap())
+            .query(query);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {token}"));
+        }
+
+        let response = request.send().await?;
+        if response.status().is_client_error() {
+            return Err(BiskyError::ApiError(response.json::<ApiError>().await?));
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Send binary data to an XRPC procedure on a service other than the PDS. The status and
+    /// body are returned as they are, as services may describe a failure in a regular output
+    pub(crate) async fn service_post_binary(
+        &self,
+        service: &reqwest::Url,
+        path: &str,
+        query: &[(&str, &str)],
+        body: &[u8],
+        mime_type: &str,
+        token: &str,
+    ) -> Result<(reqwest::StatusCode, String), BiskyError> {
+        let response = reqwest::Client::new()
+            .post(service.join(&format!("xrpc/{path}")).unwrap())
+            .query(query)
+            .header("content-type", mime_type)
+            .header("authorization", format!("Bearer {token}"))
+            .body(body.to_vec())
+            .send()
+            .await?;
+
+        Ok((response.status(), response.text().await?))
+    }
+
     pub(crate) async fn xrpc_get<D: DeserializeOwned + std::fmt::Debug>(
         &mut self,
         path: &str,
         query: Option<&[(&str, &str)]>,
+    ) -> Result<D, BiskyError> {
+        self.xrpc_get_via(path, query, None).await
+    }
+
+    /// Like `xrpc_get`, but asks the PDS to forward the call to another service, see
+    /// `xrpc_post_via`
+    pub(crate) async fn xrpc_get_via<D: DeserializeOwned + std::fmt::Debug>(
+        &mut self,
+        path: &str,
+        query: Option<&[(&str, &str)]>,
+        proxy: Option<&str>,
     ) -> Result<D, BiskyError> {
         fn make_request<T: GetService>(
             self_: &T,
             path: &str,
             query: &Option<&[(&str, &str)]>,
+            proxy: Option<&str>,
         ) -> Result<reqwest::RequestBuilder, BiskyError> {
             let mut request = reqwest::Client::new()
                 .get(self_.get_service().join(&format!("xrpc/{path}")).unwrap())
                 .header("authorization", format!("Bearer {}", self_.access_token()?));
 
+            if let Some(proxy) = proxy {
+                request = request.header("atproto-proxy", proxy);
+            }
+
+            if !self_.labelers().is_empty() {
+                request = request.header("atproto-accept-labelers", self_.labelers().join(", "));
+            }
             if let Some(query) = query {
                 request = request.query(query);
             }
@@ -187,13 +396,13 @@ impl Client {
             Ok(request)
         }
 
-        let mut response = make_request(self, path, &query)?.send().await?;
+        let mut response = make_request(self, path, &query, proxy)?.send().await?;
 
         if response.status() == reqwest::StatusCode::BAD_REQUEST {
             let error = response.json::<ApiError>().await?;
             if error.error == "ExpiredToken" {
                 self.xrpc_refresh_token().await?;
-                response = make_request(self, path, &query)?.send().await?;
+                response = make_request(self, path, &query, proxy)?.send().await?;
             } else {
                 return Err(BiskyError::ApiError(error));
             }
@@ -206,6 +415,17 @@ impl Client {
         &mut self,
         path: &str,
         body: &D1,
+    ) -> Result<D2, BiskyError> {
+        self.xrpc_post_via(path, body, None).await
+    }
+
+    /// Like `xrpc_post`, but asks the PDS to forward the call to another service, given as
+    /// `{did}#{service id}` (for example `did:plc:...#atproto_labeler`)
+    pub(crate) async fn xrpc_post_via<D1: Serialize, D2: DeserializeOwned>(
+        &mut self,
+        path: &str,
+        body: &D1,
+        proxy: Option<&str>,
     ) -> Result<D2, BiskyError> {
         let body = serde_json::to_string(body)?;
 
@@ -213,28 +433,36 @@ impl Client {
             self_: &T,
             path: &str,
             body: &str,
+            proxy: Option<&str>,
         ) -> Result<reqwest::RequestBuilder, BiskyError> {
-            let req = reqwest::Client::new()
+            let mut req = reqwest::Client::new()
                 .post(self_.get_service().join(&format!("xrpc/{path}")).unwrap())
                 .header("content-type", "application/json")
                 .header("authorization", format!("Bearer {}", self_.access_token()?))
                 .body(body.to_string());
+            if let Some(proxy) = proxy {
+                req = req.header("atproto-proxy", proxy);
+            }
             Ok(req)
         }
 
-        let mut response = make_request(self, path, &body)?.send().await?;
+        let mut response = make_request(self, path, &body, proxy)?.send().await?;
 
         if response.status() == reqwest::StatusCode::BAD_REQUEST {
             let error = response.json::<ApiError>().await?;
             if error.error == "ExpiredToken" {
                 self.xrpc_refresh_token().await?;
-                response = make_request(self, path, &body)?.send().await?;
+                response = make_request(self, path, &body, proxy)?.send().await?;
             } else {
                 return Err(BiskyError::ApiError(error));
             }
         }
         let text = response.error_for_status()?.text().await?;
-        Ok(serde_json::from_str(&text)?)
+        // some procedures answer with an empty body rather than an empty object
+        match text.is_empty() {
+            true => Ok(serde_json::from_str("{}")?),
+            false => Ok(serde_json::from_str(&text)?),
+        }
     }
 
     pub(crate) async fn xrpc_post_binary<D2: DeserializeOwned>(
@@ -363,6 +591,21 @@ impl<'a, D: DeserializeOwned + std::fmt::Debug> RecordStream<'a, D> {
     }
 }
 
+impl RecordStream<'_, Post> {
+    /// The next post that doesn't match `filter`
+    pub async fn next_filtered(
+        &mut self,
+        filter: &KeywordFilter,
+    ) -> Result<Record<Post>, StreamError> {
+        loop {
+            let record = self.next().await?;
+            if filter.allows_record(&record) {
+                return Ok(record);
+            }
+        }
+    }
+}
+
 impl Client {
     pub async fn repo_list_records<D: DeserializeOwned + std::fmt::Debug>(
         &mut self,
@@ -424,6 +667,82 @@ impl Client {
         .await
     }
 
+    ///com.atproto.repo.getRecord
+    pub async fn repo_get_record<D: DeserializeOwned + std::fmt::Debug>(
+        &mut self,
+        repo: &str,
+        collection: &str,
+        rkey: &str,
+    ) -> Result<GetRecordOutput<D>, BiskyError> {
+        self.xrpc_get(
+            "com.atproto.repo.getRecord",
+            Some(&[("repo", repo), ("collection", collection), ("rkey", rkey)]),
+        )
+        .await
+    }
+
+    ///com.atproto.repo.putRecord
+    pub async fn repo_put_record<S: Serialize>(
+        &mut self,
+        repo: &str,
+        collection: &str,
+        rkey: &str,
+        record: S,
+        swap_record: Option<Option<&str>>,
+    ) -> Result<PutRecordOutput, BiskyError> {
+        self.xrpc_post(
+            "com.atproto.repo.putRecord",
+            &PutRecord {
+                repo,
+                collection,
+                rkey,
+                record,
+                swap_record,
+            },
+        )
+        .await
+    }
+
+    ///com.atproto.repo.applyWrites
+    pub async fn repo_apply_writes<S: Serialize>(
+        &mut self,
+        repo: &str,
+        writes: &[ApplyWrite<S>],
+    ) -> Result<ApplyWritesOutput, BiskyError> {
+        self.xrpc_post(
+            "com.atproto.repo.applyWrites",
+            &ApplyWrites { repo, writes },
+        )
+        .await
+    }
+
+    ///com.atproto.repo.deleteRecord
+    pub async fn repo_delete_record(
+        &mut self,
+        repo: &str,
+        collection: &str,
+        rkey: &str,
+    ) -> Result<DeleteRecordOutput, BiskyError> {
+        self.xrpc_post(
+            "com.atproto.repo.deleteRecord",
+            &DeleteRecord {
+                repo,
+                collection,
+                rkey,
+            },
+        )
+        .await
+    }
+
+    ///com.atproto.repo.describeRepo
+    pub async fn repo_describe_repo(
+        &mut self,
+        repo: &str,
+    ) -> Result<DescribeRepoOutput, BiskyError> {
+        self.xrpc_get("com.atproto.repo.describeRepo", Some(&[("repo", repo)]))
+            .await
+    }
+
     pub async fn repo_upload_blob<D: DeserializeOwned>(
         &mut self,
         blob: &[u8],
@@ -454,4 +773,141 @@ impl Client {
             Err(StreamError::NoCursor)
         }
     }
+
+    ///com.atproto.identity.resolveHandle
+    pub async fn identity_resolve_handle(&mut self, handle: &str) -> Result<String, BiskyError> {
+        self.xrpc_get::<ResolveHandleOutput>(
+            "com.atproto.identity.resolveHandle",
+            Some(&[("handle", handle)]),
+        )
+        .await
+        .map(|o| o.did)
+    }
+
+    ///com.atproto.moderation.createReport
+    ///
+    /// Reports go to the labeler whose DID is given, or to the PDS's default moderation service
+    pub async fn moderation_create_report(
+        &mut self,
+        subject: &ReportSubject,
+        reason_type: &ReasonType,
+        reason: Option<&str>,
+        labeler: Option<&str>,
+    ) -> Result<CreateReportOutput, BiskyError> {
+        let proxy = labeler.map(|labeler| format!("{labeler}#atproto_labeler"));
+        self.xrpc_post_via(
+            "com.atproto.moderation.createReport",
+            &CreateReport {
+                reason_type,
+                reason,
+                subject,
+            },
+            proxy.as_deref(),
+        )
+        .await
+    }
+
+    ///com.atproto.server.getServiceAuth
+    pub async fn server_get_service_auth(
+        &mut self,
+        aud: &str,
+        lxm: Option<&str>,
+        exp: Option<DateTime<Utc>>,
+    ) -> Result<String, BiskyError> {
+        let exp = exp.map(|exp| exp.timestamp().to_string());
+        let mut query = Vec::from([("aud", aud)]);
+        if let Some(lxm) = lxm {
+            query.push(("lxm", lxm));
+        }
+        if let Some(exp) = &exp {
+            query.push(("exp", exp));
+        }
+
+        self.xrpc_get::<GetServiceAuthOutput>("com.atproto.server.getServiceAuth", Some(&query))
+            .await
+            .map(|o| o.token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_at_uris() {
+        for (uri, collection, rkey) in [
+            ("at://did:plc:alice", None, None),
+            (
+                "at://did:plc:alice/app.bsky.feed.post",
+                Some("app.bsky.feed.post"),
+                None,
+            ),
+            (
+                "at://did:plc:alice/app.bsky.feed.post/3k2a",
+                Some("app.bsky.feed.post"),
+                Some("3k2a"),
+            ),
+        ] {
+            let parsed: AtUri = uri.parse().unwrap();
+            assert_eq!(parsed.authority, "did:plc:alice");
+            assert_eq!(parsed.collection.as_deref(), collection);
+            assert_eq!(parsed.rkey.as_deref(), rkey);
+            assert_eq!(parsed.to_string(), uri);
+        }
+
+        let handle: AtUri = "at://alice.test/app.bsky.graph.list/3k2a".parse().unwrap();
+        assert_eq!(
+            handle,
+            AtUri::new("alice.test", "app.bsky.graph.list", "3k2a")
+        );
+    }
+
+    #[test]
+    fn drops_a_trailing_slash_query_and_fragment() {
+        for (uri, expected) in [
+            ("at://did:plc:alice/", "at://did:plc:alice"),
+            (
+                "at://did:plc:alice/app.bsky.feed.post/",
+                "at://did:plc:alice/app.bsky.feed.post",
+            ),
+            (
+                "at://did:plc:alice/app.bsky.feed.post/3k2a/",
+                "at://did:plc:alice/app.bsky.feed.post/3k2a",
+            ),
+            (
+                "at://did:plc:alice/app.bsky.feed.post/3k2a?x=1#y",
+                "at://did:plc:alice/app.bsky.feed.post/3k2a",
+            ),
+        ] {
+            assert_eq!(uri.parse::<AtUri>().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_at_uris() {
+        for uri in [
+            "",
+            "did:plc:alice",
+            "https://bsky.app/profile/did:plc:alice",
+            "at://",
+            "at:///app.bsky.feed.post",
+            "at://did:plc:alice//3k2a",
+            "at://did:plc:alice/app.bsky.feed.post/3k2a/extra",
+            "at://did:plc:alice/app.bsky.feed.post/3k2a//",
+        ] {
+            assert!(
+                matches!(uri.parse::<AtUri>(), Err(BiskyError::InvalidAtUri(u)) if u == uri),
+                "{uri}"
+            );
+        }
+    }
+
+    #[test]
+    fn serializes_as_a_string() {
+        let uri = AtUri::new("did:plc:alice", "app.bsky.feed.post", "3k2a");
+        let json = serde_json::to_value(&uri).unwrap();
+        assert_eq!(json, "at://did:plc:alice/app.bsky.feed.post/3k2a");
+        assert_eq!(serde_json::from_value::<AtUri>(json).unwrap(), uri);
+        assert!(serde_json::from_value::<AtUri>("bad".into()).is_err());
+    }
 }
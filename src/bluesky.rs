@@ -1,19 +1,70 @@
-use crate::atproto::{Client, RecordStream, StreamError};
-use crate::errors::BiskyError;
-use crate::lexicon::app::bsky::actor::{ProfileView, ProfileViewDetailed};
+use crate::atproto::{new_tid, AtUri, Client, RecordStream, StreamError};
+use crate::errors::{ApiError, BiskyError};
+use crate::filter::KeywordFilter;
+use crate::lexicon::app::bsky::actor::{
+    MutedWord, Preferences, Profile, ProfileView, ProfileViewBasic, ProfileViewDetailed,
+    SearchActorsOutput, SearchActorsTypeaheadOutput,
+};
+use crate::lexicon::app::bsky::embed::{AspectRatio, Caption, ExternalObject, Image, Video};
 use crate::lexicon::app::bsky::feed::{
-    GetLikesLike, GetLikesOutput, GetPostThreadOutput, Post, ThreadViewPostEnum,
+    AuthorFeed, AuthorFeedFilter, FeedViewPost, GeneratorView, GetActorLikesOutput,
+    GetFeedGeneratorOutput, GetFeedGeneratorsOutput, GetFeedOutput, GetLikesLike, GetLikesOutput,
+    GetListFeedOutput, GetPostThreadOutput, GetPostsOutput, GetQuotesOutput, GetRepostedByOutput,
+    GetTimelineOutput, Like, Post, PostView, Postgate, PostgateEmbeddingRule, Repost, SearchPosts,
+    SearchPostsOutput, SearchPostsSort, ThreadViewPostEnum, Threadgate, ThreadgateRule,
+};
+use crate::lexicon::app::bsky::graph::{
+    Block, Follow, GetActorStarterPacksOutput, GetFollowersOutput, GetFollowsOutput,
+    GetListBlocksOutput, GetListMutesOutput, GetListOutput, GetListsOutput, GetStarterPackOutput,
+    List, ListBlock, ListItem, ListItemView, ListView, MuteActor, MuteActorList, MuteThread,
+    StarterPack, StarterPackFeedItem, StarterPackView, StarterPackViewBasic,
 };
-use crate::lexicon::app::bsky::graph::{GetFollowersOutput, GetFollowsOutput};
+use crate::lexicon::app::bsky::labeler::{GetServicesOutput, LabelerViewDetailed};
 use crate::lexicon::app::bsky::notification::{
-    ListNotificationsOutput, Notification, NotificationCount, NotificationRecord, UpdateSeen,
+    GetPreferencesOutput as GetNotificationPreferencesOutput, ListNotificationsOutput,
+    Notification, NotificationCount, NotificationPreferences, NotificationReason,
+    NotificationRecord, PutPreferences, PutPreferencesV2, PutPreferencesV2Output, RegisterPush,
+    UnregisterPush, UpdateSeen,
+};
+use crate::lexicon::app::bsky::video::{
+    GetJobStatusOutput, GetUploadLimitsOutput, JobStatus, UploadVideoOutput,
+};
+use crate::lexicon::chat::bsky::convo::{
+    ConvoOutput, ConvoRef, ConvoView, DeletedMessageView, GetLogOutput, GetMessagesOutput,
+    ListConvosOutput, LogEvent, Message, MessageInput, MessageRef, MessageView, Reaction,
+    ReactionOutput, SendMessage, SendMessageBatch, SendMessageBatchOutput, UpdateRead,
+};
+use crate::lexicon::com::atproto::moderation::{CreateReportOutput, ReasonType, ReportSubject};
+use crate::lexicon::com::atproto::repo::{
+    ApplyWrite, ApplyWriteResult, Blob, BlobOutput, CreateRecordOutput, Record, StrongRef,
+};
+use crate::linkcard::LinkCardFetcher;
+use crate::media::prepare_image;
+use crate::moderation::ModerationOptions;
+use crate::notifications::{group_notifications, NotificationGroup};
+use crate::profile::ProfileUpdate;
+use crate::storage::Storage;
+use crate::validation::{
+    validate_message, validate_post, validate_profile, validate_report_reason, validate_video,
+    ValidationError, MAX_AVATAR_SIZE, MAX_BANNER_SIZE, MAX_CAPTIONS, MAX_CAPTION_BLOB_SIZE,
+    MAX_EXTERNAL_THUMB_SIZE, MAX_IMAGE_BLOB_SIZE, MAX_STARTER_PACK_FEEDS, MAX_VIDEO_BLOB_SIZE,
 };
-use crate::lexicon::com::atproto::repo::{BlobOutput, CreateRecordOutput, Record};
 use chrono::{DateTime, Utc};
+use reqwest::Url;
 use serde::de::DeserializeOwned;
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// The most writes com.atproto.repo.applyWrites takes in one call
+const MAX_APPLY_WRITES: usize = 200;
+/// How often `upload_video` asks the video service whether the video is ready
+const VIDEO_JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long `upload_video` waits for a video to be processed before giving up
+const VIDEO_JOB_MAX_WAIT: Duration = Duration::from_secs(10 * 60);
+
 pub struct Bluesky {
     client: Client,
 }
@@ -23,7 +74,7 @@ impl Bluesky {
         Self { client }
     }
 
-    pub fn user(&mut self, username: &str) -> Result<BlueskyUser, BiskyError> {
+    pub fn user(&mut self, username: &str) -> Result<BlueskyUser<'_>, BiskyError> {
         let Some(_session) = &self.client.session else{
             return Err(BiskyError::MissingSession);
         };
@@ -33,7 +84,12 @@ impl Bluesky {
         })
     }
 
-    pub fn me(&mut self) -> Result<BlueskyMe, BiskyError> {
+    /// See `Client::set_labelers`
+    pub fn set_labelers(&mut self, labelers: Vec<String>) {
+        self.client.set_labelers(labelers);
+    }
+
+    pub fn me(&mut self) -> Result<BlueskyMe<'_>, BiskyError> {
         let Some(session) = &self.client.session else{
             return Err(BiskyError::MissingSession);
         };
@@ -43,6 +99,249 @@ impl Bluesky {
         })
     }
 
+    /// Resolve a handle to the DID it currently points at
+    pub async fn resolve_handle(&mut self, handle: &str) -> Result<String, BiskyError> {
+        self.client.identity_resolve_handle(handle).await
+    }
+
+    ///app.bsky.actor.getProfile
+    pub async fn bsky_get_profile(
+        &mut self,
+        actor: &str,
+    ) -> Result<ProfileViewDetailed, BiskyError> {
+        self.client
+            .xrpc_get("app.bsky.actor.getProfile", Some(&[("actor", actor)]))
+            .await
+    }
+
+    ///app.bsky.actor.getPreferences
+    pub async fn bsky_get_preferences(&mut self) -> Result<Preferences, BiskyError> {
+        self.client
+            .xrpc_get("app.bsky.actor.getPreferences", None)
+            .await
+    }
+
+    ///app.bsky.labeler.getServices
+    ///
+    /// Fetches the detailed views, which include the labelers' label definitions
+    pub async fn bsky_get_labeler_services(
+        &mut self,
+        dids: &[&str],
+    ) -> Result<Vec<LabelerViewDetailed>, BiskyError> {
+        let mut query = dids.iter().map(|did| ("dids", *did)).collect::<Vec<_>>();
+        query.push(("detailed", "true"));
+
+        self.client
+            .xrpc_get::<GetServicesOutput<LabelerViewDetailed>>(
+                "app.bsky.labeler.getServices",
+                Some(&query),
+            )
+            .await
+            .map(|o| o.views)
+    }
+
+    ///app.bsky.actor.putPreferences
+    pub async fn bsky_put_preferences(
+        &mut self,
+        preferences: &Preferences,
+    ) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.actor.putPreferences", preferences)
+            .await
+    }
+
+    ///app.bsky.graph.muteActor
+    pub async fn bsky_mute_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.muteActor", &MuteActor { actor })
+            .await
+    }
+
+    ///app.bsky.graph.unmuteActor
+    pub async fn bsky_unmute_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.unmuteActor", &MuteActor { actor })
+            .await
+    }
+
+    ///app.bsky.graph.muteThread
+    pub async fn bsky_mute_thread(&mut self, root: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.muteThread", &MuteThread { root })
+            .await
+    }
+
+    ///app.bsky.graph.unmuteThread
+    pub async fn bsky_unmute_thread(&mut self, root: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.unmuteThread", &MuteThread { root })
+            .await
+    }
+
+    ///app.bsky.graph.getList, a single page of members along with the list itself
+    pub async fn bsky_get_list(
+        &mut self,
+        list: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<GetListOutput, BiskyError> {
+        let limit = std::cmp::min(limit, 100).to_string();
+        let mut query = Vec::from([("list", list), ("limit", limit.as_str())]);
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor));
+        }
+
+        self.client
+            .xrpc_get("app.bsky.graph.getList", Some(&query))
+            .await
+    }
+
+    ///app.bsky.graph.getList, paging through the members only
+    pub async fn bsky_get_list_items(
+        &mut self,
+        list: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<ListItemView>, Option<String>), BiskyError> {
+        let query = Vec::from([("list", list)]);
+
+        self.bsky_paginate(
+            "app.bsky.graph.getList",
+            &query,
+            limit,
+            cursor,
+            |o: GetListOutput| (o.items, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.graph.getLists
+    pub async fn bsky_get_lists(
+        &mut self,
+        actor: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<ListView>, Option<String>), BiskyError> {
+        let query = Vec::from([("actor", actor)]);
+
+        self.bsky_paginate(
+            "app.bsky.graph.getLists",
+            &query,
+            limit,
+            cursor,
+            |o: GetListsOutput| (o.lists, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.graph.getListMutes
+    pub async fn bsky_get_list_mutes(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<ListView>, Option<String>), BiskyError> {
+        self.bsky_paginate(
+            "app.bsky.graph.getListMutes",
+            &[],
+            limit,
+            cursor,
+            |o: GetListMutesOutput| (o.lists, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.graph.getListBlocks
+    pub async fn bsky_get_list_blocks(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<ListView>, Option<String>), BiskyError> {
+        self.bsky_paginate(
+            "app.bsky.graph.getListBlocks",
+            &[],
+            limit,
+            cursor,
+            |o: GetListBlocksOutput| (o.lists, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.graph.muteActorList
+    pub async fn bsky_mute_actor_list(&mut self, list: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.muteActorList", &MuteActorList { list })
+            .await
+    }
+
+    ///app.bsky.graph.unmuteActorList
+    pub async fn bsky_unmute_actor_list(&mut self, list: &str) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.graph.unmuteActorList", &MuteActorList { list })
+            .await
+    }
+
+    ///app.bsky.graph.getStarterPack
+    pub async fn bsky_get_starter_pack(
+        &mut self,
+        starter_pack: &str,
+    ) -> Result<StarterPackView, BiskyError> {
+        self.client
+            .xrpc_get::<GetStarterPackOutput>(
+                "app.bsky.graph.getStarterPack",
+                Some(&[("starterPack", starter_pack)]),
+            )
+            .await
+            .map(|o| o.starter_pack)
+    }
+
+    ///app.bsky.graph.getActorStarterPacks
+    pub async fn bsky_get_actor_starter_packs(
+        &mut self,
+        actor: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<StarterPackViewBasic>, Option<String>), BiskyError> {
+        let query = Vec::from([("actor", actor)]);
+
+        self.bsky_paginate(
+            "app.bsky.graph.getActorStarterPacks",
+            &query,
+            limit,
+            cursor,
+            |o: GetActorStarterPacksOutput| (o.starter_packs, o.cursor),
+        )
+        .await
+    }
+
+    /// Turn a link to a starter pack into its AT URI. Takes `go.bsky.app` short links as well
+    /// as `bsky.app/start/...` and `bsky.app/starter-pack/...` links; a handle in the link is
+    /// resolved to a DID
+    pub async fn resolve_starter_pack_url(&mut self, url: &str) -> Result<AtUri, BiskyError> {
+        let invalid_url = || BiskyError::InvalidUrl(url.to_string());
+        let mut link = Url::parse(url).map_err(|_| invalid_url())?;
+
+        if link.host_str() == Some("go.bsky.app") {
+            link = follow_short_link(link).await?;
+        }
+
+        let segments = link
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let [_, actor, rkey] = segments.as_slice() else {
+            return Err(invalid_url());
+        };
+        if !matches!(segments[0], "start" | "starter-pack") {
+            return Err(invalid_url());
+        }
+
+        let did = match actor.starts_with("did:") {
+            true => actor.to_string(),
+            false => self.resolve_handle(actor).await?,
+        };
+        Ok(AtUri::new(&did, "app.bsky.graph.starterpack", rkey))
+    }
+
     /// Get the user's notification count. Can take a date to mark them as seen
     pub async fn bsky_get_notification_count(
         &mut self,
@@ -61,24 +360,51 @@ impl Bluesky {
     }
 
     pub async fn bsky_list_notifications<D: DeserializeOwned + std::fmt::Debug>(
+        &mut self,
+        limit: usize,
+        seen_at: Option<&str>,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<Notification<D>>, Option<String>), BiskyError> {
+        self.bsky_list_notifications_filtered(limit, &[], None, seen_at, cursor)
+            .await
+    }
+
+    ///app.bsky.notification.listNotifications
+    ///
+    /// Only notifications with one of `reasons` are listed, or all of them if it is empty.
+    /// `priority` limits them to accounts you follow, or overrides your priority preference.
+    ///
+    /// Pages are followed until `limit` notifications are listed or a page comes back empty or
+    /// without a cursor. The cursor returned is the last one seen, or None if the last page had
+    /// none
+    pub async fn bsky_list_notifications_filtered<D: DeserializeOwned + std::fmt::Debug>(
         &mut self,
         mut limit: usize,
+        reasons: &[NotificationReason],
+        priority: Option<bool>,
         seen_at: Option<&str>,
         cursor: Option<&str>,
     ) -> Result<(Vec<Notification<D>>, Option<String>), BiskyError> {
         let mut notifications = Vec::new();
-        let mut response_cursor = None;
+        let mut cursor = cursor.map(str::to_string);
+        let priority = priority.map(|priority| priority.to_string());
 
         while limit > 0 {
             let query_limit = std::cmp::min(limit, 100).to_string();
-            let mut query = Vec::from([("limit", query_limit.as_ref())]);
+            let mut query = Vec::from([("limit", query_limit.as_str())]);
 
-            if let Some(cursor) = cursor {
+            if let Some(cursor) = cursor.as_ref() {
                 query.push(("cursor", cursor));
             }
             if let Some(seen_at) = seen_at {
                 query.push(("seenAt", seen_at));
             }
+            for reason in reasons {
+                query.push(("reasons", reason.as_str()));
+            }
+            if let Some(priority) = priority.as_ref() {
+                query.push(("priority", priority));
+            }
 
             let mut response = self
                 .client
@@ -93,13 +419,19 @@ impl Bluesky {
                 break;
             }
 
-            limit -= response.notifications.len();
-
-            response_cursor = response.cursor.take();
+            limit = limit.saturating_sub(response.notifications.len());
             notifications.append(&mut response.notifications);
+
+            match response.cursor {
+                Some(response_cursor) => cursor = Some(response_cursor),
+                None => {
+                    cursor = None;
+                    break;
+                }
+            }
         }
 
-        Ok((notifications, response_cursor))
+        Ok((notifications, cursor))
     }
 
     pub async fn bsky_update_seen(&mut self, seen_at: DateTime<Utc>) -> Result<(), BiskyError> {
@@ -108,6 +440,66 @@ impl Bluesky {
             .await
     }
 
+    ///app.bsky.notification.putPreferences
+    pub async fn bsky_put_notification_preferences(
+        &mut self,
+        priority: bool,
+    ) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response(
+                "app.bsky.notification.putPreferences",
+                &PutPreferences { priority },
+            )
+            .await
+    }
+
+    ///app.bsky.notification.getPreferences
+    pub async fn bsky_get_notification_preferences(
+        &mut self,
+    ) -> Result<NotificationPreferences, BiskyError> {
+        self.client
+            .xrpc_get::<GetNotificationPreferencesOutput>(
+                "app.bsky.notification.getPreferences",
+                None,
+            )
+            .await
+            .map(|o| o.preferences)
+    }
+
+    ///app.bsky.notification.putPreferencesV2
+    pub async fn bsky_put_notification_preferences_v2(
+        &mut self,
+        preferences: &PutPreferencesV2,
+    ) -> Result<NotificationPreferences, BiskyError> {
+        self.client
+            .xrpc_post::<_, PutPreferencesV2Output>(
+                "app.bsky.notification.putPreferencesV2",
+                preferences,
+            )
+            .await
+            .map(|o| o.preferences)
+    }
+
+    ///app.bsky.notification.registerPush
+    pub async fn bsky_register_push(&mut self, push: &RegisterPush) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.notification.registerPush", push)
+            .await
+    }
+
+    ///app.bsky.notification.unregisterPush
+    pub async fn bsky_unregister_push(
+        &mut self,
+        push: &UnregisterPush<'_>,
+    ) -> Result<(), BiskyError> {
+        self.client
+            .xrpc_post_no_response("app.bsky.notification.unregisterPush", push)
+            .await
+    }
+
+    /// Stream notifications after walking every current page, see
+    /// `Bluesky::bsky_list_notifications_filtered`. Fails with `StreamError::NoCursor` if the
+    /// last page had no cursor to continue from
     pub async fn bsky_stream_notifications<'a, D: DeserializeOwned + std::fmt::Debug>(
         &'a mut self,
         seen_at: Option<&'a str>,
@@ -239,12 +631,24 @@ impl Bluesky {
         Ok((followers, response_cursor))
     }
 
-    ///app.bsky.feed.getPostThread
+    ///app.bsky.feed.getPostThread. `depth` limits how many levels of replies and
+    ///`parent_height` how many ancestors are included
     pub async fn bsky_get_post_thread(
         &mut self,
         uri: &str,
+        depth: Option<usize>,
+        parent_height: Option<usize>,
     ) -> Result<ThreadViewPostEnum, BiskyError> {
-        let query = Vec::from([("uri", uri)]);
+        let depth = depth.map(|d| d.to_string());
+        let parent_height = parent_height.map(|p| p.to_string());
+        let mut query = Vec::from([("uri", uri)]);
+
+        if let Some(depth) = depth.as_ref() {
+            query.push(("depth", depth));
+        }
+        if let Some(parent_height) = parent_height.as_ref() {
+            query.push(("parentHeight", parent_height));
+        }
 
         let response = self
             .client
@@ -253,166 +657,3520 @@ impl Bluesky {
 
         Ok(response.thread)
     }
-}
-
-pub struct BlueskyMe<'a> {
-    client: &'a mut Bluesky,
-    username: String,
-}
 
-impl<'a> BlueskyMe<'a> {
-    /// Post a new Post to your skyline
-    pub async fn post(&mut self, post: Post) -> Result<CreateRecordOutput, BiskyError> {
-        self.client
-            .client
-            .repo_create_record(&self.username, "app.bsky.feed.post", &post)
+    /// Page through a cursor-paginated query, collecting up to `limit` items. `extract` splits
+    /// a response into its items and the cursor for the next page
+    async fn bsky_paginate<O: DeserializeOwned + std::fmt::Debug, T>(
+        &mut self,
+        path: &str,
+        query: &[(&str, &str)],
+        limit: usize,
+        cursor: Option<&str>,
+        extract: fn(O) -> (Vec<T>, Option<String>),
+    ) -> Result<(Vec<T>, Option<String>), BiskyError> {
+        self.bsky_paginate_via(path, query, limit, cursor, None, extract)
             .await
     }
-    /// Get the notifications for the user
-    ///app.bsky.notification.listNotifications#
-    pub async fn get_notification_count(
+
+    /// Like `bsky_paginate`, but through the service `proxy`, see `Client::xrpc_get_via`
+    async fn bsky_paginate_via<O: DeserializeOwned + std::fmt::Debug, T>(
         &mut self,
-        seen_at: Option<&str>,
-    ) -> Result<NotificationCount, BiskyError> {
-        self.client.bsky_get_notification_count(seen_at).await
+        path: &str,
+        query: &[(&str, &str)],
+        mut limit: usize,
+        cursor: Option<&str>,
+        proxy: Option<&str>,
+        extract: fn(O) -> (Vec<T>, Option<String>),
+    ) -> Result<(Vec<T>, Option<String>), BiskyError> {
+        let mut items = Vec::new();
+        let mut cursor = cursor.map(str::to_string);
+
+        while limit > 0 {
+            let query_limit = std::cmp::min(limit, 100).to_string();
+            let mut page_query = query.to_vec();
+            page_query.push(("limit", &query_limit));
+
+            if let Some(cursor) = cursor.as_ref() {
+                page_query.push(("cursor", cursor));
+            }
+
+            let (mut page, page_cursor) = extract(
+                self.client
+                    .xrpc_get_via::<O>(path, Some(&page_query), proxy)
+                    .await?,
+            );
+
+            if page.is_empty() {
+                // caller requested more records than are available
+                break;
+            }
+
+            limit = limit.saturating_sub(page.len());
+            items.append(&mut page);
+
+            cursor = page_cursor;
+            if cursor.is_none() {
+                // the server has nothing after this page
+                break;
+            }
+        }
+
+        Ok((items, cursor))
     }
-    /// Get the notifications for the user
-    ///app.bsky.notification.listNotifications#
-    pub async fn list_notifications(
+
+    ///app.bsky.feed.getTimeline
+    pub async fn bsky_get_timeline(
         &mut self,
+        algorithm: Option<&str>,
         limit: usize,
-    ) -> Result<Vec<Notification<NotificationRecord>>, BiskyError> {
-        self.client
-            .bsky_list_notifications(limit, None, None)
-            .await
-            .map(|l| l.0)
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        let mut query = Vec::new();
+
+        if let Some(algorithm) = algorithm {
+            query.push(("algorithm", algorithm));
+        }
+
+        self.bsky_paginate(
+            "app.bsky.feed.getTimeline",
+            &query,
+            limit,
+            cursor,
+            |o: GetTimelineOutput| (o.feed, o.cursor),
+        )
+        .await
     }
 
-    pub async fn stream_notifications(
+    ///app.bsky.feed.getAuthorFeed
+    pub async fn bsky_get_author_feed(
         &mut self,
-    ) -> Result<NotificationStream<Notification<NotificationRecord>>, StreamError> {
-        self.client.bsky_stream_notifications(None).await
+        actor: &str,
+        filter: AuthorFeedFilter,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        let query = Vec::from([("actor", actor), ("filter", filter.as_str())]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getAuthorFeed",
+            &query,
+            limit,
+            cursor,
+            |o: AuthorFeed| (o.feed, o.cursor),
+        )
+        .await
     }
-    /// Tell Bsky when the notifications were seen, marking them as old
-    pub async fn update_seen(&mut self) -> Result<(), BiskyError> {
-        self.client.bsky_update_seen(Utc::now()).await
+
+    ///app.bsky.feed.getFeed
+    pub async fn bsky_get_feed(
+        &mut self,
+        feed: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        let query = Vec::from([("feed", feed)]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getFeed",
+            &query,
+            limit,
+            cursor,
+            |o: GetFeedOutput| (o.feed, o.cursor),
+        )
+        .await
     }
 
-    /// Upload a Blob(Image) for use in a Bsky Post later
-    pub async fn upload_blob(
+    ///app.bsky.feed.getListFeed
+    pub async fn bsky_get_list_feed(
         &mut self,
-        blob: &[u8],
-        mime_type: &str,
-    ) -> Result<BlobOutput, BiskyError> {
-        self.client.client.repo_upload_blob(blob, mime_type).await
+        list: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        let query = Vec::from([("list", list)]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getListFeed",
+            &query,
+            limit,
+            cursor,
+            |o: GetListFeedOutput| (o.feed, o.cursor),
+        )
+        .await
     }
 
-    pub async fn get_post_thread(&mut self, uri: &str) -> Result<ThreadViewPostEnum, BiskyError> {
-        self.client.bsky_get_post_thread(uri).await
+    ///app.bsky.feed.getActorLikes
+    pub async fn bsky_get_actor_likes(
+        &mut self,
+        actor: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        let query = Vec::from([("actor", actor)]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getActorLikes",
+            &query,
+            limit,
+            cursor,
+            |o: GetActorLikesOutput| (o.feed, o.cursor),
+        )
+        .await
     }
-}
-pub struct BlueskyUser<'a> {
-    client: &'a mut Bluesky,
-    username: String,
-}
 
-impl BlueskyUser<'_> {
-    pub async fn get_profile(&mut self) -> Result<ProfileViewDetailed, BiskyError> {
+    ///app.bsky.feed.getFeedGenerator
+    pub async fn bsky_get_feed_generator(
+        &mut self,
+        feed: &str,
+    ) -> Result<GetFeedGeneratorOutput, BiskyError> {
         self.client
-            .client
-            .xrpc_get(
-                "app.bsky.actor.getProfile",
-                Some(&[("actor", &self.username)]),
-            )
+            .xrpc_get("app.bsky.feed.getFeedGenerator", Some(&[("feed", feed)]))
             .await
     }
-    pub async fn get_likes(
+
+    ///app.bsky.feed.getFeedGenerators
+    pub async fn bsky_get_feed_generators(
+        &mut self,
+        feeds: &[&str],
+    ) -> Result<Vec<GeneratorView>, BiskyError> {
+        let query = feeds
+            .iter()
+            .map(|feed| ("feeds", *feed))
+            .collect::<Vec<_>>();
+
+        let response = self
+            .client
+            .xrpc_get::<GetFeedGeneratorsOutput>("app.bsky.feed.getFeedGenerators", Some(&query))
+            .await?;
+
+        Ok(response.feeds)
+    }
+
+    ///app.bsky.feed.getPosts. Any number of URIs can be given; they are requested in chunks of
+    ///the 25 the server accepts per call. Posts that can't be found are left out of the result
+    pub async fn bsky_get_posts(&mut self, uris: &[&str]) -> Result<Vec<PostView>, BiskyError> {
+        let mut posts = Vec::new();
+
+        for chunk in uris.chunks(25) {
+            let query = chunk.iter().map(|uri| ("uris", *uri)).collect::<Vec<_>>();
+
+            let mut response = self
+                .client
+                .xrpc_get::<GetPostsOutput>("app.bsky.feed.getPosts", Some(&query))
+                .await?;
+
+            posts.append(&mut response.posts);
+        }
+
+        Ok(posts)
+    }
+
+    ///app.bsky.feed.getRepostedBy
+    pub async fn bsky_get_reposted_by(
         &mut self,
         uri: &str,
         limit: usize,
         cursor: Option<&str>,
-    ) -> Result<Vec<GetLikesLike>, BiskyError> {
+    ) -> Result<(Vec<ProfileView>, Option<String>), BiskyError> {
+        let query = Vec::from([("uri", uri)]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getRepostedBy",
+            &query,
+            limit,
+            cursor,
+            |o: GetRepostedByOutput| (o.reposted_by, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.feed.getQuotes
+    pub async fn bsky_get_quotes(
+        &mut self,
+        uri: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<PostView>, Option<String>), BiskyError> {
+        let query = Vec::from([("uri", uri)]);
+
+        self.bsky_paginate(
+            "app.bsky.feed.getQuotes",
+            &query,
+            limit,
+            cursor,
+            |o: GetQuotesOutput| (o.posts, o.cursor),
+        )
+        .await
+    }
+
+    ///app.bsky.feed.searchPosts
+    pub async fn bsky_search_posts(
+        &mut self,
+        search: &SearchPosts,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<PostView>, Option<String>), BiskyError> {
+        self.bsky_paginate(
+            "app.bsky.feed.searchPosts",
+            &search.query(),
+            limit,
+            cursor,
+            |o: SearchPostsOutput| (o.posts, o.cursor),
+        )
+        .await
+    }
+
+    /// Page through the results of `app.bsky.feed.searchPosts` lazily, a page at a time
+    pub fn bsky_search_posts_pager(&mut self, search: SearchPosts) -> SearchPostsPager<'_> {
+        SearchPostsPager {
+            client: self,
+            search,
+            queue: VecDeque::new(),
+            cursor: None,
+            exhausted: false,
+        }
+    }
+
+    /// Watch `searches` for new posts, see `SearchWatchStream`. The stream picks up from the
+    /// state in `storage`; if there is none (see `Storage::is_not_found`), posts that already
+    /// match aren't emitted. Any other error reading the state is returned
+    pub async fn bsky_stream_searches<S: Storage<SearchWatchState>>(
+        &mut self,
+        searches: Vec<SearchPosts>,
+        storage: S,
+    ) -> Result<SearchWatchStream<'_, S>, StreamError> {
+        let state = match storage.get().await {
+            Ok(state) => Some(state),
+            Err(error) if storage.is_not_found(&error) => None,
+            Err(error) => return Err(BiskyError::StorageError(error.to_string()).into()),
+        };
+        let first_run = state.is_none();
+        let state = state.unwrap_or_default();
+
+        let mut stream = SearchWatchStream {
+            client: self,
+            searches,
+            storage,
+            seen: state.seen.iter().cloned().collect(),
+            state,
+            queue: VecDeque::new(),
+        };
+
+        if first_run {
+            for post in stream.poll(1).await? {
+                stream.remember(post.uri);
+            }
+            stream.save().await?;
+        }
+
+        Ok(stream)
+    }
+
+    ///app.bsky.actor.searchActors
+    pub async fn bsky_search_actors(
+        &mut self,
+        q: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<ProfileView>, Option<String>), BiskyError> {
+        let query = Vec::from([("q", q)]);
+
+        self.bsky_paginate(
+            "app.bsky.actor.searchActors",
+            &query,
+            limit,
+            cursor,
+            |o: SearchActorsOutput| (o.actors, o.cursor),
+        )
+        .await
+    }
+
+    /// Page through the results of `app.bsky.actor.searchActors` lazily, a page at a time
+    pub fn bsky_search_actors_pager(&mut self, q: &str) -> SearchActorsPager<'_> {
+        SearchActorsPager {
+            client: self,
+            q: q.to_string(),
+            queue: VecDeque::new(),
+            cursor: None,
+            exhausted: false,
+        }
+    }
+
+    ///app.bsky.actor.searchActorsTypeahead
+    pub async fn bsky_search_actors_typeahead(
+        &mut self,
+        q: &str,
+        limit: usize,
+    ) -> Result<Vec<ProfileViewBasic>, BiskyError> {
+        let limit = std::cmp::min(limit, 100).to_string();
+
         self.client
-            .bsky_get_likes(uri, limit, cursor)
+            .xrpc_get::<SearchActorsTypeaheadOutput>(
+                "app.bsky.actor.searchActorsTypeahead",
+                Some(&[("q", q), ("limit", &limit)]),
+            )
             .await
-            .map(|l| l.0)
+            .map(|o| o.actors)
     }
-    pub async fn get_follows(
+
+    /// The `did:web` the video service is addressed by in service auth tokens
+    fn video_service_did(&self) -> String {
+        let video_service = self.client.video_service();
+        format!("did:web:{}", video_service.host_str().unwrap_or_default())
+    }
+
+    ///app.bsky.video.getUploadLimits
+    pub async fn bsky_get_upload_limits(&mut self) -> Result<GetUploadLimitsOutput, BiskyError> {
+        let token = self
+            .client
+            .server_get_service_auth(
+                &self.video_service_did(),
+                Some("app.bsky.video.getUploadLimits"),
+                None,
+            )
+            .await?;
+
+        self.client
+            .service_get(
+                self.client.video_service(),
+                "app.bsky.video.getUploadLimits",
+                &[],
+                Some(&token),
+            )
+            .await
+    }
+
+    ///app.bsky.video.uploadVideo
+    ///
+    /// The video service stores the processed video in `did`'s repo, so the service auth token
+    /// is addressed to the PDS hosting it
+    pub async fn bsky_upload_video(
+        &mut self,
+        did: &str,
+        video: &[u8],
+        mime_type: &str,
+    ) -> Result<JobStatus, BiskyError> {
+        let repo = self.client.repo_describe_repo(did).await?;
+        let Some(pds) = repo.pds_endpoint().and_then(|pds| Url::parse(pds).ok()) else{
+            return Err(BiskyError::UnexpectedResponse(format!(
+                "DID document of {did} has no PDS"
+            )));
+        };
+
+        let token = self
+            .client
+            .server_get_service_auth(
+                &format!("did:web:{}", pds.host_str().unwrap_or_default()),
+                Some("com.atproto.repo.uploadBlob"),
+                Some(Utc::now() + chrono::Duration::minutes(30)),
+            )
+            .await?;
+
+        let extension = mime_type.rsplit('/').next().unwrap_or("mp4");
+        let name = format!("{}.{extension}", Utc::now().timestamp_millis());
+        let (status, text) = self
+            .client
+            .service_post_binary(
+                self.client.video_service(),
+                "app.bsky.video.uploadVideo",
+                &[("did", did), ("name", &name)],
+                video,
+                mime_type,
+                &token,
+            )
+            .await?;
+
+        // the service answers with the job status itself rather than the lexicon's output, and
+        // does so too when the video was uploaded before
+        if let Ok(output) = serde_json::from_str::<UploadVideoOutput>(&text) {
+            return Ok(output.job_status);
+        }
+        if let Ok(job_status) = serde_json::from_str::<JobStatus>(&text) {
+            return Ok(job_status);
+        }
+        if !status.is_success() {
+            if let Ok(error) = serde_json::from_str::<ApiError>(&text) {
+                return Err(BiskyError::ApiError(error));
+            }
+        }
+        Err(BiskyError::UnexpectedResponse(text))
+    }
+
+    ///app.bsky.video.getJobStatus
+    pub async fn bsky_get_job_status(&mut self, job_id: &str) -> Result<JobStatus, BiskyError> {
+        self.client
+            .service_get::<GetJobStatusOutput>(
+                self.client.video_service(),
+                "app.bsky.video.getJobStatus",
+                &[("jobId", job_id)],
+                None,
+            )
+            .await
+            .map(|o| o.job_status)
+    }
+
+    ///chat.bsky.convo.listConvos
+    pub async fn chat_list_convos(
         &mut self,
         limit: usize,
         cursor: Option<&str>,
-    ) -> Result<Vec<ProfileView>, BiskyError> {
+    ) -> Result<(Vec<ConvoView>, Option<String>), BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.bsky_paginate_via(
+            "chat.bsky.convo.listConvos",
+            &[],
+            limit,
+            cursor,
+            Some(&proxy),
+            |o: ListConvosOutput| (o.convos, o.cursor),
+        )
+        .await
+    }
+
+    ///chat.bsky.convo.getConvo
+    pub async fn chat_get_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
         self.client
-            .bsky_get_follows(&self.username, limit, cursor)
+            .xrpc_get_via::<ConvoOutput>(
+                "chat.bsky.convo.getConvo",
+                Some(&[("convoId", convo_id)]),
+                Some(&proxy),
+            )
             .await
-            .map(|l| l.0)
+            .map(|o| o.convo)
     }
-    pub async fn get_followers(
+
+    ///chat.bsky.convo.getConvoForMembers
+    pub async fn chat_get_convo_for_members(
+        &mut self,
+        members: &[&str],
+    ) -> Result<ConvoView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        let query = members
+            .iter()
+            .map(|member| ("members", *member))
+            .collect::<Vec<_>>();
+        self.client
+            .xrpc_get_via::<ConvoOutput>(
+                "chat.bsky.convo.getConvoForMembers",
+                Some(&query),
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.convo)
+    }
+
+    ///chat.bsky.convo.getMessages
+    pub async fn chat_get_messages(
         &mut self,
+        convo_id: &str,
         limit: usize,
         cursor: Option<&str>,
-    ) -> Result<Vec<ProfileView>, BiskyError> {
+    ) -> Result<(Vec<Message>, Option<String>), BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.bsky_paginate_via(
+            "chat.bsky.convo.getMessages",
+            &[("convoId", convo_id)],
+            limit,
+            cursor,
+            Some(&proxy),
+            |o: GetMessagesOutput| (o.messages, o.cursor),
+        )
+        .await
+    }
+
+    ///chat.bsky.convo.sendMessage
+    pub async fn chat_send_message(
+        &mut self,
+        convo_id: &str,
+        message: &MessageInput,
+    ) -> Result<MessageView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
         self.client
-            .bsky_get_followers(&self.username, limit, cursor)
+            .xrpc_post_via(
+                "chat.bsky.convo.sendMessage",
+                &SendMessage { convo_id, message },
+                Some(&proxy),
+            )
             .await
-            .map(|l| l.0)
     }
 
-    pub async fn list_posts(&mut self) -> Result<Vec<Record<Post>>, BiskyError> {
+    ///chat.bsky.convo.sendMessageBatch
+    pub async fn chat_send_message_batch(
+        &mut self,
+        items: &[SendMessage<'_>],
+    ) -> Result<Vec<MessageView>, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
         self.client
-            .client
-            .repo_list_records(
-                &self.username,
-                "app.bsky.feed.post",
-                usize::MAX,
-                false,
-                None,
+            .xrpc_post_via::<_, SendMessageBatchOutput>(
+                "chat.bsky.convo.sendMessageBatch",
+                &SendMessageBatch { items },
+                Some(&proxy),
             )
             .await
-            .map(|l| l.0)
+            .map(|o| o.items)
     }
 
-    pub async fn stream_posts(&mut self) -> Result<RecordStream<Post>, StreamError> {
+    ///chat.bsky.convo.deleteMessageForSelf
+    pub async fn chat_delete_message_for_self(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+    ) -> Result<DeletedMessageView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
         self.client
-            .client
-            .repo_stream_records(&self.username, "app.bsky.feed.post")
+            .xrpc_post_via(
+                "chat.bsky.convo.deleteMessageForSelf",
+                &MessageRef {
+                    convo_id,
+                    message_id,
+                },
+                Some(&proxy),
+            )
+            .await
+    }
+
+    ///chat.bsky.convo.updateRead
+    pub async fn chat_update_read(
+        &mut self,
+        convo_id: &str,
+        message_id: Option<&str>,
+    ) -> Result<ConvoView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.client
+            .xrpc_post_via::<_, ConvoOutput>(
+                "chat.bsky.convo.updateRead",
+                &UpdateRead {
+                    convo_id,
+                    message_id,
+                },
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.convo)
+    }
+
+    ///chat.bsky.convo.muteConvo
+    pub async fn chat_mute_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.client
+            .xrpc_post_via::<_, ConvoOutput>(
+                "chat.bsky.convo.muteConvo",
+                &ConvoRef { convo_id },
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.convo)
+    }
+
+    ///chat.bsky.convo.unmuteConvo
+    pub async fn chat_unmute_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.client
+            .xrpc_post_via::<_, ConvoOutput>(
+                "chat.bsky.convo.unmuteConvo",
+                &ConvoRef { convo_id },
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.convo)
+    }
+
+    ///chat.bsky.convo.addReaction
+    pub async fn chat_add_reaction(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+        value: &str,
+    ) -> Result<MessageView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.client
+            .xrpc_post_via::<_, ReactionOutput>(
+                "chat.bsky.convo.addReaction",
+                &Reaction {
+                    convo_id,
+                    message_id,
+                    value,
+                },
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.message)
+    }
+
+    ///chat.bsky.convo.removeReaction
+    pub async fn chat_remove_reaction(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+        value: &str,
+    ) -> Result<MessageView, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        self.client
+            .xrpc_post_via::<_, ReactionOutput>(
+                "chat.bsky.convo.removeReaction",
+                &Reaction {
+                    convo_id,
+                    message_id,
+                    value,
+                },
+                Some(&proxy),
+            )
+            .await
+            .map(|o| o.message)
+    }
+
+    ///chat.bsky.convo.getLog
+    pub async fn chat_get_log(&mut self, cursor: Option<&str>) -> Result<GetLogOutput, BiskyError> {
+        let proxy = self.client.chat_proxy().to_string();
+        let query = cursor.map(|cursor| [("cursor", cursor)]);
+        self.client
+            .xrpc_get_via(
+                "chat.bsky.convo.getLog",
+                query.as_ref().map(|query| query.as_slice()),
+                Some(&proxy),
+            )
             .await
     }
+
+    /// Stream chat events, such as new messages, from now on. Without any chat history yet there
+    /// is no cursor, and the stream starts from the first event
+    pub async fn chat_stream_log(&mut self) -> Result<ChatLogStream<'_>, StreamError> {
+        let cursor = self.chat_get_log(None).await?.cursor;
+        Ok(ChatLogStream {
+            client: self,
+            queue: VecDeque::new(),
+            cursor,
+        })
+    }
 }
 
-pub struct NotificationStream<'a, D: DeserializeOwned> {
+pub struct BlueskyMe<'a> {
     client: &'a mut Bluesky,
-    limit: usize,
-    seen_at: Option<&'a str>,
-    queue: VecDeque<Notification<D>>,
-    cursor: String,
+    username: String,
 }
 
-impl<'a, D: DeserializeOwned + std::fmt::Debug> NotificationStream<'a, D> {
-    pub async fn next(&mut self) -> Result<Notification<D>, StreamError> {
-        if let Some(notification) = self.queue.pop_front() {
-            Ok(notification)
-        } else {
-            loop {
-                let (notifications, cursor) = self
-                    .client
-                    .bsky_list_notifications(self.limit, self.seen_at, Some(self.cursor.as_ref()))
-                    .await?;
+impl<'a> BlueskyMe<'a> {
+    /// Post a new Post to your skyline. The post is validated locally before it is sent
+    pub async fn post(&mut self, post: Post) -> Result<CreateRecordOutput, BiskyError> {
+        validate_post(&post)?;
+        self.client
+            .client
+            .repo_create_record(&self.username, "app.bsky.feed.post", &post)
+            .await
+    }
 
-                let mut notifications = VecDeque::from(notifications);
-                if let Some(first_notification) = notifications.pop_front() {
-                    if let Some(cursor) = cursor {
-                        self.cursor = cursor;
-                    } else {
-                        return Err(StreamError::NoCursor);
-                    }
+    /// Post with a threadgate limiting who can reply; an empty `allow` lets no one reply. Both
+    /// are written at once so that no one can reply in between. Returns the post and the URI of
+    /// its threadgate
+    pub async fn post_with_threadgate(
+        &mut self,
+        post: Post,
+        allow: Vec<ThreadgateRule>,
+    ) -> Result<(CreateRecordOutput, AtUri), BiskyError> {
+        validate_post(&post)?;
 
-                    self.queue.append(&mut notifications);
-                    return Ok(first_notification);
-                } else {
-                    tokio::time::sleep(Duration::from_secs(15)).await;
+        let rkey = new_tid();
+        let post_uri = AtUri::new(&self.username, "app.bsky.feed.post", &rkey);
+        let threadgate = Threadgate {
+            post: post_uri.to_string(),
+            allow: Some(allow),
+            created_at: Utc::now(),
+            hidden_replies: None,
+            extra: HashMap::new(),
+        };
+        let writes = [
+            ApplyWrite::Create {
+                collection: "app.bsky.feed.post".to_string(),
+                rkey: Some(rkey.clone()),
+                value: serde_json::to_value(&post)?,
+            },
+            ApplyWrite::Create {
+                collection: "app.bsky.feed.threadgate".to_string(),
+                rkey: Some(rkey.clone()),
+                value: serde_json::to_value(&threadgate)?,
+            },
+        ];
+
+        let output = self
+            .client
+            .client
+            .repo_apply_writes(&self.username, &writes)
+            .await?;
+        let Some(ApplyWriteResult::Create { uri, cid }) = output.results.into_iter().next() else{
+            return Err(BiskyError::UnexpectedResponse(
+                "applyWrites did not return the created post".to_string(),
+            ));
+        };
+
+        Ok((
+            CreateRecordOutput { cid, uri },
+            AtUri::new(&self.username, "app.bsky.feed.threadgate", &rkey),
+        ))
+    }
+
+    /// Get the notifications for the user
+    ///app.bsky.notification.listNotifications#
+    pub async fn get_notification_count(
+        &mut self,
+        seen_at: Option<&str>,
+    ) -> Result<NotificationCount, BiskyError> {
+        self.client.bsky_get_notification_count(seen_at).await
+    }
+    /// Get the notifications for the user
+    ///app.bsky.notification.listNotifications#
+    pub async fn list_notifications(
+        &mut self,
+        limit: usize,
+    ) -> Result<Vec<Notification<NotificationRecord>>, BiskyError> {
+        self.client
+            .bsky_list_notifications(limit, None, None)
+            .await
+            .map(|l| l.0)
+    }
+
+    /// Get the notifications for the user with one of `reasons`, see
+    /// `Bluesky::bsky_list_notifications_filtered`
+    pub async fn list_notifications_filtered(
+        &mut self,
+        limit: usize,
+        reasons: &[NotificationReason],
+        priority: Option<bool>,
+    ) -> Result<Vec<Notification<NotificationRecord>>, BiskyError> {
+        self.client
+            .bsky_list_notifications_filtered(limit, reasons, priority, None, None)
+            .await
+            .map(|l| l.0)
+    }
+
+    /// List notifications grouped by reason and subject, see `group_notifications`, with the
+    /// subject posts fetched in batches
+    pub async fn list_notification_groups(
+        &mut self,
+        limit: usize,
+        window: chrono::Duration,
+    ) -> Result<Vec<NotificationGroup>, BiskyError> {
+        let notifications = self.list_notifications(limit).await?;
+        let mut groups = group_notifications(notifications, window);
+
+        let mut uris = Vec::new();
+        for group in &groups {
+            if let Some(subject) = &group.reason_subject {
+                if subject.contains("/app.bsky.feed.post/") && !uris.contains(&subject.as_str()) {
+                    uris.push(subject.as_str());
                 }
             }
         }
+        let posts = self
+            .client
+            .bsky_get_posts(&uris)
+            .await?
+            .into_iter()
+            .map(|post| (post.uri.clone(), Arc::new(post)))
+            .collect::<HashMap<_, _>>();
+
+        for group in &mut groups {
+            group.subject = group
+                .reason_subject
+                .as_ref()
+                .and_then(|subject| posts.get(subject).cloned());
+        }
+
+        Ok(groups)
+    }
+
+    pub async fn stream_notifications(
+        &mut self,
+    ) -> Result<NotificationStream<'_, NotificationRecord>, StreamError> {
+        self.client.bsky_stream_notifications(None).await
+    }
+    /// Tell Bsky when the notifications were seen, marking them as old
+    pub async fn update_seen(&mut self) -> Result<(), BiskyError> {
+        self.client.bsky_update_seen(Utc::now()).await
+    }
+
+    /// Only list notifications from accounts you follow, or go back to listing all of them
+    pub async fn set_notification_priority(&mut self, priority: bool) -> Result<(), BiskyError> {
+        self.client
+            .bsky_put_notification_preferences(priority)
+            .await
+    }
+
+    /// Which notifications you get, by reason, in the list and as push notifications
+    pub async fn get_notification_preferences(
+        &mut self,
+    ) -> Result<NotificationPreferences, BiskyError> {
+        self.client.bsky_get_notification_preferences().await
+    }
+
+    /// Change some of your notification preferences, leaving the ones not set as they are.
+    /// Returns all of them as they are now
+    pub async fn update_notification_preferences(
+        &mut self,
+        preferences: &PutPreferencesV2,
+    ) -> Result<NotificationPreferences, BiskyError> {
+        self.client
+            .bsky_put_notification_preferences_v2(preferences)
+            .await
+    }
+
+    /// Send push notifications for this account to a device
+    pub async fn register_push(&mut self, push: &RegisterPush) -> Result<(), BiskyError> {
+        self.client.bsky_register_push(push).await
+    }
+
+    /// Stop sending push notifications to a device registered with `register_push`
+    pub async fn unregister_push(&mut self, push: &RegisterPush) -> Result<(), BiskyError> {
+        self.client
+            .bsky_unregister_push(&UnregisterPush {
+                service_did: &push.service_did,
+                token: &push.token,
+                platform: push.platform,
+                app_id: &push.app_id,
+            })
+            .await
+    }
+
+    /// List your direct message conversations, most recently active first
+    pub async fn list_convos(&mut self, limit: usize) -> Result<Vec<ConvoView>, BiskyError> {
+        self.client.chat_list_convos(limit, None).await.map(|l| l.0)
+    }
+
+    pub async fn get_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        self.client.chat_get_convo(convo_id).await
+    }
+
+    /// The conversation between you and `members`, which is started if there isn't one yet
+    pub async fn get_convo_for_members(
+        &mut self,
+        members: &[&str],
+    ) -> Result<ConvoView, BiskyError> {
+        self.client.chat_get_convo_for_members(members).await
+    }
+
+    /// The messages of a conversation, newest first
+    pub async fn get_messages(
+        &mut self,
+        convo_id: &str,
+        limit: usize,
+    ) -> Result<Vec<Message>, BiskyError> {
+        self.client
+            .chat_get_messages(convo_id, limit, None)
+            .await
+            .map(|l| l.0)
+    }
+
+    /// Send a direct message, such as `MessageInput::new("hello")`
+    pub async fn send_message(
+        &mut self,
+        convo_id: &str,
+        message: &MessageInput,
+    ) -> Result<MessageView, BiskyError> {
+        validate_message(message)?;
+        self.client.chat_send_message(convo_id, message).await
+    }
+
+    /// Send several direct messages, possibly to different conversations, at once
+    pub async fn send_message_batch(
+        &mut self,
+        items: &[SendMessage<'_>],
+    ) -> Result<Vec<MessageView>, BiskyError> {
+        for item in items {
+            validate_message(item.message)?;
+        }
+        self.client.chat_send_message_batch(items).await
+    }
+
+    /// Delete a message for you only, the other members still see it
+    pub async fn delete_message_for_self(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+    ) -> Result<DeletedMessageView, BiskyError> {
+        self.client
+            .chat_delete_message_for_self(convo_id, message_id)
+            .await
+    }
+
+    /// Mark the messages of a conversation as read, up to `message_id` or all of them
+    pub async fn update_read(
+        &mut self,
+        convo_id: &str,
+        message_id: Option<&str>,
+    ) -> Result<ConvoView, BiskyError> {
+        self.client.chat_update_read(convo_id, message_id).await
+    }
+
+    pub async fn mute_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        self.client.chat_mute_convo(convo_id).await
+    }
+
+    pub async fn unmute_convo(&mut self, convo_id: &str) -> Result<ConvoView, BiskyError> {
+        self.client.chat_unmute_convo(convo_id).await
+    }
+
+    /// React to a message with a single emoji
+    pub async fn add_reaction(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+        value: &str,
+    ) -> Result<MessageView, BiskyError> {
+        self.client
+            .chat_add_reaction(convo_id, message_id, value)
+            .await
+    }
+
+    pub async fn remove_reaction(
+        &mut self,
+        convo_id: &str,
+        message_id: &str,
+        value: &str,
+    ) -> Result<MessageView, BiskyError> {
+        self.client
+            .chat_remove_reaction(convo_id, message_id, value)
+            .await
+    }
+
+    /// Stream chat events, such as messages sent to you, see `ChatLogStream`
+    pub async fn stream_chat_log(&mut self) -> Result<ChatLogStream<'_>, StreamError> {
+        self.client.chat_stream_log().await
+    }
+
+    /// Upload a Blob(Image) for use in a Bsky Post later
+    pub async fn upload_blob(
+        &mut self,
+        blob: &[u8],
+        mime_type: &str,
+    ) -> Result<BlobOutput, BiskyError> {
+        self.client.client.repo_upload_blob(blob, mime_type).await
+    }
+
+    /// Upload an image for use in a Bsky Post later. The image is stripped of metadata and
+    /// scaled down and recompressed as needed to fit the blob size limit, see `prepare_image`
+    pub async fn upload_image(&mut self, image: &[u8], alt: &str) -> Result<Image, BiskyError> {
+        let prepared = prepare_image(image, MAX_IMAGE_BLOB_SIZE)?;
+        let blob_output = self.upload_blob(&prepared.data, prepared.mime_type).await?;
+
+        Ok(Image {
+            image: blob_output.blob,
+            alt: alt.to_string(),
+            aspect_ratio: Some(prepared.aspect_ratio),
+        })
+    }
+
+    /// Read an image file and upload it like `upload_image`
+    pub async fn upload_image_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        alt: &str,
+    ) -> Result<Image, BiskyError> {
+        let image = tokio::fs::read(path).await?;
+        self.upload_image(&image, alt).await
+    }
+
+    /// Upload a video through the video service and wait for it to be processed. Captions are
+    /// given as pairs of a language code and a WebVTT file. The aspect ratio isn't read from the
+    /// video, so it should be passed in for the video to be laid out before it loads. Processing
+    /// that takes longer than ten minutes is reported as `VideoProcessingFailed`
+    pub async fn upload_video(
+        &mut self,
+        video: &[u8],
+        mime_type: &str,
+        alt: Option<&str>,
+        aspect_ratio: Option<AspectRatio>,
+        captions: &[(&str, &[u8])],
+    ) -> Result<Video, BiskyError> {
+        if video.len() > MAX_VIDEO_BLOB_SIZE {
+            return Err(ValidationError::BlobTooLarge {
+                size: video.len(),
+                max: MAX_VIDEO_BLOB_SIZE,
+            }
+            .into());
+        }
+        if captions.len() > MAX_CAPTIONS {
+            return Err(ValidationError::TooManyCaptions {
+                count: captions.len(),
+                max: MAX_CAPTIONS,
+            }
+            .into());
+        }
+        if let Some((_, caption)) = captions
+            .iter()
+            .find(|(_, caption)| caption.len() > MAX_CAPTION_BLOB_SIZE)
+        {
+            return Err(ValidationError::BlobTooLarge {
+                size: caption.len(),
+                max: MAX_CAPTION_BLOB_SIZE,
+            }
+            .into());
+        }
+
+        let limits = self.client.bsky_get_upload_limits().await?;
+        if !limits.can_upload {
+            return Err(BiskyError::VideoUploadRejected(
+                limits
+                    .message
+                    .or(limits.error)
+                    .unwrap_or_else(|| "not allowed to upload videos".to_string()),
+            ));
+        }
+        if limits
+            .remaining_daily_bytes
+            .is_some_and(|remaining| remaining < video.len() as u64)
+        {
+            return Err(BiskyError::VideoUploadRejected(
+                "daily upload limit reached".to_string(),
+            ));
+        }
+
+        let mut job_status = self
+            .client
+            .bsky_upload_video(&self.username, video, mime_type)
+            .await?;
+        let deadline = tokio::time::Instant::now() + VIDEO_JOB_MAX_WAIT;
+        let blob = loop {
+            if job_status.state == JobStatus::FAILED {
+                return Err(BiskyError::VideoProcessingFailed(
+                    job_status
+                        .error
+                        .or(job_status.message)
+                        .unwrap_or(job_status.state),
+                ));
+            }
+            if let Some(blob) = job_status.blob.take() {
+                break blob;
+            }
+            if job_status.state == JobStatus::COMPLETED {
+                return Err(BiskyError::VideoProcessingFailed(format!(
+                    "job {} completed without a video",
+                    job_status.job_id
+                )));
+            }
+            if tokio::time::Instant::now() + VIDEO_JOB_POLL_INTERVAL > deadline {
+                return Err(BiskyError::VideoProcessingFailed(format!(
+                    "job {} still {} after {} seconds",
+                    job_status.job_id,
+                    job_status.state,
+                    VIDEO_JOB_MAX_WAIT.as_secs()
+                )));
+            }
+
+            tokio::time::sleep(VIDEO_JOB_POLL_INTERVAL).await;
+            job_status = self.client.bsky_get_job_status(&job_status.job_id).await?;
+        };
+
+        let mut uploaded_captions = Vec::new();
+        for (lang, caption) in captions {
+            uploaded_captions.push(Caption {
+                lang: lang.to_string(),
+                file: self.upload_blob(caption, "text/vtt").await?.blob,
+            });
+        }
+
+        let video = Video {
+            video: blob,
+            captions: match uploaded_captions.is_empty() {
+                true => None,
+                false => Some(uploaded_captions),
+            },
+            alt: alt.map(str::to_string),
+            aspect_ratio,
+        };
+        validate_video(&video)?;
+        Ok(video)
+    }
+
+    /// Change your profile. The profile record is read, changed and written back only if it
+    /// hasn't been changed by anything else in the meantime; if it has, this fails with an
+    /// `InvalidSwap` `ApiError` and can be retried. Fields the update doesn't touch, including
+    /// ones this crate doesn't know about, are kept
+    pub async fn update_profile(
+        &mut self,
+        mut update: ProfileUpdate,
+    ) -> Result<StrongRef, BiskyError> {
+        let existing = match self
+            .client
+            .client
+            .repo_get_record::<Profile>(&self.username, "app.bsky.actor.profile", "self")
+            .await
+        {
+            Ok(record) => Some(record),
+            Err(BiskyError::ApiError(error)) if error.error == "RecordNotFound" => None,
+            Err(e) => return Err(e),
+        };
+        let swap_record = match &existing {
+            Some(record) => record.cid.clone().map(Some),
+            None => Some(None),
+        };
+        let mut profile = existing.map(|record| record.value).unwrap_or_default();
+
+        update.apply(&mut profile);
+        if let Some(avatar) = update.avatar.take() {
+            profile.avatar = match avatar {
+                Some(image) => Some(self.upload_prepared_image(&image, MAX_AVATAR_SIZE).await?),
+                None => None,
+            };
+        }
+        if let Some(banner) = update.banner.take() {
+            profile.banner = match banner {
+                Some(image) => Some(self.upload_prepared_image(&image, MAX_BANNER_SIZE).await?),
+                None => None,
+            };
+        }
+        profile.rust_type = Some("app.bsky.actor.profile".to_string());
+        validate_profile(&profile)?;
+
+        let output = self
+            .client
+            .client
+            .repo_put_record(
+                &self.username,
+                "app.bsky.actor.profile",
+                "self",
+                &profile,
+                swap_record.as_ref().map(|cid| cid.as_deref()),
+            )
+            .await?;
+        Ok(StrongRef {
+            uri: output.uri,
+            cid: output.cid,
+        })
+    }
+
+    async fn upload_prepared_image(
+        &mut self,
+        image: &[u8],
+        max_size: usize,
+    ) -> Result<Blob, BiskyError> {
+        let prepared = prepare_image(image, max_size)?;
+        Ok(self
+            .upload_blob(&prepared.data, prepared.mime_type)
+            .await?
+            .blob)
+    }
+
+    pub async fn get_preferences(&mut self) -> Result<Preferences, BiskyError> {
+        self.client.bsky_get_preferences().await
+    }
+
+    /// Load your moderation settings: label preferences, muted words and hidden posts, along
+    /// with the label definitions of the labelers you subscribe to.
+    ///
+    /// This doesn't change what the client asks for. Views only carry labels from the labelers
+    /// passed to `Bluesky::set_labelers`, so pass it `ModerationOptions::labeler_dids` as well,
+    /// or labels from labelers other than Bluesky's own never reach the options
+    pub async fn moderation_options(&mut self) -> Result<ModerationOptions, BiskyError> {
+        let preferences = self.get_preferences().await?;
+        let options = ModerationOptions::from_preferences(&self.username, &preferences, &[]);
+
+        let dids = options.labeler_dids();
+        let labelers = self
+            .client
+            .bsky_get_labeler_services(&dids.iter().map(String::as_str).collect::<Vec<_>>())
+            .await?;
+
+        Ok(ModerationOptions::from_preferences(
+            &self.username,
+            &preferences,
+            &labelers,
+        ))
+    }
+
+    /// Replace all of your preferences. Use `get_preferences` first to change only some of them
+    pub async fn put_preferences(&mut self, preferences: &Preferences) -> Result<(), BiskyError> {
+        self.client.bsky_put_preferences(preferences).await
+    }
+
+    /// Read your preferences, change them and write them back if the change did anything
+    async fn update_preferences<T>(
+        &mut self,
+        update: impl FnOnce(&mut Preferences) -> Result<T, serde_json::Error>,
+    ) -> Result<T, BiskyError> {
+        let mut preferences = self.get_preferences().await?;
+        let before = serde_json::to_value(&preferences)?;
+        let result = update(&mut preferences)?;
+        if serde_json::to_value(&preferences)? != before {
+            self.put_preferences(&preferences).await?;
+        }
+        Ok(result)
+    }
+
+    /// Mute a word, replacing how it is muted if it already is
+    pub async fn add_muted_word(&mut self, word: MutedWord) -> Result<(), BiskyError> {
+        self.update_preferences(|preferences| preferences.add_muted_word(word))
+            .await
+    }
+
+    /// Unmute a word. Returns whether it was muted
+    pub async fn remove_muted_word(&mut self, value: &str) -> Result<bool, BiskyError> {
+        self.update_preferences(|preferences| preferences.remove_muted_word(value))
+            .await
+    }
+
+    /// Pin a feed generator or list, saving it if it isn't saved
+    pub async fn pin_feed(&mut self, uri: &str) -> Result<(), BiskyError> {
+        self.update_preferences(|preferences| preferences.pin_feed(uri))
+            .await
+    }
+
+    /// Unpin a feed, leaving it saved. Returns whether it was pinned
+    pub async fn unpin_feed(&mut self, uri: &str) -> Result<bool, BiskyError> {
+        self.update_preferences(|preferences| preferences.unpin_feed(uri))
+            .await
+    }
+
+    /// Build a link card for `url` from the page's OpenGraph and Twitter card tags, uploading its
+    /// preview image as the thumbnail. The title falls back to the URL and the description to
+    /// nothing; a preview image that can't be downloaded or decoded leaves the card without a
+    /// thumbnail
+    pub async fn link_card(
+        &mut self,
+        fetcher: &LinkCardFetcher,
+        url: &str,
+    ) -> Result<ExternalObject, BiskyError> {
+        let parsed_url = Url::parse(url).map_err(|_| BiskyError::InvalidUrl(url.to_string()))?;
+        let metadata = fetcher.fetch_metadata(&parsed_url).await?;
+
+        let mut thumb = None;
+        if let Some(image_url) = &metadata.image {
+            let prepared = match fetcher.fetch_image(image_url).await {
+                Ok(image) => prepare_image(&image, MAX_EXTERNAL_THUMB_SIZE).ok(),
+                Err(_) => None,
+            };
+            if let Some(prepared) = prepared {
+                let blob_output = self.upload_blob(&prepared.data, prepared.mime_type).await?;
+                thumb = Some(blob_output.blob);
+            }
+        }
+
+        Ok(ExternalObject {
+            uri: url.to_string(),
+            title: metadata.title.unwrap_or_else(|| url.to_string()),
+            description: metadata.description.unwrap_or_default(),
+            thumb,
+        })
+    }
+
+    /// Fetch the thread around a post. `depth` limits how many levels of replies and
+    /// `parent_height` how many ancestors are included, or the server's defaults if `None`
+    pub async fn get_post_thread(
+        &mut self,
+        uri: &str,
+        depth: Option<usize>,
+        parent_height: Option<usize>,
+    ) -> Result<ThreadViewPostEnum, BiskyError> {
+        self.client
+            .bsky_get_post_thread(uri, depth, parent_height)
+            .await
+    }
+
+    /// Get the home timeline of the user
+    pub async fn get_timeline(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        self.client.bsky_get_timeline(None, limit, cursor).await
+    }
+
+    /// Create a record in your repo and return its URI
+    async fn create_record<S: serde::Serialize>(
+        &mut self,
+        collection: &str,
+        record: &S,
+    ) -> Result<AtUri, BiskyError> {
+        self.client
+            .client
+            .repo_create_record::<CreateRecordOutput, _>(&self.username, collection, record)
+            .await?
+            .uri
+            .parse()
+    }
+
+    /// The record key of `uri`, checking that it names one of your records in `collection`
+    fn own_rkey<'u>(&self, uri: &'u AtUri, collection: &str) -> Result<&'u str, BiskyError> {
+        let (Some(uri_collection), Some(rkey)) = (&uri.collection, &uri.rkey) else {
+            return Err(BiskyError::InvalidAtUri(format!(
+                "{uri} does not name a record"
+            )));
+        };
+        if uri_collection != collection {
+            return Err(BiskyError::InvalidAtUri(format!(
+                "{uri} is not a {collection} record"
+            )));
+        }
+        if uri.authority != self.username {
+            return Err(BiskyError::InvalidAtUri(format!(
+                "{uri} is not one of your records"
+            )));
+        }
+        Ok(rkey)
+    }
+
+    /// Delete one of your records, checking first that it is in `collection`
+    async fn delete_record(&mut self, uri: &AtUri, collection: &str) -> Result<(), BiskyError> {
+        let rkey = self.own_rkey(uri, collection)?;
+
+        self.client
+            .client
+            .repo_delete_record(&self.username, collection, rkey)
+            .await?;
+        Ok(())
+    }
+
+    /// Like a post. Returns the URI of the like record, which `unlike` takes
+    pub async fn like(&mut self, subject: impl Into<StrongRef>) -> Result<AtUri, BiskyError> {
+        let like = Like {
+            created_at: Utc::now(),
+            subject: subject.into(),
+        };
+        self.create_record("app.bsky.feed.like", &like).await
+    }
+
+    /// Delete a like record
+    pub async fn unlike(&mut self, like: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(like, "app.bsky.feed.like").await
+    }
+
+    /// Remove your like from a post, finding the like record through the post's viewer state
+    pub async fn unlike_post(&mut self, post: &PostView) -> Result<(), BiskyError> {
+        let Some(like) = post.viewer.as_ref().and_then(|v| v.like.as_ref()) else{
+            return Err(BiskyError::NothingToUndo(format!(
+                "{} is not liked",
+                post.uri
+            )));
+        };
+        self.unlike(&like.parse()?).await
+    }
+
+    /// Repost a post. Returns the URI of the repost record, which `unrepost` takes
+    pub async fn repost(&mut self, subject: impl Into<StrongRef>) -> Result<AtUri, BiskyError> {
+        let repost = Repost {
+            created_at: Utc::now(),
+            subject: subject.into(),
+        };
+        self.create_record("app.bsky.feed.repost", &repost).await
+    }
+
+    /// Delete a repost record
+    pub async fn unrepost(&mut self, repost: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(repost, "app.bsky.feed.repost").await
+    }
+
+    /// Undo your repost of a post, finding the repost record through the post's viewer state
+    pub async fn unrepost_post(&mut self, post: &PostView) -> Result<(), BiskyError> {
+        let Some(repost) = post.viewer.as_ref().and_then(|v| v.repost.as_ref()) else{
+            return Err(BiskyError::NothingToUndo(format!(
+                "{} is not reposted",
+                post.uri
+            )));
+        };
+        self.unrepost(&repost.parse()?).await
+    }
+
+    /// Follow an account by DID. Returns the URI of the follow record, which `unfollow` takes
+    pub async fn follow(&mut self, did: &str) -> Result<AtUri, BiskyError> {
+        let follow = Follow {
+            created_at: Utc::now(),
+            subject: did.to_string(),
+        };
+        self.create_record("app.bsky.graph.follow", &follow).await
+    }
+
+    /// Delete a follow record
+    pub async fn unfollow(&mut self, follow: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(follow, "app.bsky.graph.follow").await
+    }
+
+    /// Unfollow an account by DID or handle, finding the follow record through the viewer state
+    /// of their profile
+    pub async fn unfollow_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        let profile = self.client.bsky_get_profile(actor).await?;
+        let Some(follow) = profile.viewer.and_then(|v| v.following) else{
+            return Err(BiskyError::NothingToUndo(format!(
+                "{actor} is not followed"
+            )));
+        };
+        self.unfollow(&follow.parse()?).await
+    }
+
+    /// Block an account by DID. Returns the URI of the block record, which `unblock` takes
+    pub async fn block(&mut self, did: &str) -> Result<AtUri, BiskyError> {
+        let block = Block {
+            created_at: Utc::now(),
+            subject: did.to_string(),
+        };
+        self.create_record("app.bsky.graph.block", &block).await
+    }
+
+    /// Delete a block record
+    pub async fn unblock(&mut self, block: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(block, "app.bsky.graph.block").await
+    }
+
+    /// Unblock an account by DID or handle, finding the block record through the viewer state
+    /// of their profile
+    pub async fn unblock_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        let profile = self.client.bsky_get_profile(actor).await?;
+        let Some(block) = profile.viewer.and_then(|v| v.blocking) else{
+            return Err(BiskyError::NothingToUndo(format!("{actor} is not blocked")));
+        };
+        self.unblock(&block.parse()?).await
+    }
+
+    /// Create a list. Returns its URI, which the other list methods take
+    pub async fn create_list(&mut self, list: &List) -> Result<AtUri, BiskyError> {
+        self.create_record("app.bsky.graph.list", list).await
+    }
+
+    /// Change one of your lists. The list is written back only if it hasn't been changed by
+    /// anything else in the meantime
+    pub async fn update_list(
+        &mut self,
+        list: &AtUri,
+        update: impl FnOnce(&mut List),
+    ) -> Result<StrongRef, BiskyError> {
+        self.update_record(list, "app.bsky.graph.list", None, update)
+            .await
+    }
+
+    pub async fn rename_list(&mut self, list: &AtUri, name: &str) -> Result<StrongRef, BiskyError> {
+        self.update_list(list, |list| list.name = name.to_string())
+            .await
+    }
+
+    /// Delete one of your lists along with its membership records
+    pub async fn delete_list(&mut self, list: &AtUri) -> Result<(), BiskyError> {
+        let items = self.list_item_records(list, |_| true).await?;
+        self.delete_records("app.bsky.graph.listitem", &items)
+            .await?;
+        self.delete_record(list, "app.bsky.graph.list").await
+    }
+
+    /// Add accounts to one of your lists by DID, in as few writes as possible. Accounts already
+    /// on the list are skipped. Returns the URIs of the new membership records
+    pub async fn add_list_members(
+        &mut self,
+        list: &AtUri,
+        dids: &[&str],
+    ) -> Result<Vec<AtUri>, BiskyError> {
+        let existing = self
+            .list_item_records(list, |did| dids.contains(&did))
+            .await?
+            .into_iter()
+            .map(|(_, did)| did)
+            .collect::<HashSet<_>>();
+
+        let mut added = HashSet::new();
+        let writes = dids
+            .iter()
+            .filter(|did| !existing.contains(**did) && added.insert(**did))
+            .map(|did| ApplyWrite::Create {
+                collection: "app.bsky.graph.listitem".to_string(),
+                rkey: None,
+                value: ListItem {
+                    subject: did.to_string(),
+                    list: list.to_string(),
+                    created_at: Utc::now(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let mut uris = Vec::new();
+        for chunk in writes.chunks(MAX_APPLY_WRITES) {
+            let output = self
+                .client
+                .client
+                .repo_apply_writes(&self.username, chunk)
+                .await?;
+            for result in output.results {
+                if let ApplyWriteResult::Create { uri, .. } = result {
+                    uris.push(uri.parse()?);
+                }
+            }
+        }
+        Ok(uris)
+    }
+
+    /// Remove accounts from one of your lists by DID, in as few writes as possible. Returns how
+    /// many membership records were deleted
+    pub async fn remove_list_members(
+        &mut self,
+        list: &AtUri,
+        dids: &[&str],
+    ) -> Result<usize, BiskyError> {
+        let items = self
+            .list_item_records(list, |did| dids.contains(&did))
+            .await?;
+        self.delete_records("app.bsky.graph.listitem", &items)
+            .await?;
+        Ok(items.len())
+    }
+
+    /// The membership records of one of your lists whose subject DID `filter` accepts, as pairs
+    /// of record key and DID. They are read from your repo rather than the AppView, which leaves
+    /// out members whose accounts are deleted or taken down. Every listitem record in the repo is
+    /// read, whichever list it belongs to, so callers read them once per operation
+    async fn list_item_records(
+        &mut self,
+        list: &AtUri,
+        filter: impl Fn(&str) -> bool,
+    ) -> Result<Vec<(String, String)>, BiskyError> {
+        let list = list.to_string();
+        let (records, _) = self
+            .client
+            .client
+            .repo_list_records::<ListItem>(
+                &self.username,
+                "app.bsky.graph.listitem",
+                usize::MAX,
+                false,
+                None,
+            )
+            .await?;
+
+        let mut items = Vec::new();
+        for record in records {
+            if record.value.list == list && filter(&record.value.subject) {
+                let uri = record.uri.parse::<AtUri>()?;
+                items.push((uri.rkey.unwrap_or_default(), record.value.subject));
+            }
+        }
+        Ok(items)
+    }
+
+    /// Delete records from `collection` by record key, in as few writes as possible
+    async fn delete_records<T>(
+        &mut self,
+        collection: &str,
+        records: &[(String, T)],
+    ) -> Result<(), BiskyError> {
+        let writes = records
+            .iter()
+            .map(|(rkey, _)| ApplyWrite::<()>::Delete {
+                collection: collection.to_string(),
+                rkey: rkey.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        for chunk in writes.chunks(MAX_APPLY_WRITES) {
+            self.client
+                .client
+                .repo_apply_writes(&self.username, chunk)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Read one of your records, change it and write it back, only if it hasn't been changed by
+    /// anything else in the meantime. If the record doesn't exist, `missing` is changed and
+    /// written in its place when given, only if nothing else creates the record first
+    async fn update_record<T: DeserializeOwned + Serialize + std::fmt::Debug>(
+        &mut self,
+        uri: &AtUri,
+        collection: &str,
+        missing: Option<T>,
+        update: impl FnOnce(&mut T),
+    ) -> Result<StrongRef, BiskyError> {
+        let rkey = self.own_rkey(uri, collection)?;
+
+        let existing = self
+            .client
+            .client
+            .repo_get_record::<T>(&self.username, collection, rkey)
+            .await;
+        let (mut value, swap_record) = match (existing, missing) {
+            (Ok(record), _) => (record.value, record.cid.map(Some)),
+            (Err(BiskyError::ApiError(error)), Some(missing))
+                if error.error == "RecordNotFound" =>
+            {
+                (missing, Some(None))
+            }
+            (Err(e), _) => return Err(e),
+        };
+        update(&mut value);
+
+        let output = self
+            .client
+            .client
+            .repo_put_record(
+                &self.username,
+                collection,
+                rkey,
+                &value,
+                swap_record.as_ref().map(|cid| cid.as_deref()),
+            )
+            .await?;
+        Ok(StrongRef {
+            uri: output.uri,
+            cid: output.cid,
+        })
+    }
+
+    /// Create a starter pack: a reference list of `members` (DIDs) with the pack's name, and
+    /// the pack itself recommending that list and up to three `feeds`. Returns the URI of the
+    /// starter pack. If adding the members or writing the pack fails, the list is deleted again
+    pub async fn create_starter_pack(
+        &mut self,
+        name: &str,
+        description: Option<&str>,
+        members: &[&str],
+        feeds: &[&str],
+    ) -> Result<AtUri, BiskyError> {
+        if feeds.len() > MAX_STARTER_PACK_FEEDS {
+            return Err(ValidationError::TooManyStarterPackFeeds {
+                count: feeds.len(),
+                max: MAX_STARTER_PACK_FEEDS,
+            }
+            .into());
+        }
+
+        let list = self
+            .create_list(&List::new(name, List::REFERENCELIST))
+            .await?;
+        let starter_pack = StarterPack {
+            name: name.to_string(),
+            description: description.map(str::to_string),
+            description_facets: None,
+            list: list.to_string(),
+            feeds: match feeds.is_empty() {
+                true => None,
+                false => Some(
+                    feeds
+                        .iter()
+                        .map(|uri| StarterPackFeedItem {
+                            uri: uri.to_string(),
+                        })
+                        .collect(),
+                ),
+            },
+            created_at: Utc::now(),
+            extra: HashMap::new(),
+        };
+        let result = async {
+            self.add_list_members(&list, members).await?;
+            self.create_record("app.bsky.graph.starterpack", &starter_pack)
+                .await
+        }
+        .await;
+
+        if result.is_err() {
+            // Nothing refers to the list without the pack. Failing to delete it matters less
+            // than why the pack couldn't be made, so that error is the one returned
+            let _ = self.delete_list(&list).await;
+        }
+        result
+    }
+
+    /// Change one of your starter packs. Members are changed through its list, see
+    /// `add_list_members` and `remove_list_members`
+    pub async fn update_starter_pack(
+        &mut self,
+        starter_pack: &AtUri,
+        update: impl FnOnce(&mut StarterPack),
+    ) -> Result<StrongRef, BiskyError> {
+        self.update_record(starter_pack, "app.bsky.graph.starterpack", None, update)
+            .await
+    }
+
+    /// Delete one of your starter packs, along with its list if that is yours too
+    pub async fn delete_starter_pack(&mut self, starter_pack: &AtUri) -> Result<(), BiskyError> {
+        let Some(rkey) = &starter_pack.rkey else{
+            return Err(BiskyError::InvalidAtUri(format!(
+                "{starter_pack} does not name a record"
+            )));
+        };
+        let record = self
+            .client
+            .client
+            .repo_get_record::<StarterPack>(&self.username, "app.bsky.graph.starterpack", rkey)
+            .await?;
+
+        self.delete_record(starter_pack, "app.bsky.graph.starterpack")
+            .await?;
+
+        let list = record.value.list.parse::<AtUri>()?;
+        if list.authority == self.username {
+            self.delete_list(&list).await?;
+        }
+        Ok(())
+    }
+
+    /// Set who can reply to one of your posts, keeping any hidden replies. `None` lets everyone
+    /// reply and an empty list lets no one. Returns the URI of the threadgate
+    pub async fn set_threadgate(
+        &mut self,
+        post: &AtUri,
+        allow: Option<Vec<ThreadgateRule>>,
+    ) -> Result<AtUri, BiskyError> {
+        self.update_gate(
+            post,
+            "app.bsky.feed.threadgate",
+            |threadgate: &mut Threadgate| threadgate.allow = allow,
+        )
+        .await
+    }
+
+    /// Delete the threadgate of one of your posts, which also unhides its hidden replies
+    pub async fn remove_threadgate(&mut self, post: &AtUri) -> Result<(), BiskyError> {
+        self.delete_gate(post, "app.bsky.feed.threadgate").await
+    }
+
+    /// Hide a reply in the thread started by your post `root`. Returns the URI of the threadgate
+    pub async fn hide_reply(&mut self, root: &AtUri, reply: &str) -> Result<AtUri, BiskyError> {
+        self.update_gate(
+            root,
+            "app.bsky.feed.threadgate",
+            |threadgate: &mut Threadgate| {
+                let hidden_replies = threadgate.hidden_replies.get_or_insert_with(Vec::new);
+                if !hidden_replies.iter().any(|hidden| hidden == reply) {
+                    hidden_replies.push(reply.to_string());
+                }
+            },
+        )
+        .await
+    }
+
+    /// Unhide a reply in the thread started by your post `root`
+    pub async fn unhide_reply(&mut self, root: &AtUri, reply: &str) -> Result<AtUri, BiskyError> {
+        self.update_gate(
+            root,
+            "app.bsky.feed.threadgate",
+            |threadgate: &mut Threadgate| {
+                if let Some(hidden_replies) = &mut threadgate.hidden_replies {
+                    hidden_replies.retain(|hidden| hidden != reply);
+                }
+            },
+        )
+        .await
+    }
+
+    /// Allow or disallow quoting one of your posts. Returns the URI of the postgate
+    pub async fn set_quotes_disabled(
+        &mut self,
+        post: &AtUri,
+        disabled: bool,
+    ) -> Result<AtUri, BiskyError> {
+        self.update_gate(post, "app.bsky.feed.postgate", |postgate: &mut Postgate| {
+            let rules = postgate.embedding_rules.get_or_insert_with(Vec::new);
+            rules.retain(|rule| *rule != PostgateEmbeddingRule::Disable);
+            if disabled {
+                rules.push(PostgateEmbeddingRule::Disable);
+            }
+        })
+        .await
+    }
+
+    /// Detach one of your posts from a post quoting it, so that the quote no longer shows it.
+    /// Returns the URI of the postgate
+    pub async fn detach_quote(&mut self, post: &AtUri, quote: &str) -> Result<AtUri, BiskyError> {
+        self.update_gate(post, "app.bsky.feed.postgate", |postgate: &mut Postgate| {
+            let detached = postgate
+                .detached_embedding_uris
+                .get_or_insert_with(Vec::new);
+            if !detached.iter().any(|uri| uri == quote) {
+                detached.push(quote.to_string());
+            }
+        })
+        .await
+    }
+
+    /// Undo `detach_quote`
+    pub async fn reattach_quote(&mut self, post: &AtUri, quote: &str) -> Result<AtUri, BiskyError> {
+        self.update_gate(post, "app.bsky.feed.postgate", |postgate: &mut Postgate| {
+            if let Some(detached) = &mut postgate.detached_embedding_uris {
+                detached.retain(|uri| uri != quote);
+            }
+        })
+        .await
+    }
+
+    /// Delete the postgate of one of your posts
+    pub async fn remove_postgate(&mut self, post: &AtUri) -> Result<(), BiskyError> {
+        self.delete_gate(post, "app.bsky.feed.postgate").await
+    }
+
+    /// The gate in `collection` of one of your posts, which shares the post's record key
+    fn gate_uri(&self, post: &AtUri, collection: &str) -> Result<AtUri, BiskyError> {
+        let rkey = self.own_rkey(post, "app.bsky.feed.post")?;
+        Ok(AtUri::new(&self.username, collection, rkey))
+    }
+
+    /// Change the gate in `collection` of one of your posts, see `update_record`. A missing gate
+    /// is created
+    async fn update_gate<T: Gate>(
+        &mut self,
+        post: &AtUri,
+        collection: &str,
+        update: impl FnOnce(&mut T),
+    ) -> Result<AtUri, BiskyError> {
+        let gate = self.gate_uri(post, collection)?;
+        self.update_record(&gate, collection, Some(T::new(post)), update)
+            .await?;
+        Ok(gate)
+    }
+
+    async fn delete_gate(&mut self, post: &AtUri, collection: &str) -> Result<(), BiskyError> {
+        let gate = self.gate_uri(post, collection)?;
+        self.delete_record(&gate, collection).await
+    }
+
+    /// Mute everyone on a moderation list
+    pub async fn mute_list(&mut self, list: &str) -> Result<(), BiskyError> {
+        self.client.bsky_mute_actor_list(list).await
+    }
+
+    pub async fn unmute_list(&mut self, list: &str) -> Result<(), BiskyError> {
+        self.client.bsky_unmute_actor_list(list).await
+    }
+
+    /// Block everyone on a moderation list. Returns the URI of the list block record, which
+    /// `unblock_list` takes
+    pub async fn block_list(&mut self, list: &str) -> Result<AtUri, BiskyError> {
+        let block = ListBlock {
+            created_at: Utc::now(),
+            subject: list.to_string(),
+        };
+        self.create_record("app.bsky.graph.listblock", &block).await
+    }
+
+    /// Delete a list block record
+    pub async fn unblock_list(&mut self, list_block: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(list_block, "app.bsky.graph.listblock")
+            .await
+    }
+
+    /// Report an account (see `ReportSubject::account`) or a record, such as a post, to the
+    /// moderation service of your PDS, or to the labeler with the DID `labeler`
+    pub async fn report(
+        &mut self,
+        subject: impl Into<ReportSubject>,
+        reason_type: ReasonType,
+        reason: Option<&str>,
+        labeler: Option<&str>,
+    ) -> Result<CreateReportOutput, BiskyError> {
+        if let Some(reason) = reason {
+            validate_report_reason(reason)?;
+        }
+        self.client
+            .client
+            .moderation_create_report(&subject.into(), &reason_type, reason, labeler)
+            .await
+    }
+
+    /// Mute an account. Mutes are private and not stored in your repo
+    pub async fn mute_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        self.client.bsky_mute_actor(actor).await
+    }
+
+    pub async fn unmute_actor(&mut self, actor: &str) -> Result<(), BiskyError> {
+        self.client.bsky_unmute_actor(actor).await
+    }
+
+    /// Mute the thread starting at the post `root`
+    pub async fn mute_thread(&mut self, root: &str) -> Result<(), BiskyError> {
+        self.client.bsky_mute_thread(root).await
+    }
+
+    pub async fn unmute_thread(&mut self, root: &str) -> Result<(), BiskyError> {
+        self.client.bsky_unmute_thread(root).await
+    }
+
+    /// Delete one of your posts
+    pub async fn delete_post(&mut self, post: &AtUri) -> Result<(), BiskyError> {
+        self.delete_record(post, "app.bsky.feed.post").await
+    }
+
+    /// Get the posts the user has liked. The server only allows this for your own account
+    pub async fn get_actor_likes(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        self.client
+            .bsky_get_actor_likes(&self.username, limit, cursor)
+            .await
+    }
+}
+pub struct BlueskyUser<'a> {
+    client: &'a mut Bluesky,
+    username: String,
+}
+
+impl BlueskyUser<'_> {
+    pub async fn get_profile(&mut self) -> Result<ProfileViewDetailed, BiskyError> {
+        self.client.bsky_get_profile(&self.username).await
+    }
+    pub async fn get_likes(
+        &mut self,
+        uri: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Vec<GetLikesLike>, BiskyError> {
+        self.client
+            .bsky_get_likes(uri, limit, cursor)
+            .await
+            .map(|l| l.0)
+    }
+    pub async fn get_follows(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Vec<ProfileView>, BiskyError> {
+        self.client
+            .bsky_get_follows(&self.username, limit, cursor)
+            .await
+            .map(|l| l.0)
+    }
+    pub async fn get_followers(
+        &mut self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Vec<ProfileView>, BiskyError> {
+        self.client
+            .bsky_get_followers(&self.username, limit, cursor)
+            .await
+            .map(|l| l.0)
+    }
+
+    pub async fn get_author_feed(
+        &mut self,
+        filter: AuthorFeedFilter,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<FeedViewPost>, Option<String>), BiskyError> {
+        self.client
+            .bsky_get_author_feed(&self.username, filter, limit, cursor)
+            .await
+    }
+
+    pub async fn list_posts(&mut self) -> Result<Vec<Record<Post>>, BiskyError> {
+        self.client
+            .client
+            .repo_list_records(
+                &self.username,
+                "app.bsky.feed.post",
+                usize::MAX,
+                false,
+                None,
+            )
+            .await
+            .map(|l| l.0)
+    }
+
+    pub async fn stream_posts(&mut self) -> Result<RecordStream<'_, Post>, StreamError> {
+        self.client
+            .client
+            .repo_stream_records(&self.username, "app.bsky.feed.post")
+            .await
+    }
+}
+
+pub struct NotificationStream<'a, D: DeserializeOwned> {
+    client: &'a mut Bluesky,
+    limit: usize,
+    seen_at: Option<&'a str>,
+    queue: VecDeque<Notification<D>>,
+    cursor: String,
+}
+
+impl<'a, D: DeserializeOwned + std::fmt::Debug> NotificationStream<'a, D> {
+    pub async fn next(&mut self) -> Result<Notification<D>, StreamError> {
+        if let Some(notification) = self.queue.pop_front() {
+            Ok(notification)
+        } else {
+            loop {
+                let (notifications, cursor) = self
+                    .client
+                    .bsky_list_notifications(self.limit, self.seen_at, Some(self.cursor.as_ref()))
+                    .await?;
+
+                let mut notifications = VecDeque::from(notifications);
+                if let Some(first_notification) = notifications.pop_front() {
+                    if let Some(cursor) = cursor {
+                        self.cursor = cursor;
+                    } else {
+                        return Err(StreamError::NoCursor);
+                    }
+
+                    self.queue.append(&mut notifications);
+                    return Ok(first_notification);
+                } else {
+                    tokio::time::sleep(Duration::from_secs(15)).await;
+                }
+            }
+        }
+    }
+}
+
+impl NotificationStream<'_, NotificationRecord> {
+    /// The next notification that doesn't carry a post matching `filter`
+    pub async fn next_filtered(
+        &mut self,
+        filter: &KeywordFilter,
+    ) -> Result<Notification<NotificationRecord>, StreamError> {
+        loop {
+            let notification = self.next().await?;
+            if filter.allows_notification(&notification) {
+                return Ok(notification);
+            }
+        }
+    }
+}
+
+/// Chat events as they happen, see `Bluesky::chat_stream_log`. Events caused by you, such as
+/// the messages you send, are included too
+pub struct ChatLogStream<'a> {
+    client: &'a mut Bluesky,
+    queue: VecDeque<LogEvent>,
+    cursor: Option<String>,
+}
+
+impl ChatLogStream<'_> {
+    pub async fn next(&mut self) -> Result<LogEvent, StreamError> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Ok(event);
+            }
+
+            let output = self.client.chat_get_log(self.cursor.as_deref()).await?;
+            if output.cursor.is_some() {
+                self.cursor = output.cursor;
+            }
+
+            if output.logs.is_empty() {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            } else {
+                self.queue.extend(output.logs);
+            }
+        }
+    }
+}
+
+/// A record that gates a post and shares its record key
+trait Gate: DeserializeOwned + Serialize + std::fmt::Debug {
+    fn new(post: &AtUri) -> Self;
+}
+
+impl Gate for Threadgate {
+    fn new(post: &AtUri) -> Self {
+        Threadgate {
+            post: post.to_string(),
+            allow: None,
+            created_at: Utc::now(),
+            hidden_replies: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+impl Gate for Postgate {
+    fn new(post: &AtUri) -> Self {
+        Postgate {
+            post: post.to_string(),
+            created_at: Utc::now(),
+            detached_embedding_uris: None,
+            embedding_rules: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+/// The link a `go.bsky.app` short link points at
+async fn follow_short_link(link: Url) -> Result<Url, BiskyError> {
+    let response = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?
+        .get(link.clone())
+        .header("accept", "application/json")
+        .send()
+        .await?;
+
+    // the short link service answers JSON requests with the target, others with a redirect to
+    // it
+    let target = match response.status().is_redirection() {
+        true => response
+            .headers()
+            .get("location")
+            .and_then(|location| location.to_str().ok())
+            .map(str::to_string),
+        false => response
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?
+            .get("url")
+            .and_then(|target| target.as_str())
+            .map(str::to_string),
+    };
+    target
+        .and_then(|target| link.join(&target).ok())
+        .ok_or_else(|| BiskyError::InvalidUrl(link.to_string()))
+}
+
+/// What a `SearchWatchStream` has emitted, kept in a `Storage` so a restarted stream doesn't
+/// repeat itself
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SearchWatchState {
+    /// URIs of the most recently emitted posts, oldest first
+    pub seen: VecDeque<String>,
+}
+
+/// How many emitted URIs are remembered for deduplication
+const SEARCH_WATCH_SEEN_CAPACITY: usize = 10_000;
+/// How many pages of each search are read per poll before giving up on catching up
+const SEARCH_WATCH_MAX_PAGES: usize = 5;
+
+/// New posts matching any of a set of searches, polled from `app.bsky.feed.searchPosts`. Each
+/// post is emitted once, even if it matches several searches, and each poll's posts come out in
+/// the order they were indexed. The state is saved once the posts of a poll have all been
+/// emitted, so a stream stopped partway through a poll repeats that poll's posts when restarted
+pub struct SearchWatchStream<'a, S> {
+    client: &'a mut Bluesky,
+    searches: Vec<SearchPosts>,
+    storage: S,
+    state: SearchWatchState,
+    seen: HashSet<String>,
+    queue: VecDeque<PostView>,
+}
+
+impl<S: Storage<SearchWatchState>> SearchWatchStream<'_, S> {
+    pub async fn next(&mut self) -> Result<PostView, StreamError> {
+        loop {
+            if let Some(post) = self.queue.pop_front() {
+                self.remember(post.uri.clone());
+                if self.queue.is_empty() {
+                    self.save().await?;
+                }
+                return Ok(post);
+            }
+
+            let posts = self.poll(SEARCH_WATCH_MAX_PAGES).await?;
+            if posts.is_empty() {
+                tokio::time::sleep(Duration::from_secs(15)).await;
+            } else {
+                self.queue.extend(posts);
+            }
+        }
+    }
+
+    /// Fetch the latest posts of every search that haven't been seen, reading back until a seen
+    /// post turns up or `max_pages` pages have been read
+    async fn poll(&mut self, max_pages: usize) -> Result<Vec<PostView>, BiskyError> {
+        let mut posts = Vec::new();
+        let mut found = HashSet::new();
+
+        for search in &self.searches {
+            let mut search = search.clone();
+            search.sort = Some(SearchPostsSort::Latest);
+            let mut cursor = None;
+
+            for _ in 0..max_pages {
+                let (page, page_cursor) = self
+                    .client
+                    .bsky_search_posts(&search, 100, cursor.as_deref())
+                    .await?;
+
+                let caught_up = page.iter().any(|post| self.seen.contains(&post.uri));
+                for post in page {
+                    if !self.seen.contains(&post.uri) && found.insert(post.uri.clone()) {
+                        posts.push(post);
+                    }
+                }
+
+                if caught_up || page_cursor.is_none() {
+                    break;
+                }
+                cursor = page_cursor;
+            }
+        }
+
+        posts.sort_by(|a, b| a.indexed_at.cmp(&b.indexed_at).then(a.uri.cmp(&b.uri)));
+        Ok(posts)
+    }
+
+    fn remember(&mut self, uri: String) {
+        if self.seen.insert(uri.clone()) {
+            self.state.seen.push_back(uri);
+        }
+        while self.state.seen.len() > SEARCH_WATCH_SEEN_CAPACITY {
+            if let Some(uri) = self.state.seen.pop_front() {
+                self.seen.remove(&uri);
+            }
+        }
+    }
+
+    async fn save(&self) -> Result<(), BiskyError> {
+        self.storage
+            .set(Some(&self.state))
+            .await
+            .map_err(|e| BiskyError::StorageError(e.to_string()))
+    }
+}
+
+/// Search results fetched a page at a time as they are consumed, see
+/// `Bluesky::bsky_search_posts_pager`
+pub struct SearchPostsPager<'a> {
+    client: &'a mut Bluesky,
+    search: SearchPosts,
+    queue: VecDeque<PostView>,
+    cursor: Option<String>,
+    exhausted: bool,
+}
+
+impl SearchPostsPager<'_> {
+    /// The next result, or `None` once there are no more
+    pub async fn next(&mut self) -> Result<Option<PostView>, BiskyError> {
+        if self.queue.is_empty() && !self.exhausted {
+            let (posts, cursor) = self
+                .client
+                .bsky_search_posts(&self.search, 100, self.cursor.as_deref())
+                .await?;
+
+            self.exhausted = posts.is_empty() || cursor.is_none();
+            self.cursor = cursor;
+            self.queue.extend(posts);
+        }
+        Ok(self.queue.pop_front())
+    }
+}
+
+/// Search results fetched a page at a time as they are consumed, see
+/// `Bluesky::bsky_search_actors_pager`
+pub struct SearchActorsPager<'a> {
+    client: &'a mut Bluesky,
+    q: String,
+    queue: VecDeque<ProfileView>,
+    cursor: Option<String>,
+    exhausted: bool,
+}
+
+impl SearchActorsPager<'_> {
+    /// The next result, or `None` once there are no more
+    pub async fn next(&mut self) -> Result<Option<ProfileView>, BiskyError> {
+        if self.queue.is_empty() && !self.exhausted {
+            let (actors, cursor) = self
+                .client
+                .bsky_search_actors(&self.q, 100, self.cursor.as_deref())
+                .await?;
+
+            self.exhausted = actors.is_empty() || cursor.is_none();
+            self.cursor = cursor;
+            self.queue.extend(actors);
+        }
+        Ok(self.queue.pop_front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atproto::{ClientBuilder, UserSession};
+    use crate::lexicon::app::bsky::feed::SearchPostsBuilder;
+    use crate::test_server::{Request, Response, TestServer};
+
+    const DID: &str = "did:plc:alice";
+
+    fn blob(mime_type: &str, size: usize) -> serde_json::Value {
+        serde_json::json!({
+            "$type": "blob",
+            "ref": {"$link": "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"},
+            "mimeType": mime_type,
+            "size": size,
+        })
+    }
+
+    fn job_status(state: &str, blob: Option<serde_json::Value>) -> serde_json::Value {
+        serde_json::json!({
+            "jobId": "job1",
+            "did": DID,
+            "state": state,
+            "blob": blob,
+            "error": (state == JobStatus::FAILED).then_some("encoding failed"),
+        })
+    }
+
+    /// A PDS and video service in one, where `upload` answers uploadVideo and `job` answers
+    /// getJobStatus
+    fn video_service(
+        upload: serde_json::Value,
+        job: impl Fn() -> serde_json::Value + Send + Sync + 'static,
+    ) -> TestServer {
+        TestServer::start(move |request: &Request| {
+            let body = match request.path() {
+                "/xrpc/com.atproto.server.getServiceAuth" => serde_json::json!({"token": "t"}),
+                "/xrpc/app.bsky.video.getUploadLimits" => serde_json::json!({"canUpload": true}),
+                "/xrpc/com.atproto.repo.describeRepo" => serde_json::json!({
+                    "handle": "alice.test",
+                    "did": DID,
+                    "didDoc": {"service": [{
+                        "id": "#atproto_pds",
+                        "type": "AtprotoPersonalDataServer",
+                        "serviceEndpoint": "https://pds.example.com",
+                    }]},
+                    "collections": [],
+                    "handleIsCorrect": true,
+                }),
+                "/xrpc/app.bsky.video.uploadVideo" => upload.clone(),
+                "/xrpc/app.bsky.video.getJobStatus" => serde_json::json!({"jobStatus": job()}),
+                "/xrpc/com.atproto.repo.uploadBlob" => {
+                    serde_json::json!({"blob": blob("text/vtt", request.body.len())})
+                }
+                _ => return Response::new(404, "text/plain", ""),
+            };
+            Response::json(body.to_string())
+        })
+    }
+
+    fn bluesky(server: &TestServer) -> Bluesky {
+        let client = ClientBuilder::default()
+            .service(server.url.clone())
+            .video_service(server.url.clone())
+            .session(Some(UserSession {
+                did: DID.to_string(),
+                handle: "alice.test".to_string(),
+                ..Default::default()
+            }))
+            .build()
+            .unwrap();
+        Bluesky::new(client)
+    }
+
+    #[tokio::test]
+    async fn skips_writing_unchanged_preferences() {
+        let server = TestServer::start(|request: &Request| match request.path() {
+            "/xrpc/app.bsky.actor.getPreferences" => Response::json(
+                serde_json::json!({"preferences": [{
+                    "$type": "app.bsky.actor.defs#mutedWordsPref",
+                    "items": [{"value": "word", "targets": ["content"]}],
+                }]})
+                .to_string(),
+            ),
+            _ => Response::json(""),
+        });
+        let mut bluesky = bluesky(&server);
+        let mut me = bluesky.me().unwrap();
+
+        assert!(!me.remove_muted_word("other").await.unwrap());
+        assert!(!me
+            .unpin_feed("at://did:plc:a/app.bsky.feed.generator/f")
+            .await
+            .unwrap());
+        let puts = |server: &TestServer| {
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.path() == "/xrpc/app.bsky.actor.putPreferences")
+                .count()
+        };
+        assert_eq!(puts(&server), 0);
+
+        assert!(me.remove_muted_word("WORD").await.unwrap());
+        assert_eq!(puts(&server), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn uploads_video_and_captions() {
+        let server = video_service(job_status("JOB_STATE_ENCODING", None), || {
+            job_status(JobStatus::COMPLETED, Some(blob("video/mp4", 5)))
+        });
+        let mut bluesky = bluesky(&server);
+
+        let video = bluesky
+            .me()
+            .unwrap()
+            .upload_video(
+                b"video",
+                "video/mp4",
+                Some("alt"),
+                None,
+                &[("en", b"WEBVTT")],
+            )
+            .await
+            .unwrap();
+        assert_eq!(video.video.mime_type, "video/mp4");
+        assert_eq!(video.alt.as_deref(), Some("alt"));
+        let captions = video.captions.unwrap();
+        assert_eq!(captions[0].lang, "en");
+        assert_eq!(captions[0].file.mime_type, "text/vtt");
+
+        let requests = server.requests();
+        let upload = requests
+            .iter()
+            .find(|request| request.path() == "/xrpc/app.bsky.video.uploadVideo")
+            .unwrap();
+        assert_eq!(upload.method, "POST");
+        assert!(upload.target.contains("did=did%3Aplc%3Aalice"));
+        assert!(upload.target.contains("name="));
+        assert_eq!(upload.header("content-type"), Some("video/mp4"));
+        assert_eq!(upload.header("authorization"), Some("Bearer t"));
+        assert_eq!(upload.body, b"video");
+        assert!(requests.iter().any(|request| request.path()
+            == "/xrpc/app.bsky.video.getJobStatus"
+            && request.target.contains("jobId=job1")));
+        let caption = requests
+            .iter()
+            .find(|request| request.path() == "/xrpc/com.atproto.repo.uploadBlob")
+            .unwrap();
+        assert_eq!(caption.header("content-type"), Some("text/vtt"));
+        assert_eq!(caption.body, b"WEBVTT");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_failed_jobs() {
+        let server = video_service(job_status("JOB_STATE_ENCODING", None), || {
+            job_status(JobStatus::FAILED, None)
+        });
+        let result = bluesky(&server)
+            .me()
+            .unwrap()
+            .upload_video(b"video", "video/mp4", None, None, &[])
+            .await;
+        assert!(
+            matches!(result, Err(BiskyError::VideoProcessingFailed(error)) if error == "encoding failed")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_completed_jobs_without_a_blob() {
+        let server = video_service(job_status(JobStatus::COMPLETED, None), || {
+            unreachable!("a completed job isn't polled")
+        });
+        let result = bluesky(&server)
+            .me()
+            .unwrap()
+            .upload_video(b"video", "video/mp4", None, None, &[])
+            .await;
+        assert!(matches!(result, Err(BiskyError::VideoProcessingFailed(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_on_stuck_jobs() {
+        let server = video_service(job_status("JOB_STATE_ENCODING", None), || {
+            job_status("JOB_STATE_ENCODING", None)
+        });
+        let result = bluesky(&server)
+            .me()
+            .unwrap()
+            .upload_video(b"video", "video/mp4", None, None, &[])
+            .await;
+        assert!(matches!(result, Err(BiskyError::VideoProcessingFailed(_))));
+        let polls = server
+            .requests()
+            .iter()
+            .filter(|request| request.path() == "/xrpc/app.bsky.video.getJobStatus")
+            .count();
+        assert_eq!(
+            polls as u32,
+            VIDEO_JOB_MAX_WAIT.as_secs() as u32 / VIDEO_JOB_POLL_INTERVAL.as_secs() as u32
+        );
+    }
+
+    /// A `Storage` in memory, shared between its clones
+    #[derive(Clone, Default)]
+    struct Memory(Arc<std::sync::Mutex<Option<serde_json::Value>>>);
+
+    #[async_trait::async_trait]
+    impl<T: DeserializeOwned + Serialize + Sync> Storage<T> for Memory {
+        type Error = std::io::Error;
+
+        async fn set(&self, data: Option<&T>) -> Result<(), Self::Error> {
+            *self.0.lock().unwrap() = data.map(|data| serde_json::to_value(data).unwrap());
+            Ok(())
+        }
+
+        async fn get(&self) -> Result<T, Self::Error> {
+            let value = self.0.lock().unwrap().clone();
+            Ok(serde_json::from_value(
+                value.ok_or(std::io::ErrorKind::NotFound)?,
+            )?)
+        }
+
+        fn is_not_found(&self, error: &Self::Error) -> bool {
+            error.kind() == std::io::ErrorKind::NotFound
+        }
+    }
+
+    impl Memory {
+        fn seen(&self) -> Vec<String> {
+            let state = self.0.lock().unwrap().clone().unwrap();
+            serde_json::from_value::<SearchWatchState>(state)
+                .unwrap()
+                .seen
+                .into()
+        }
+    }
+
+    fn post_uri(n: usize) -> String {
+        format!("at://{DID}/app.bsky.feed.post/{n}")
+    }
+
+    fn post_view(n: usize) -> serde_json::Value {
+        serde_json::json!({
+            "uri": post_uri(n),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": DID, "handle": "alice.test"},
+            "record": {"createdAt": "2024-01-01T00:00:00Z", "text": n.to_string()},
+            "indexedAt": (DateTime::UNIX_EPOCH + chrono::Duration::seconds(n as i64)).to_rfc3339(),
+        })
+    }
+
+    /// Posts to search, each the queries it matches and its number, which orders them by time
+    type SearchIndex = Arc<std::sync::Mutex<Vec<(Vec<&'static str>, usize)>>>;
+
+    /// Answers searchPosts from `posts`. Pages are as long as asked for, and the cursor is an
+    /// offset
+    fn search_service(posts: SearchIndex) -> TestServer {
+        TestServer::start(move |request: &Request| {
+            let url = Url::parse(&format!("http://localhost{}", request.target)).unwrap();
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.to_string())
+            };
+            let q = param("q").unwrap();
+            let limit: usize = param("limit").unwrap().parse().unwrap();
+            let offset: usize = param("cursor").map_or(0, |cursor| cursor.parse().unwrap());
+
+            let mut matching = posts
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(queries, _)| queries.contains(&q.as_str()))
+                .map(|(_, n)| *n)
+                .collect::<Vec<_>>();
+            matching.sort_by(|a, b| b.cmp(a));
+            let page = matching.iter().skip(offset).take(limit);
+            let end = offset + limit;
+            Response::json(
+                serde_json::json!({
+                    "posts": page.map(|n| post_view(*n)).collect::<Vec<_>>(),
+                    "cursor": (end < matching.len()).then(|| end.to_string()),
+                })
+                .to_string(),
+            )
+        })
+    }
+
+    fn searches(queries: &[&str]) -> Vec<SearchPosts> {
+        queries
+            .iter()
+            .map(|q| SearchPostsBuilder::default().q(*q).build().unwrap())
+            .collect()
+    }
+
+    fn searches_made(server: &TestServer) -> usize {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path() == "/xrpc/app.bsky.feed.searchPosts")
+            .count()
+    }
+
+    #[tokio::test]
+    async fn watches_searches_across_queries_and_restarts() {
+        let posts = Arc::new(std::sync::Mutex::new(vec![
+            (vec!["a"], 1),
+            (vec!["a", "b"], 2),
+            (vec!["b"], 3),
+        ]));
+        let server = search_service(posts.clone());
+        let mut bluesky = bluesky(&server);
+        let storage = Memory::default();
+
+        // The first run only remembers what already matches
+        let mut stream = bluesky
+            .bsky_stream_searches(searches(&["a", "b"]), storage.clone())
+            .await
+            .unwrap();
+        assert_eq!(searches_made(&server), 2);
+        assert_eq!(storage.seen(), [post_uri(1), post_uri(2), post_uri(3)]);
+
+        // A post matching both queries comes out once, and posts come out oldest first
+        posts.lock().unwrap().push((vec!["b"], 5));
+        posts.lock().unwrap().push((vec!["a", "b"], 4));
+        assert_eq!(stream.next().await.unwrap().uri, post_uri(4));
+        assert_eq!(stream.next().await.unwrap().uri, post_uri(5));
+        drop(stream);
+
+        // A restarted stream picks up where the last one stopped
+        posts.lock().unwrap().push((vec!["a"], 6));
+        let mut stream = bluesky
+            .bsky_stream_searches(searches(&["a", "b"]), storage.clone())
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().uri, post_uri(6));
+        assert_eq!(storage.seen().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn forgets_the_oldest_seen_posts() {
+        let posts = Arc::new(std::sync::Mutex::new(vec![(vec!["a"], 1)]));
+        let server = search_service(posts.clone());
+        let mut bluesky = bluesky(&server);
+        let storage = Memory::default();
+        let seen = (0..SEARCH_WATCH_SEEN_CAPACITY)
+            .map(|n| format!("at://{DID}/app.bsky.feed.post/old{n}"))
+            .collect();
+        Storage::set(&storage, Some(&SearchWatchState { seen }))
+            .await
+            .unwrap();
+
+        let mut stream = bluesky
+            .bsky_stream_searches(searches(&["a"]), storage.clone())
+            .await
+            .unwrap();
+        assert_eq!(stream.next().await.unwrap().uri, post_uri(1));
+
+        let seen = storage.seen();
+        assert_eq!(seen.len(), SEARCH_WATCH_SEEN_CAPACITY);
+        assert_eq!(seen[0], format!("at://{DID}/app.bsky.feed.post/old1"));
+        assert_eq!(seen.last(), Some(&post_uri(1)));
+    }
+
+    #[tokio::test]
+    async fn reads_a_limited_number_of_pages() {
+        let posts = (1..=SEARCH_WATCH_MAX_PAGES * 100 + 50)
+            .map(|n| (vec!["a"], n))
+            .collect();
+        let server = search_service(Arc::new(std::sync::Mutex::new(posts)));
+        let mut bluesky = bluesky(&server);
+        let storage = Memory::default();
+        Storage::set(&storage, Some(&SearchWatchState::default()))
+            .await
+            .unwrap();
+
+        let mut stream = bluesky
+            .bsky_stream_searches(searches(&["a"]), storage.clone())
+            .await
+            .unwrap();
+        // The oldest 50 are out of reach
+        for n in 51..=SEARCH_WATCH_MAX_PAGES * 100 + 50 {
+            assert_eq!(stream.next().await.unwrap().uri, post_uri(n));
+        }
+        assert_eq!(searches_made(&server), SEARCH_WATCH_MAX_PAGES);
+    }
+
+    #[tokio::test]
+    async fn fails_on_unreadable_state() {
+        struct Broken;
+
+        #[async_trait::async_trait]
+        impl Storage<SearchWatchState> for Broken {
+            type Error = std::io::Error;
+
+            async fn set(&self, _: Option<&SearchWatchState>) -> Result<(), Self::Error> {
+                Ok(())
+            }
+
+            async fn get(&self) -> Result<SearchWatchState, Self::Error> {
+                Err(std::io::Error::other("disk on fire"))
+            }
+        }
+
+        let server = search_service(Default::default());
+        let mut bluesky = bluesky(&server);
+        let result = bluesky.bsky_stream_searches(searches(&["a"]), Broken).await;
+        assert!(matches!(
+            result,
+            Err(StreamError::Bisky(BiskyError::StorageError(error))) if error == "disk on fire"
+        ));
+        assert_eq!(searches_made(&server), 0);
+    }
+
+    /// A PDS whose repo holds `items`, each a listitem record key, list record key and subject,
+    /// and which accepts any applyWrites
+    fn list_service(items: &[(&str, &str, &str)]) -> TestServer {
+        let records = items
+            .iter()
+            .map(|(rkey, list, subject)| {
+                serde_json::json!({
+                    "uri": format!("at://{DID}/app.bsky.graph.listitem/{rkey}"),
+                    "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                    "value": {
+                        "$type": "app.bsky.graph.listitem",
+                        "subject": subject,
+                        "list": format!("at://{DID}/app.bsky.graph.list/{list}"),
+                        "createdAt": "2024-01-01T00:00:00Z",
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        TestServer::start(move |request: &Request| match request.path() {
+            // Like a PDS, one page and then an empty one
+            "/xrpc/com.atproto.repo.listRecords" if request.target.contains("cursor=") => {
+                Response::json(serde_json::json!({"records": []}).to_string())
+            }
+            "/xrpc/com.atproto.repo.listRecords" => Response::json(
+                serde_json::json!({"records": records, "cursor": "last"}).to_string(),
+            ),
+            "/xrpc/com.atproto.repo.applyWrites" => {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let results = body["writes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .enumerate()
+                    .map(|(n, _)| {
+                        serde_json::json!({
+                            "$type": "com.atproto.repo.applyWrites#createResult",
+                            "uri": format!("at://{DID}/app.bsky.graph.listitem/new{n}"),
+                            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Response::json(serde_json::json!({"results": results}).to_string())
+            }
+            _ => Response::new(404, "text/plain", ""),
+        })
+    }
+
+    fn applied_writes(server: &TestServer) -> Vec<serde_json::Value> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path() == "/xrpc/com.atproto.repo.applyWrites")
+            .flat_map(|request| {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                body["writes"].as_array().unwrap().clone()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn manages_list_members_from_the_repo() {
+        // bob is on both lists, and carol's account is gone, so the AppView wouldn't show her
+        let server = list_service(&[
+            ("i1", "friends", "did:plc:bob"),
+            ("i2", "foes", "did:plc:bob"),
+            ("i3", "friends", "did:plc:carol"),
+        ]);
+        let mut bluesky = bluesky(&server);
+        let mut me = bluesky.me().unwrap();
+        let friends = AtUri::new(DID, "app.bsky.graph.list", "friends");
+
+        let added = me
+            .add_list_members(&friends, &["did:plc:bob", "did:plc:dave", "did:plc:dave"])
+            .await
+            .unwrap();
+        assert_eq!(added.len(), 1);
+        let writes = applied_writes(&server);
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0]["value"]["subject"], "did:plc:dave");
+        assert_eq!(writes[0]["value"]["list"], friends.to_string());
+
+        let removed = me
+            .remove_list_members(&friends, &["did:plc:bob", "did:plc:carol"])
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        let rkeys = applied_writes(&server)[1..]
+            .iter()
+            .map(|write| write["rkey"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(rkeys, ["i1", "i3"]);
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|request| request.path() == "/xrpc/app.bsky.graph.getList"));
+    }
+
+    #[tokio::test]
+    async fn follows_short_links() {
+        let server = TestServer::start(|request: &Request| match request.path() {
+            "/redirect" => Response::new(302, "text/plain", "")
+                .header("location", "https://bsky.app/start/did:plc:bob/3k2a"),
+            "/json" => Response::json(
+                serde_json::json!({"url": "/starter-pack/bob.test/3k2b"}).to_string(),
+            ),
+            _ => Response::new(404, "text/plain", ""),
+        });
+
+        let link = follow_short_link(server.url.join("redirect").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(link.as_str(), "https://bsky.app/start/did:plc:bob/3k2a");
+
+        // A relative target is taken relative to the short link
+        let link = follow_short_link(server.url.join("json").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(link, server.url.join("starter-pack/bob.test/3k2b").unwrap());
+
+        assert!(follow_short_link(server.url.join("missing").unwrap())
+            .await
+            .is_err());
+        let requests = server.requests();
+        assert_eq!(requests[1].header("accept"), Some("application/json"));
+    }
+
+    #[tokio::test]
+    async fn resolves_starter_pack_urls() {
+        let server = TestServer::start(|request: &Request| match request.path() {
+            "/xrpc/com.atproto.identity.resolveHandle" => {
+                assert!(request.target.contains("handle=bob.test"));
+                Response::json(serde_json::json!({"did": "did:plc:bob"}).to_string())
+            }
+            _ => Response::new(404, "text/plain", ""),
+        });
+        let mut bluesky = bluesky(&server);
+        let expected = AtUri::new("did:plc:bob", "app.bsky.graph.starterpack", "3k2a");
+
+        for url in [
+            "https://bsky.app/start/did:plc:bob/3k2a",
+            "https://bsky.app/starter-pack/did:plc:bob/3k2a/",
+            "https://bsky.app/start/bob.test/3k2a",
+            "https://bsky.app/starter-pack/bob.test/3k2a?ref=share",
+        ] {
+            assert_eq!(
+                bluesky.resolve_starter_pack_url(url).await.unwrap(),
+                expected,
+                "{url}"
+            );
+        }
+        assert_eq!(
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.path() == "/xrpc/com.atproto.identity.resolveHandle")
+                .count(),
+            2
+        );
+
+        for url in [
+            "not a url",
+            "https://bsky.app/profile/did:plc:bob/3k2a",
+            "https://bsky.app/start/did:plc:bob",
+            "https://bsky.app/start/did:plc:bob/3k2a/extra",
+        ] {
+            assert!(
+                matches!(bluesky.resolve_starter_pack_url(url).await, Err(BiskyError::InvalidUrl(u)) if u == url),
+                "{url}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn deletes_the_list_of_a_starter_pack_that_failed() {
+        let server = TestServer::start(|request: &Request| {
+            let body = serde_json::from_slice::<serde_json::Value>(&request.body).ok();
+            match request.path() {
+                "/xrpc/com.atproto.repo.createRecord" => {
+                    let collection = body.unwrap()["collection"].as_str().unwrap().to_string();
+                    match collection.as_str() {
+                        "app.bsky.graph.list" => Response::json(
+                            serde_json::json!({
+                                "uri": format!("at://{DID}/app.bsky.graph.list/pack"),
+                                "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                            })
+                            .to_string(),
+                        ),
+                        _ => Response::new(
+                            400,
+                            "application/json",
+                            serde_json::json!({"error": "InvalidRecord", "message": "no"})
+                                .to_string(),
+                        ),
+                    }
+                }
+                "/xrpc/com.atproto.repo.listRecords" => {
+                    Response::json(serde_json::json!({"records": []}).to_string())
+                }
+                _ => Response::json("{}"),
+            }
+        });
+        let mut bluesky = bluesky(&server);
+
+        let result = bluesky
+            .me()
+            .unwrap()
+            .create_starter_pack("Pack", None, &["did:plc:bob"], &[])
+            .await;
+        assert!(
+            matches!(result, Err(BiskyError::ApiError(error)) if error.error == "InvalidRecord")
+        );
+
+        let requests = server.requests();
+        let delete = requests
+            .iter()
+            .find(|request| request.path() == "/xrpc/com.atproto.repo.deleteRecord")
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&delete.body).unwrap();
+        assert_eq!(body["collection"], "app.bsky.graph.list");
+        assert_eq!(body["rkey"], "pack");
+    }
+
+    #[tokio::test]
+    async fn posts_with_a_threadgate_in_one_write() {
+        let server = TestServer::start(|_: &Request| {
+            Response::json(
+                serde_json::json!({"results": [
+                    {
+                        "$type": "com.atproto.repo.applyWrites#createResult",
+                        "uri": format!("at://{DID}/app.bsky.feed.post/3k2a"),
+                        "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                    },
+                    {
+                        "$type": "com.atproto.repo.applyWrites#createResult",
+                        "uri": format!("at://{DID}/app.bsky.feed.threadgate/3k2a"),
+                        "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                    },
+                ]})
+                .to_string(),
+            )
+        });
+        let mut bluesky = bluesky(&server);
+        let post = Post::builder().text("hi").build().unwrap();
+
+        let (output, threadgate) = bluesky
+            .me()
+            .unwrap()
+            .post_with_threadgate(post, vec![ThreadgateRule::Following])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path(), "/xrpc/com.atproto.repo.applyWrites");
+        let writes = applied_writes(&server);
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0]["collection"], "app.bsky.feed.post");
+        assert_eq!(writes[1]["collection"], "app.bsky.feed.threadgate");
+        let rkey = writes[0]["rkey"].as_str().unwrap();
+        assert_eq!(writes[1]["rkey"], rkey);
+        let post_uri = AtUri::new(DID, "app.bsky.feed.post", rkey);
+        assert_eq!(writes[1]["value"]["post"], post_uri.to_string());
+        assert_eq!(
+            writes[1]["value"]["allow"],
+            serde_json::json!([{"$type": "app.bsky.feed.threadgate#followingRule"}])
+        );
+        assert_eq!(threadgate.rkey.as_deref(), Some(rkey));
+        assert_eq!(output.uri, format!("at://{DID}/app.bsky.feed.post/3k2a"));
+    }
+
+    /// A PDS holding at most one threadgate, which starts out missing
+    fn threadgate_service() -> TestServer {
+        let stored = std::sync::Mutex::new(None::<serde_json::Value>);
+        TestServer::start(move |request: &Request| match request.path() {
+            "/xrpc/com.atproto.repo.getRecord" => match &*stored.lock().unwrap() {
+                Some(value) => Response::json(
+                    serde_json::json!({
+                        "uri": format!("at://{DID}/app.bsky.feed.threadgate/3k2a"),
+                        "cid": "current",
+                        "value": value,
+                    })
+                    .to_string(),
+                ),
+                None => Response::new(
+                    400,
+                    "application/json",
+                    serde_json::json!({"error": "RecordNotFound", "message": "missing"})
+                        .to_string(),
+                ),
+            },
+            "/xrpc/com.atproto.repo.putRecord" => {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                *stored.lock().unwrap() = Some(body["record"].clone());
+                Response::json(
+                    serde_json::json!({
+                        "uri": format!("at://{DID}/app.bsky.feed.threadgate/3k2a"),
+                        "cid": "next",
+                    })
+                    .to_string(),
+                )
+            }
+            _ => Response::new(404, "text/plain", ""),
+        })
+    }
+
+    fn puts(server: &TestServer) -> Vec<serde_json::Value> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path() == "/xrpc/com.atproto.repo.putRecord")
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hides_and_unhides_replies_once() {
+        let server = threadgate_service();
+        let mut bluesky = bluesky(&server);
+        let mut me = bluesky.me().unwrap();
+        let post = AtUri::new(DID, "app.bsky.feed.post", "3k2a");
+        let reply = "at://did:plc:bob/app.bsky.feed.post/r";
+
+        let threadgate = me.hide_reply(&post, reply).await.unwrap();
+        assert_eq!(
+            threadgate,
+            AtUri::new(DID, "app.bsky.feed.threadgate", "3k2a")
+        );
+        me.hide_reply(&post, reply).await.unwrap();
+
+        let written = puts(&server);
+        // The first write creates the threadgate, the second replaces it
+        assert_eq!(written[0]["swapRecord"], serde_json::Value::Null);
+        assert!(written[0].as_object().unwrap().contains_key("swapRecord"));
+        assert_eq!(written[0]["rkey"], "3k2a");
+        assert_eq!(written[0]["record"]["post"], post.to_string());
+        assert_eq!(written[1]["swapRecord"], "current");
+        assert_eq!(
+            written[1]["record"]["hiddenReplies"],
+            serde_json::json!([reply])
+        );
+
+        me.unhide_reply(&post, reply).await.unwrap();
+        me.unhide_reply(&post, reply).await.unwrap();
+        let written = puts(&server);
+        assert_eq!(written[3]["record"]["hiddenReplies"], serde_json::json!([]));
+
+        let someone_elses = AtUri::new("did:plc:bob", "app.bsky.feed.post", "3k2a");
+        assert!(matches!(
+            me.hide_reply(&someone_elses, reply).await,
+            Err(BiskyError::InvalidAtUri(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn updates_only_existing_lists() {
+        let server = threadgate_service();
+        let mut bluesky = bluesky(&server);
+        let list = AtUri::new(DID, "app.bsky.graph.list", "3k2a");
+
+        let result = bluesky.me().unwrap().rename_list(&list, "new").await;
+        assert!(
+            matches!(result, Err(BiskyError::ApiError(error)) if error.error == "RecordNotFound")
+        );
+        assert!(puts(&server).is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_reports_to_labelers() {
+        // Answers with the report it was sent
+        let server = TestServer::start(|request: &Request| {
+            let mut report: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            report["id"] = 1.into();
+            report["reportedBy"] = DID.into();
+            report["createdAt"] = "2024-01-01T00:00:00Z".into();
+            Response::json(report.to_string())
+        });
+        let mut bluesky = bluesky(&server);
+        let mut me = bluesky.me().unwrap();
+        let post = StrongRef {
+            uri: "at://did:plc:bob/app.bsky.feed.post/3k2a".to_string(),
+            cid: "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm".to_string(),
+        };
+
+        let output = me
+            .report(
+                ReportSubject::account("did:plc:bob"),
+                ReasonType::Spam,
+                Some("buy now"),
+                Some("did:plc:labeler"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.reason_type, ReasonType::Spam);
+        assert_eq!(output.subject, ReportSubject::account("did:plc:bob"));
+        me.report(
+            post.clone(),
+            ReasonType::Unknown("com.example#reasonOdd".to_string()),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let requests = server.requests();
+        assert!(requests
+            .iter()
+            .all(|request| request.path() == "/xrpc/com.atproto.moderation.createReport"));
+        assert_eq!(
+            requests[0].header("atproto-proxy"),
+            Some("did:plc:labeler#atproto_labeler")
+        );
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&requests[0].body).unwrap(),
+            serde_json::json!({
+                "reasonType": "com.atproto.moderation.defs#reasonSpam",
+                "reason": "buy now",
+                "subject": {"$type": "com.atproto.admin.defs#repoRef", "did": "did:plc:bob"},
+            })
+        );
+
+        assert_eq!(requests[1].header("atproto-proxy"), None);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&requests[1].body).unwrap(),
+            serde_json::json!({
+                "reasonType": "com.example#reasonOdd",
+                "subject": {
+                    "$type": "com.atproto.repo.strongRef",
+                    "uri": post.uri,
+                    "cid": post.cid,
+                },
+            })
+        );
+    }
+
+    fn notification(n: usize, reason: &str, subject: Option<String>) -> serde_json::Value {
+        let record = match reason {
+            "like" => serde_json::json!({
+                "$type": "app.bsky.feed.like",
+                "subject": {
+                    "uri": subject,
+                    "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                },
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+            "follow" => serde_json::json!({
+                "$type": "app.bsky.graph.follow",
+                "subject": DID,
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+            _ => serde_json::json!({
+                "$type": "app.bsky.feed.post",
+                "text": n.to_string(),
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+        };
+        serde_json::json!({
+            "uri": format!("at://did:plc:bob/app.bsky.feed.like/{n}"),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": "did:plc:bob", "handle": "bob.test"},
+            "reason": reason,
+            "reasonSubject": subject,
+            "record": record,
+            "isRead": false,
+            "indexedAt": (DateTime::UNIX_EPOCH + chrono::Duration::seconds(n as i64)).to_rfc3339(),
+        })
+    }
+
+    /// Answers listNotifications with `pages`, each its notifications and cursor. The first page
+    /// is listed without a cursor and cursor `n` lists page `n`
+    fn notification_service(
+        pages: Vec<(Vec<serde_json::Value>, Option<&'static str>)>,
+    ) -> TestServer {
+        TestServer::start(move |request: &Request| {
+            let url = Url::parse(&format!("http://localhost{}", request.target)).unwrap();
+            let page = url
+                .query_pairs()
+                .find(|(key, _)| key == "cursor")
+                .map_or(0, |(_, cursor)| cursor.parse().unwrap());
+            let (notifications, cursor) = &pages[page];
+            Response::json(
+                serde_json::json!({"notifications": notifications, "cursor": cursor}).to_string(),
+            )
+        })
+    }
+
+    #[tokio::test]
+    async fn lists_notifications_until_the_last_cursor() {
+        let pages = Vec::from([
+            (
+                Vec::from([
+                    notification(3, "reply", None),
+                    notification(2, "mention", None),
+                ]),
+                Some("1"),
+            ),
+            (Vec::from([notification(1, "follow", None)]), None),
+        ]);
+        let server = notification_service(pages);
+        let mut bluesky = bluesky(&server);
+
+        let (notifications, cursor) = bluesky
+            .bsky_list_notifications::<NotificationRecord>(usize::MAX, None, None)
+            .await
+            .unwrap();
+        assert_eq!(notifications.len(), 3);
+        assert_eq!(cursor, None);
+        assert_eq!(server.requests().len(), 2);
+        assert!(server.requests()[1].target.contains("cursor=1"));
+        // Without a cursor to continue from there is nothing to stream
+        assert!(matches!(
+            bluesky
+                .bsky_stream_notifications::<NotificationRecord>(None)
+                .await,
+            Err(StreamError::NoCursor)
+        ));
+
+        let (notifications, cursor) = bluesky
+            .bsky_list_notifications::<NotificationRecord>(2, None, None)
+            .await
+            .unwrap();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(cursor.as_deref(), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_cursor_before_an_empty_page() {
+        let server = notification_service(Vec::from([
+            (
+                Vec::from([
+                    notification(3, "reply", None),
+                    notification(2, "mention", None),
+                ]),
+                Some("1"),
+            ),
+            (Vec::from([notification(1, "follow", None)]), Some("2")),
+            (Vec::new(), None),
+        ]));
+        let mut bluesky = bluesky(&server);
+
+        let (notifications, cursor) = bluesky
+            .bsky_list_notifications::<NotificationRecord>(usize::MAX, None, None)
+            .await
+            .unwrap();
+        assert_eq!(notifications.len(), 3);
+        // An empty last page keeps the cursor of the page before it
+        assert_eq!(cursor.as_deref(), Some("2"));
+        assert!(bluesky
+            .bsky_stream_notifications::<NotificationRecord>(None)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn fetches_group_subjects_in_one_batch() {
+        let server = TestServer::start(|request: &Request| {
+            let url = Url::parse(&format!("http://localhost{}", request.target)).unwrap();
+            let body = match request.path() {
+                "/xrpc/app.bsky.notification.listNotifications" => serde_json::json!({
+                    "notifications": [
+                        notification(6, "like", Some(post_uri(1))),
+                        notification(5, "repost", Some(post_uri(1))),
+                        notification(4, "like", Some(post_uri(1))),
+                        notification(3, "like", Some(post_uri(2))),
+                        notification(2, "follow", None),
+                        notification(1, "reply", Some(post_uri(1))),
+                    ],
+                }),
+                "/xrpc/app.bsky.feed.getPosts" => serde_json::json!({
+                    "posts": url
+                        .query_pairs()
+                        .map(|(_, uri)| post_view(uri.rsplit('/').next().unwrap().parse().unwrap()))
+                        .collect::<Vec<_>>(),
+                }),
+                _ => return Response::new(404, "text/plain", ""),
+            };
+            Response::json(body.to_string())
+        });
+        let mut bluesky = bluesky(&server);
+
+        let groups = bluesky
+            .me()
+            .unwrap()
+            .list_notification_groups(50, chrono::Duration::days(2))
+            .await
+            .unwrap();
+        let counts = groups
+            .iter()
+            .map(|group| (group.reason.as_str(), group.count()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [
+                ("like", 2),
+                ("repost", 1),
+                ("like", 1),
+                ("follow", 1),
+                ("reply", 1)
+            ]
+        );
+
+        let get_posts = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.path() == "/xrpc/app.bsky.feed.getPosts")
+            .collect::<Vec<_>>();
+        assert_eq!(get_posts.len(), 1);
+        let url = Url::parse(&format!("http://localhost{}", get_posts[0].target)).unwrap();
+        let uris = url
+            .query_pairs()
+            .map(|(_, uri)| uri.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(uris, [post_uri(1), post_uri(2)]);
+
+        let subject = |n: usize| groups[n].subject.as_ref().unwrap();
+        assert_eq!(subject(0).uri, post_uri(1));
+        assert!(Arc::ptr_eq(subject(0), subject(1)));
+        assert!(Arc::ptr_eq(subject(0), subject(4)));
+        assert_eq!(subject(2).uri, post_uri(2));
+        assert!(groups[3].subject.is_none());
+    }
+
+    #[tokio::test]
+    async fn puts_notification_preferences_and_push_registrations() {
+        use crate::lexicon::app::bsky::notification::{FilterablePreference, PushPlatform};
+
+        let filterable = serde_json::json!({"include": "all", "list": true, "push": true});
+        let preference = serde_json::json!({"list": true, "push": false});
+        let preferences = serde_json::json!({"preferences": {
+            "chat": {"include": "accepted", "push": true},
+            "follow": filterable,
+            "like": filterable,
+            "likeViaRepost": {"include": "follows", "list": true, "push": false},
+            "mention": filterable,
+            "quote": filterable,
+            "reply": filterable,
+            "repost": filterable,
+            "repostViaRepost": filterable,
+            "starterpackJoined": preference,
+            "subscribedPost": {"list": false, "push": true},
+            "unverified": preference,
+            "verified": preference,
+        }});
+        let server = TestServer::start(move |request: &Request| match request.path() {
+            "/xrpc/app.bsky.notification.getPreferences"
+            | "/xrpc/app.bsky.notification.putPreferencesV2" => {
+                Response::json(preferences.to_string())
+            }
+            _ => Response::new(200, "text/plain", ""),
+        });
+        let mut bluesky = bluesky(&server);
+        let mut me = bluesky.me().unwrap();
+
+        me.set_notification_priority(true).await.unwrap();
+        let current = me.get_notification_preferences().await.unwrap();
+        assert_eq!(current.chat.include, "accepted");
+        assert_eq!(current.like_via_repost.include, "follows");
+        assert!(!current.like_via_repost.push);
+        assert!(!current.subscribed_post.list);
+        assert!(current.subscribed_post.push);
+
+        let updated = me
+            .update_notification_preferences(&PutPreferencesV2 {
+                like_via_repost: Some(FilterablePreference {
+                    include: "follows".to_string(),
+                    list: true,
+                    push: false,
+                }),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.like_via_repost.include, "follows");
+
+        let push = RegisterPush {
+            service_did: "did:web:api.bsky.app".to_string(),
+            token: "token".to_string(),
+            platform: PushPlatform::Ios,
+            app_id: "xyz.blueskyweb.app".to_string(),
+            age_restricted: None,
+        };
+        me.register_push(&push).await.unwrap();
+        me.unregister_push(&push).await.unwrap();
+
+        let requests = server.requests();
+        let body =
+            |n: usize| serde_json::from_slice::<serde_json::Value>(&requests[n].body).unwrap();
+        assert_eq!(
+            requests[0].path(),
+            "/xrpc/app.bsky.notification.putPreferences"
+        );
+        assert_eq!(body(0), serde_json::json!({"priority": true}));
+        assert_eq!(
+            requests[2].path(),
+            "/xrpc/app.bsky.notification.putPreferencesV2"
+        );
+        assert_eq!(
+            body(2),
+            serde_json::json!({
+                "likeViaRepost": {"include": "follows", "list": true, "push": false},
+            })
+        );
+        let registration = serde_json::json!({
+            "serviceDid": "did:web:api.bsky.app",
+            "token": "token",
+            "platform": "ios",
+            "appId": "xyz.blueskyweb.app",
+        });
+        assert_eq!(
+            requests[3].path(),
+            "/xrpc/app.bsky.notification.registerPush"
+        );
+        assert_eq!(body(3), registration);
+        assert_eq!(
+            requests[4].path(),
+            "/xrpc/app.bsky.notification.unregisterPush"
+        );
+        assert_eq!(body(4), registration);
+    }
+
+    #[tokio::test]
+    async fn streams_chat_logs_through_the_chat_proxy() {
+        use crate::lexicon::chat::bsky::convo::{LogEvent, Message};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let log_calls = AtomicUsize::new(0);
+        let server = TestServer::start(move |request: &Request| {
+            let body = match request.path() {
+                // No history when the stream starts, then a page of logs
+                "/xrpc/chat.bsky.convo.getLog" => match log_calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => serde_json::json!({"logs": []}),
+                    _ => serde_json::json!({
+                        "cursor": "2",
+                        "logs": [
+                            {
+                                "$type": "chat.bsky.convo.defs#logBeginConvo",
+                                "rev": "1",
+                                "convoId": "convo1",
+                            },
+                            {
+                                "$type": "chat.bsky.convo.defs#logCreateMessage",
+                                "rev": "2",
+                                "convoId": "convo1",
+                                "message": {
+                                    "$type": "chat.bsky.convo.defs#messageView",
+                                    "id": "message1",
+                                    "rev": "2",
+                                    "text": "hi",
+                                    "sender": {"did": "did:plc:bob"},
+                                    "sentAt": "2024-01-01T00:00:00Z",
+                                },
+                            },
+                            {"$type": "chat.bsky.convo.defs#logSomethingNew", "rev": "3"},
+                        ],
+                    }),
+                },
+                "/xrpc/chat.bsky.convo.muteConvo" => serde_json::json!({"convo": {
+                    "id": "convo1",
+                    "rev": "3",
+                    "members": [],
+                    "muted": true,
+                    "unreadCount": 0,
+                }}),
+                _ => return Response::new(404, "text/plain", ""),
+            };
+            Response::json(body.to_string())
+        });
+        let mut bluesky = bluesky(&server);
+
+        let mut stream = bluesky.chat_stream_log().await.unwrap();
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            LogEvent::BeginConvo(log) if log.convo_id == "convo1"
+        ));
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            LogEvent::CreateMessage(log)
+                if matches!(&log.message, Message::View(message) if message.text == "hi")
+        ));
+        assert!(matches!(stream.next().await.unwrap(), LogEvent::Unknown(_)));
+        drop(stream);
+
+        let convo = bluesky.chat_mute_convo("convo1").await.unwrap();
+        assert!(convo.muted);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        // The stream continues without a cursor until it gets one
+        assert!(!requests[1].target.contains("cursor"));
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[2].method, "POST");
+        assert!(requests
+            .iter()
+            .all(|request| request.header("atproto-proxy")
+                == Some("did:web:api.bsky.chat#bsky_chat")));
     }
 }
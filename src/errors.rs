@@ -1,3 +1,4 @@
+use crate::validation::ValidationError;
 use miette::Diagnostic;
 use serde::Deserialize;
 use thiserror::Error;
@@ -20,6 +21,24 @@ pub enum BiskyError {
     JsonError(#[from] serde_json::Error),
     #[error("Storage Error: {0}")]
     StorageError(String),
+    #[error(transparent)]
+    ValidationError(#[from] ValidationError),
+    #[error("Invalid AT URI: {0}")]
+    InvalidAtUri(String),
+    #[error("Nothing to undo: {0}")]
+    NothingToUndo(String),
+    #[error(transparent)]
+    ImageError(#[from] image::ImageError),
+    #[error("Image can't be compressed to {0} bytes")]
+    ImageTooLarge(usize),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Response is larger than {0} bytes")]
+    ResponseTooLarge(usize),
+    #[error("Video upload rejected: {0}")]
+    VideoUploadRejected(String),
+    #[error("Video processing failed: {0}")]
+    VideoProcessingFailed(String),
 }
 
 #[derive(Debug, Error, Deserialize)]
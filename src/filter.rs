@@ -0,0 +1,326 @@
+use crate::atproto::AtUri;
+use crate::lexicon::app::bsky::actor::MutedWord;
+use crate::lexicon::app::bsky::feed::{FeedViewPost, Post};
+use crate::lexicon::app::bsky::notification::{Notification, NotificationRecord};
+use crate::lexicon::com::atproto::repo::Record;
+use crate::moderation::{contains_word, PostText};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+/// How a keyword is matched against text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordMatch {
+    /// Whole words or phrases, ignoring surrounding punctuation, as muted words are matched by
+    /// the official clients. `rust` matches `Rust!` but not `trust`
+    WholeWord,
+    /// Anywhere in the text, so `rust` also matches `trust`
+    Substring,
+}
+
+/// A word or phrase to filter out, matched ignoring case
+#[derive(Debug, Clone)]
+pub struct Keyword {
+    pub value: String,
+    pub match_mode: KeywordMatch,
+    /// Match against post text, image and video alt text and link cards
+    pub in_text: bool,
+    /// Match against tags, exactly and without `#`
+    pub in_tags: bool,
+    /// The keyword stops applying after this
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Don't apply the keyword to accounts you follow
+    pub exclude_following: bool,
+}
+
+impl Keyword {
+    /// Match `value` as a whole word in text and tags, from everyone and without expiry
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            match_mode: KeywordMatch::WholeWord,
+            in_text: true,
+            in_tags: true,
+            expires_at: None,
+            exclude_following: false,
+        }
+    }
+
+    /// Match anywhere in the text rather than as a whole word
+    pub fn substring(mut self) -> Self {
+        self.match_mode = KeywordMatch::Substring;
+        self
+    }
+
+    /// Only match tags
+    pub fn tags_only(mut self) -> Self {
+        self.in_text = false;
+        self
+    }
+
+    pub fn expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    pub fn exclude_following(mut self) -> Self {
+        self.exclude_following = true;
+        self
+    }
+
+    fn matches(&self, post: &PostText) -> bool {
+        let value = self.value.to_lowercase();
+        if self.in_tags && post.tags.iter().any(|tag| tag.to_lowercase() == value) {
+            return true;
+        }
+        if !self.in_text {
+            return false;
+        }
+
+        std::iter::once(post.text)
+            .chain(post.media_texts.iter().copied())
+            .any(|text| match self.match_mode {
+                KeywordMatch::WholeWord => contains_word(text, &value, post.langs),
+                KeywordMatch::Substring => text.to_lowercase().contains(&value),
+            })
+    }
+}
+
+impl From<&MutedWord> for Keyword {
+    fn from(word: &MutedWord) -> Self {
+        Self {
+            value: word.value.clone(),
+            match_mode: KeywordMatch::WholeWord,
+            in_text: word.targets.iter().any(|target| target == "content"),
+            in_tags: true,
+            expires_at: word.expires_at,
+            exclude_following: word.actor_target.as_deref() == Some("exclude-following"),
+        }
+    }
+}
+
+/// Filters posts by keyword locally, without depending on the account's preferences on the
+/// server. Works on feeds, record streams and notifications
+#[derive(Debug, Clone, Default)]
+pub struct KeywordFilter {
+    pub keywords: Vec<Keyword>,
+    /// DIDs of the accounts you follow, for keywords that exclude them. Feeds carry this in the
+    /// author's viewer state, so it is only needed for records and notifications
+    pub following: HashSet<String>,
+}
+
+impl KeywordFilter {
+    pub fn new(keywords: Vec<Keyword>) -> Self {
+        Self {
+            keywords,
+            following: HashSet::new(),
+        }
+    }
+
+    /// A filter with the same words as your muted words preference, see
+    /// `Preferences::muted_words`
+    pub fn from_muted_words(words: &[MutedWord]) -> Self {
+        Self::new(words.iter().map(Keyword::from).collect())
+    }
+
+    pub fn following(mut self, following: HashSet<String>) -> Self {
+        self.following = following;
+        self
+    }
+
+    /// The first keyword `post` by `author` matches, if any
+    pub fn find(&self, post: &Post, author: &str) -> Option<&Keyword> {
+        self.find_inner(post, self.following.contains(author))
+    }
+
+    // `is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    fn find_inner(&self, post: &Post, author_followed: bool) -> Option<&Keyword> {
+        let post_text = PostText::new(post);
+        let now = Utc::now();
+
+        self.keywords.iter().find(|keyword| {
+            keyword
+                .expires_at
+                .map_or(true, |expires_at| expires_at > now)
+                && !(keyword.exclude_following && author_followed)
+                && keyword.matches(&post_text)
+        })
+    }
+
+    /// Whether a post from a feed matches no keyword
+    pub fn allows_feed_post(&self, feed_post: &FeedViewPost) -> bool {
+        let author = &feed_post.post.author;
+        let followed = self.following.contains(&author.did)
+            || author
+                .viewer
+                .as_ref()
+                .is_some_and(|viewer| viewer.following.is_some());
+        self.find_inner(&feed_post.post.record, followed).is_none()
+    }
+
+    /// Remove the posts matching a keyword from a feed
+    pub fn retain_feed(&self, feed: &mut Vec<FeedViewPost>) {
+        feed.retain(|feed_post| self.allows_feed_post(feed_post));
+    }
+
+    /// Whether a post record, such as one from `RecordStream::next_filtered`, matches no keyword
+    pub fn allows_record(&self, record: &Record<Post>) -> bool {
+        let author = record
+            .uri
+            .parse::<AtUri>()
+            .map(|uri| uri.authority)
+            .unwrap_or_default();
+        self.find(&record.value, &author).is_none()
+    }
+
+    /// Whether a notification matches no keyword. Only notifications carrying a post, such as
+    /// replies and mentions, can match
+    pub fn allows_notification(&self, notification: &Notification<NotificationRecord>) -> bool {
+        let NotificationRecord::Post(post) = &notification.record else {
+            return true;
+        };
+        let author = &notification.author;
+        let followed = self.following.contains(&author.did)
+            || author
+                .viewer
+                .as_ref()
+                .is_some_and(|viewer| viewer.following.is_some());
+        self.find_inner(post, followed).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    const BOB: &str = "did:plc:bob";
+
+    fn post(text: &str, tags: &[&str]) -> Post {
+        serde_json::from_value(json!({
+            "createdAt": "2024-01-01T00:00:00Z",
+            "text": text,
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    fn finds(keyword: Keyword, post: &Post) -> bool {
+        KeywordFilter::new(vec![keyword]).find(post, BOB).is_some()
+    }
+
+    #[test]
+    fn matches_whole_words_or_substrings() {
+        let trust = post("I trust you", &[]);
+        assert!(!finds(Keyword::new("rust"), &trust));
+        assert!(finds(Keyword::new("RUST").substring(), &trust));
+
+        let rust = post("Rust!", &[]);
+        assert!(finds(Keyword::new("rust"), &rust));
+        assert!(finds(Keyword::new("rust").substring(), &rust));
+    }
+
+    #[test]
+    fn matches_tags_exactly() {
+        let tagged = post("hi", &["Rust"]);
+        assert!(finds(Keyword::new("rust").tags_only(), &tagged));
+        assert!(!finds(Keyword::new("rus").substring(), &tagged));
+
+        let untagged = post("rust", &[]);
+        assert!(!finds(Keyword::new("rust").tags_only(), &untagged));
+
+        let hashtag: Post = serde_json::from_value(json!({
+            "createdAt": "2024-01-01T00:00:00Z",
+            "text": "#rust",
+            "facets": [{
+                "index": {"byteStart": 0, "byteEnd": 5},
+                "features": [{"$type": "app.bsky.richtext.facet#tag", "tag": "rust"}],
+            }],
+        }))
+        .unwrap();
+        assert!(finds(Keyword::new("rust").tags_only(), &hashtag));
+
+        let mut text_only = Keyword::new("rust");
+        text_only.in_tags = false;
+        assert!(!finds(text_only, &tagged));
+    }
+
+    #[test]
+    fn stops_matching_once_expired() {
+        let rust = post("rust", &[]);
+        let now = Utc::now();
+        assert!(!finds(
+            Keyword::new("rust").expires_at(now - Duration::minutes(1)),
+            &rust
+        ));
+        assert!(finds(
+            Keyword::new("rust").expires_at(now + Duration::minutes(1)),
+            &rust
+        ));
+    }
+
+    #[test]
+    fn skips_followed_accounts() {
+        let rust = post("rust", &[]);
+        let filter = KeywordFilter::new(vec![Keyword::new("rust").exclude_following()])
+            .following(HashSet::from([BOB.to_string()]));
+        assert!(filter.find(&rust, BOB).is_none());
+        assert!(filter.find(&rust, "did:plc:carol").is_some());
+
+        let filter = KeywordFilter::new(vec![Keyword::new("rust")])
+            .following(HashSet::from([BOB.to_string()]));
+        assert!(filter.find(&rust, BOB).is_some());
+    }
+
+    #[test]
+    fn matches_alt_text_and_link_cards() {
+        let blob = json!({
+            "$type": "blob",
+            "ref": {"$link": "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"},
+            "mimeType": "image/jpeg",
+            "size": 1,
+        });
+        for embed in [
+            json!({
+                "$type": "app.bsky.embed.images",
+                "images": [{"image": blob, "alt": "a rust logo"}],
+            }),
+            json!({
+                "$type": "app.bsky.embed.external",
+                "external": {"uri": "https://example.com", "title": "Rust", "description": ""},
+            }),
+            json!({
+                "$type": "app.bsky.embed.external",
+                "external": {"uri": "https://example.com", "title": "", "description": "on rust"},
+            }),
+            json!({"$type": "app.bsky.embed.video", "video": blob, "alt": "rust talk"}),
+        ] {
+            let post: Post = serde_json::from_value(json!({
+                "createdAt": "2024-01-01T00:00:00Z",
+                "text": "hi",
+                "embed": embed,
+            }))
+            .unwrap();
+            assert!(finds(Keyword::new("rust"), &post), "{embed}");
+            assert!(!finds(Keyword::new("rust").tags_only(), &post), "{embed}");
+        }
+    }
+
+    #[test]
+    fn follows_muted_word_targets() {
+        let word: MutedWord = serde_json::from_value(json!({
+            "value": "rust",
+            "targets": ["tag"],
+            "actorTarget": "exclude-following",
+        }))
+        .unwrap();
+        let keyword = Keyword::from(&word);
+        assert!(!keyword.in_text && keyword.in_tags && keyword.exclude_following);
+        assert_eq!(keyword.match_mode, KeywordMatch::WholeWord);
+
+        let filter = KeywordFilter::from_muted_words(&[word]);
+        assert!(filter.find(&post("rust", &[]), BOB).is_none());
+        assert!(filter.find(&post("hi", &["rust"]), BOB).is_some());
+    }
+}
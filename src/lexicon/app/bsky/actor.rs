@@ -1,13 +1,49 @@
+use super::graph::ListViewBasic;
+use crate::atproto::new_tid;
+use crate::lexicon::com::atproto::label::SelfLabelsUnion;
+use crate::lexicon::com::atproto::repo::{Blob, StrongRef};
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Label {
+    pub ver: Option<usize>,
     pub src: String,
     pub uri: String,
+    pub cid: Option<String>,
     pub val: String,
+    #[serde(default)]
     pub neg: bool,
     pub cts: DateTime<Utc>,
+    pub exp: Option<DateTime<Utc>>,
+}
+
+///app.bsky.actor.defs#viewerState
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ViewerState {
+    pub muted: Option<bool>,
+    #[serde(rename(deserialize = "mutedByList"))]
+    pub muted_by_list: Option<Box<ListViewBasic>>,
+    #[serde(rename(deserialize = "blockedBy"))]
+    pub blocked_by: Option<bool>,
+    pub blocking: Option<String>,
+    #[serde(rename(deserialize = "blockingByList"))]
+    pub blocking_by_list: Option<Box<ListViewBasic>>,
+    pub following: Option<String>,
+    #[serde(rename(deserialize = "followedBy"))]
+    pub followed_by: Option<String>,
+}
+
+///app.bsky.actor.defs#profileAssociated
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProfileAssociated {
+    pub lists: Option<usize>,
+    pub feedgens: Option<usize>,
+    #[serde(rename(deserialize = "starterPacks"))]
+    pub starter_packs: Option<usize>,
+    pub labeler: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,7 +53,12 @@ pub struct ProfileViewBasic {
     #[serde(rename(deserialize = "displayName"))]
     pub display_name: Option<String>,
     pub avatar: Option<String>,
+    pub associated: Option<ProfileAssociated>,
+    pub viewer: Option<ViewerState>,
+    #[serde(default)]
     pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "createdAt"))]
+    pub created_at: Option<DateTime<Utc>>,
     pub indexed_at: Option<String>,
 }
 
@@ -29,7 +70,12 @@ pub struct ProfileView {
     pub display_name: Option<String>,
     pub description: Option<String>,
     pub avatar: Option<String>,
+    pub associated: Option<ProfileAssociated>,
+    pub viewer: Option<ViewerState>,
+    #[serde(default)]
     pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "createdAt"))]
+    pub created_at: Option<DateTime<Utc>>,
     pub indexed_at: Option<String>,
 }
 
@@ -48,6 +94,570 @@ pub struct ProfileViewDetailed {
     pub follows_count: Option<usize>,
     #[serde(rename(deserialize = "postsCount"))]
     pub posts_count: Option<usize>,
+    pub associated: Option<ProfileAssociated>,
+    pub viewer: Option<ViewerState>,
+    #[serde(default)]
     pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "pinnedPost"))]
+    pub pinned_post: Option<StrongRef>,
+    #[serde(rename(deserialize = "createdAt"))]
+    pub created_at: Option<DateTime<Utc>>,
     pub indexed_at: Option<String>,
 }
+
+///app.bsky.actor.searchActors
+#[derive(Debug, Deserialize)]
+pub struct SearchActorsOutput {
+    pub cursor: Option<String>,
+    pub actors: Vec<ProfileView>,
+}
+
+///app.bsky.actor.searchActorsTypeahead
+#[derive(Debug, Deserialize)]
+pub struct SearchActorsTypeaheadOutput {
+    pub actors: Vec<ProfileViewBasic>,
+}
+
+///app.bsky.actor.profile
+///
+/// Fields this crate doesn't know about are kept in `extra`, so a profile can be read and
+/// written back without losing them
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    #[serde(
+        rename(deserialize = "$type", serialize = "$type"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rust_type: Option<String>,
+    #[serde(
+        rename(deserialize = "displayName", serialize = "displayName"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<SelfLabelsUnion>,
+    #[serde(
+        rename(
+            deserialize = "joinedViaStarterPack",
+            serialize = "joinedViaStarterPack"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub joined_via_starter_pack: Option<StrongRef>,
+    #[serde(
+        rename(deserialize = "pinnedPost", serialize = "pinnedPost"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pinned_post: Option<StrongRef>,
+    #[serde(
+        rename(deserialize = "createdAt", serialize = "createdAt"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#adultContentPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AdultContentPref {
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#contentLabelPref
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ContentLabelPref {
+    /// The labeler this applies to, or every labeler if it is missing
+    #[serde(
+        rename(deserialize = "labelerDid", serialize = "labelerDid"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub labeler_did: Option<String>,
+    pub label: String,
+    /// `ignore`, `show`, `warn` or `hide`
+    pub visibility: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#savedFeed
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedFeed {
+    pub id: String,
+    /// `feed`, `list` or `timeline`
+    #[serde(rename(deserialize = "type", serialize = "type"))]
+    pub feed_type: String,
+    pub value: String,
+    pub pinned: bool,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#savedFeedsPrefV2
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SavedFeedsPrefV2 {
+    pub items: Vec<SavedFeed>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#interestsPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct InterestsPref {
+    pub tags: Vec<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#mutedWord
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MutedWord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub value: String,
+    /// Where to look for the word, `content` and/or `tag`
+    pub targets: Vec<String>,
+    /// `all`, or `exclude-following` to not mute posts from accounts you follow
+    #[serde(
+        rename(deserialize = "actorTarget", serialize = "actorTarget"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub actor_target: Option<String>,
+    #[serde(
+        rename(deserialize = "expiresAt", serialize = "expiresAt"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl MutedWord {
+    /// Mute `value` in both post text and tags, from everyone and without expiry
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            value: value.into(),
+            targets: Vec::from(["content".to_string(), "tag".to_string()]),
+            actor_target: None,
+            expires_at: None,
+            extra: HashMap::new(),
+        }
+    }
+}
+
+///app.bsky.actor.defs#mutedWordsPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MutedWordsPref {
+    pub items: Vec<MutedWord>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#hiddenPostsPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HiddenPostsPref {
+    /// URIs of the hidden posts
+    pub items: Vec<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#labelerPrefItem
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LabelerPrefItem {
+    pub did: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#labelersPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct LabelersPref {
+    pub labelers: Vec<LabelerPrefItem>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#threadViewPref
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ThreadViewPref {
+    /// `oldest`, `newest`, `most-likes`, `random` or `hotness`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(
+        rename(
+            deserialize = "prioritizeFollowedUsers",
+            serialize = "prioritizeFollowedUsers"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub prioritize_followed_users: Option<bool>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.actor.defs#feedViewPref
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FeedViewPref {
+    /// The feed's URI, or `home` for the following timeline
+    pub feed: String,
+    #[serde(
+        rename(deserialize = "hideReplies", serialize = "hideReplies"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hide_replies: Option<bool>,
+    #[serde(
+        rename(
+            deserialize = "hideRepliesByUnfollowed",
+            serialize = "hideRepliesByUnfollowed"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hide_replies_by_unfollowed: Option<bool>,
+    #[serde(
+        rename(
+            deserialize = "hideRepliesByLikeCount",
+            serialize = "hideRepliesByLikeCount"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hide_replies_by_like_count: Option<usize>,
+    #[serde(
+        rename(deserialize = "hideReposts", serialize = "hideReposts"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hide_reposts: Option<bool>,
+    #[serde(
+        rename(deserialize = "hideQuotePosts", serialize = "hideQuotePosts"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hide_quote_posts: Option<bool>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///the preferences union of app.bsky.actor.defs
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum Preference {
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#adultContentPref",
+        serialize = "app.bsky.actor.defs#adultContentPref"
+    ))]
+    AdultContent(AdultContentPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#contentLabelPref",
+        serialize = "app.bsky.actor.defs#contentLabelPref"
+    ))]
+    ContentLabel(ContentLabelPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#savedFeedsPrefV2",
+        serialize = "app.bsky.actor.defs#savedFeedsPrefV2"
+    ))]
+    SavedFeedsV2(SavedFeedsPrefV2),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#interestsPref",
+        serialize = "app.bsky.actor.defs#interestsPref"
+    ))]
+    Interests(InterestsPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#mutedWordsPref",
+        serialize = "app.bsky.actor.defs#mutedWordsPref"
+    ))]
+    MutedWords(MutedWordsPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#hiddenPostsPref",
+        serialize = "app.bsky.actor.defs#hiddenPostsPref"
+    ))]
+    HiddenPosts(HiddenPostsPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#labelersPref",
+        serialize = "app.bsky.actor.defs#labelersPref"
+    ))]
+    Labelers(LabelersPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#threadViewPref",
+        serialize = "app.bsky.actor.defs#threadViewPref"
+    ))]
+    ThreadView(ThreadViewPref),
+    #[serde(rename(
+        deserialize = "app.bsky.actor.defs#feedViewPref",
+        serialize = "app.bsky.actor.defs#feedViewPref"
+    ))]
+    FeedView(FeedViewPref),
+    /// A preference of a type this version doesn't know about, or that didn't parse, kept as it
+    /// is so that writing the preferences back doesn't lose it
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+/// All of an account's preferences, as `app.bsky.actor.putPreferences` replaces them as a whole
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Preferences {
+    pub preferences: Vec<Preference>,
+}
+
+/// The first preference of a known variant, see `Preferences::find_preference`. With
+/// `or_default`, a default one is added if there is none
+macro_rules! preference_mut {
+    ($preferences:expr, $variant:ident($inner:ty), $type:literal) => {{
+        match $preferences.find_preference::<$inner>(
+            $type,
+            |p| matches!(p, Preference::$variant(_)),
+            Preference::$variant,
+        )? {
+            Some(index) => match &mut $preferences.preferences[index] {
+                Preference::$variant(preference) => Some(preference),
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    }};
+    ($preferences:expr, $variant:ident($inner:ty), $type:literal, or_default) => {{
+        let index = $preferences.find_preference::<$inner>(
+            $type,
+            |p| matches!(p, Preference::$variant(_)),
+            Preference::$variant,
+        )?;
+        let index = match index {
+            Some(index) => index,
+            None => {
+                $preferences
+                    .preferences
+                    .push(Preference::$variant(Default::default()));
+                $preferences.preferences.len() - 1
+            }
+        };
+        match &mut $preferences.preferences[index] {
+            Preference::$variant(preference) => preference,
+            _ => unreachable!(),
+        }
+    }};
+}
+
+impl Preferences {
+    /// The index of the first preference that is `known` or has the `$type` `preference_type`.
+    /// One of that type that couldn't be read is read again as `T` so that the error comes out,
+    /// rather than a second preference of the type being added next to it
+    fn find_preference<T: DeserializeOwned>(
+        &mut self,
+        preference_type: &str,
+        known: impl Fn(&Preference) -> bool,
+        variant: impl FnOnce(T) -> Preference,
+    ) -> Result<Option<usize>, serde_json::Error> {
+        let index = self
+            .preferences
+            .iter()
+            .position(|preference| match preference {
+                Preference::Unknown(value) => {
+                    value.get("$type").and_then(|t| t.as_str()) == Some(preference_type)
+                }
+                preference => known(preference),
+            });
+
+        if let Some(index) = index {
+            if let Preference::Unknown(value) = &self.preferences[index] {
+                let mut value = value.clone();
+                if let Some(object) = value.as_object_mut() {
+                    object.remove("$type");
+                }
+                self.preferences[index] = variant(serde_json::from_value(value)?);
+            }
+        }
+        Ok(index)
+    }
+
+    /// The muted words preference, added if there isn't one. Fails if there is one that can't
+    /// be read, as writing a second one back would leave two
+    pub fn muted_words_mut(&mut self) -> Result<&mut MutedWordsPref, serde_json::Error> {
+        Ok(preference_mut!(
+            self,
+            MutedWords(MutedWordsPref),
+            "app.bsky.actor.defs#mutedWordsPref",
+            or_default
+        ))
+    }
+
+    /// The saved feeds preference, added if there isn't one. Fails like `muted_words_mut`
+    pub fn saved_feeds_mut(&mut self) -> Result<&mut SavedFeedsPrefV2, serde_json::Error> {
+        Ok(preference_mut!(
+            self,
+            SavedFeedsV2(SavedFeedsPrefV2),
+            "app.bsky.actor.defs#savedFeedsPrefV2",
+            or_default
+        ))
+    }
+
+    /// The muted words, or none if there is no muted words preference
+    pub fn muted_words(&self) -> &[MutedWord] {
+        self.preferences
+            .iter()
+            .find_map(|preference| match preference {
+                Preference::MutedWords(muted_words) => Some(muted_words.items.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Mute a word, replacing any existing entry for the same word (ignoring case)
+    pub fn add_muted_word(&mut self, mut word: MutedWord) -> Result<(), serde_json::Error> {
+        let items = &mut self.muted_words_mut()?.items;
+        match items
+            .iter_mut()
+            .find(|item| item.value.to_lowercase() == word.value.to_lowercase())
+        {
+            Some(existing) => {
+                word.id = word.id.or(existing.id.take());
+                *existing = word;
+            }
+            None => {
+                word.id = word.id.or_else(|| Some(new_tid()));
+                items.push(word);
+            }
+        }
+        Ok(())
+    }
+
+    /// Unmute a word, ignoring case. Returns whether it was muted
+    pub fn remove_muted_word(&mut self, value: &str) -> Result<bool, serde_json::Error> {
+        match preference_mut!(
+            self,
+            MutedWords(MutedWordsPref),
+            "app.bsky.actor.defs#mutedWordsPref"
+        ) {
+            Some(muted_words) => {
+                let length = muted_words.items.len();
+                muted_words
+                    .items
+                    .retain(|item| item.value.to_lowercase() != value.to_lowercase());
+                Ok(muted_words.items.len() != length)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Pin a feed generator or list by URI, saving it first if it isn't saved
+    pub fn pin_feed(&mut self, uri: &str) -> Result<(), serde_json::Error> {
+        let items = &mut self.saved_feeds_mut()?.items;
+        match items.iter_mut().find(|item| item.value == uri) {
+            Some(existing) => existing.pinned = true,
+            None => items.push(SavedFeed {
+                id: new_tid(),
+                feed_type: match uri.contains("/app.bsky.graph.list/") {
+                    true => "list".to_string(),
+                    false => "feed".to_string(),
+                },
+                value: uri.to_string(),
+                pinned: true,
+                extra: HashMap::new(),
+            }),
+        }
+        Ok(())
+    }
+
+    /// Unpin a feed, leaving it saved. Returns whether it was pinned
+    pub fn unpin_feed(&mut self, uri: &str) -> Result<bool, serde_json::Error> {
+        let saved_feeds = preference_mut!(
+            self,
+            SavedFeedsV2(SavedFeedsPrefV2),
+            "app.bsky.actor.defs#savedFeedsPrefV2"
+        );
+        match saved_feeds.and_then(|saved_feeds| {
+            saved_feeds
+                .items
+                .iter_mut()
+                .find(|item| item.value == uri && item.pinned)
+        }) {
+            Some(existing) => {
+                existing.pinned = false;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preferences(value: serde_json::Value) -> Preferences {
+        serde_json::from_value(serde_json::json!({ "preferences": value })).unwrap()
+    }
+
+    #[test]
+    fn refuses_to_add_a_second_unreadable_preference() {
+        let mut preferences = preferences(serde_json::json!([{
+            "$type": "app.bsky.actor.defs#mutedWordsPref",
+            "items": [{"value": "word", "targets": "content"}],
+        }]));
+        assert!(matches!(preferences.preferences[0], Preference::Unknown(_)));
+
+        assert!(preferences.add_muted_word(MutedWord::new("other")).is_err());
+        assert!(preferences.remove_muted_word("word").is_err());
+        assert_eq!(preferences.preferences.len(), 1);
+    }
+
+    #[test]
+    fn removing_adds_no_preference() {
+        let mut preferences = preferences(serde_json::json!([]));
+        assert!(!preferences.remove_muted_word("word").unwrap());
+        assert!(!preferences
+            .unpin_feed("at://did:plc:a/app.bsky.feed.generator/f")
+            .unwrap());
+        assert!(preferences.preferences.is_empty());
+    }
+
+    #[test]
+    fn new_entries_get_distinct_ids() {
+        let mut preferences = preferences(serde_json::json!([]));
+        for word in ["a", "b", "c"] {
+            preferences.add_muted_word(MutedWord::new(word)).unwrap();
+        }
+        let ids = preferences
+            .muted_words()
+            .iter()
+            .map(|word| word.id.clone().unwrap())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn round_trips_profiles_with_unknown_fields() {
+        let json = serde_json::json!({
+            "$type": "app.bsky.actor.profile",
+            "displayName": "Alice",
+            "labels": {
+                "$type": "com.atproto.label.defs#selfLabels",
+                "values": [{"val": "!no-unauthenticated"}],
+            },
+            "createdAt": "2024-01-01T00:00:00Z",
+            "pronouns": "she/her",
+            "website": {"uri": "https://example.com"},
+        });
+        let profile: Profile = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(
+            profile.labels,
+            Some(SelfLabelsUnion::SelfLabels(_))
+        ));
+        assert_eq!(profile.extra.len(), 2);
+        assert_eq!(serde_json::to_value(&profile).unwrap(), json);
+
+        let json = serde_json::json!({
+            "displayName": "Alice",
+            "labels": {"$type": "com.atproto.label.defs#otherLabels", "values": 1},
+        });
+        let profile: Profile = serde_json::from_value(json.clone()).unwrap();
+        assert!(matches!(profile.labels, Some(SelfLabelsUnion::Unknown(_))));
+        assert_eq!(serde_json::to_value(&profile).unwrap(), json);
+    }
+}
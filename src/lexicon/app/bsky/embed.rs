@@ -1,29 +1,41 @@
-use crate::lexicon::com::atproto::repo::Blob;
+use super::actor::{Label, ProfileViewBasic};
+use super::feed::{BlockedAuthor, GeneratorView, ImagesEmbed};
+use super::graph::{ListView, StarterPackViewBasic};
+use crate::lexicon::com::atproto::repo::{Blob, StrongRef};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-// "app.bsky.embed.images#view",
-// "app.bsky.embed.external#view",
-// "app.bsky.embed.record#view",
-// "app.bsky.embed.recordWithMedia#view"
-
-///app.bsky.embed.images
-// #[derive(Debug, Deserialize, Serialize)]
-// pub struct Images {
-//     pub images: Vec<Image>,
-// }
+///app.bsky.embed.defs#aspectRatio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
+}
 
+///app.bsky.embed.images#image
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Image {
     pub image: Blob,
     pub alt: String,
+    #[serde(
+        rename(deserialize = "aspectRatio", serialize = "aspectRatio"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub aspect_ratio: Option<AspectRatio>,
 }
 
+///app.bsky.embed.images#viewImage
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ViewImage {
     pub thumb: String,
-    #[serde(rename(deserialize = "fullSize", serialize = "fullSize"))]
+    #[serde(rename(deserialize = "fullsize", serialize = "fullsize"))]
     pub full_size: String,
     pub alt: String,
+    #[serde(
+        rename(deserialize = "aspectRatio", serialize = "aspectRatio"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub aspect_ratio: Option<AspectRatio>,
 }
 
 ///app.bsky.embed.external#external
@@ -32,8 +44,8 @@ pub struct ExternalObject {
     pub uri: String,
     pub title: String,
     pub description: String,
-    #[serde(rename(deserialize = "maxSize", serialize = "maxSize"))]
-    pub max_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumb: Option<Blob>,
 }
 
 ///app.bsky.embed.external
@@ -41,3 +53,218 @@ pub struct ExternalObject {
 pub struct External {
     pub external: ExternalObject,
 }
+
+///app.bsky.embed.video#caption
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Caption {
+    pub lang: String,
+    pub file: Blob,
+}
+
+///app.bsky.embed.video
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Video {
+    pub video: Blob,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub captions: Option<Vec<Caption>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt: Option<String>,
+    #[serde(
+        rename(deserialize = "aspectRatio", serialize = "aspectRatio"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub aspect_ratio: Option<AspectRatio>,
+}
+
+///app.bsky.embed.record
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Record {
+    /// Set inside a `RecordWithMedia`, where clients usually tag the record. In the `Embeds`
+    /// union the tag carries the type instead, so this stays `None`
+    #[serde(
+        rename(deserialize = "$type", serialize = "$type"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rust_type: Option<String>,
+    pub record: StrongRef,
+}
+
+///the media union of app.bsky.embed.recordWithMedia
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum Media {
+    #[serde(rename(
+        deserialize = "app.bsky.embed.images",
+        serialize = "app.bsky.embed.images"
+    ))]
+    Images(ImagesEmbed),
+    #[serde(rename(
+        deserialize = "app.bsky.embed.external",
+        serialize = "app.bsky.embed.external"
+    ))]
+    External(External),
+    #[serde(rename(
+        deserialize = "app.bsky.embed.video",
+        serialize = "app.bsky.embed.video"
+    ))]
+    Video(Video),
+    /// Media of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.embed.recordWithMedia
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecordWithMedia {
+    pub record: Record,
+    pub media: Media,
+}
+
+///app.bsky.embed.images#view
+#[derive(Debug, Deserialize)]
+pub struct ImagesView {
+    pub images: Vec<ViewImage>,
+}
+
+///app.bsky.embed.external#viewExternal
+#[derive(Debug, Deserialize)]
+pub struct ViewExternal {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+    pub thumb: Option<String>,
+}
+
+///app.bsky.embed.external#view
+#[derive(Debug, Deserialize)]
+pub struct ExternalView {
+    pub external: ViewExternal,
+}
+
+///app.bsky.embed.video#view
+#[derive(Debug, Deserialize)]
+pub struct VideoView {
+    pub cid: String,
+    pub playlist: String,
+    pub thumbnail: Option<String>,
+    pub alt: Option<String>,
+    #[serde(rename(deserialize = "aspectRatio"))]
+    pub aspect_ratio: Option<AspectRatio>,
+}
+
+///app.bsky.embed.record#viewRecord
+#[derive(Debug, Deserialize)]
+pub struct ViewRecord {
+    pub uri: String,
+    pub cid: String,
+    pub author: ProfileViewBasic,
+    /// The embedded record. This is usually an `app.bsky.feed.post`, but can be any record type
+    pub value: serde_json::Value,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "replyCount"))]
+    pub reply_count: Option<usize>,
+    #[serde(rename(deserialize = "repostCount"))]
+    pub repost_count: Option<usize>,
+    #[serde(rename(deserialize = "likeCount"))]
+    pub like_count: Option<usize>,
+    #[serde(rename(deserialize = "quoteCount"))]
+    pub quote_count: Option<usize>,
+    #[serde(default)]
+    pub embeds: Vec<EmbedView>,
+    #[serde(rename(deserialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+}
+
+///app.bsky.embed.record#viewNotFound
+#[derive(Debug, Deserialize)]
+pub struct ViewNotFound {
+    pub uri: String,
+    #[serde(rename(deserialize = "notFound"))]
+    pub not_found: bool,
+}
+
+///app.bsky.embed.record#viewBlocked
+#[derive(Debug, Deserialize)]
+pub struct ViewBlocked {
+    pub uri: String,
+    pub blocked: bool,
+    pub author: BlockedAuthor,
+}
+
+///app.bsky.embed.record#viewDetached
+#[derive(Debug, Deserialize)]
+pub struct ViewDetached {
+    pub uri: String,
+    pub detached: bool,
+}
+
+///the record union of app.bsky.embed.record#view
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ViewRecordEnum {
+    #[serde(rename(deserialize = "app.bsky.embed.record#viewRecord"))]
+    ViewRecord(Box<ViewRecord>),
+    #[serde(rename(deserialize = "app.bsky.embed.record#viewNotFound"))]
+    ViewNotFound(ViewNotFound),
+    #[serde(rename(deserialize = "app.bsky.embed.record#viewBlocked"))]
+    ViewBlocked(ViewBlocked),
+    #[serde(rename(deserialize = "app.bsky.embed.record#viewDetached"))]
+    ViewDetached(ViewDetached),
+    #[serde(rename(deserialize = "app.bsky.feed.defs#generatorView"))]
+    GeneratorView(Box<GeneratorView>),
+    #[serde(rename(deserialize = "app.bsky.graph.defs#listView"))]
+    ListView(Box<ListView>),
+    #[serde(rename(deserialize = "app.bsky.graph.defs#starterPackViewBasic"))]
+    StarterPackViewBasic(Box<StarterPackViewBasic>),
+    /// A record view of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.embed.record#view
+#[derive(Debug, Deserialize)]
+pub struct RecordView {
+    pub record: ViewRecordEnum,
+}
+
+///the media union of app.bsky.embed.recordWithMedia#view
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum MediaView {
+    #[serde(rename(deserialize = "app.bsky.embed.images#view"))]
+    Images(ImagesView),
+    #[serde(rename(deserialize = "app.bsky.embed.external#view"))]
+    External(ExternalView),
+    #[serde(rename(deserialize = "app.bsky.embed.video#view"))]
+    Video(VideoView),
+    /// Media of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.embed.recordWithMedia#view
+#[derive(Debug, Deserialize)]
+pub struct RecordWithMediaView {
+    pub record: RecordView,
+    pub media: MediaView,
+}
+
+///the hydrated form of a post's embed, as found in `PostView::embed`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum EmbedView {
+    #[serde(rename(deserialize = "app.bsky.embed.images#view"))]
+    Images(ImagesView),
+    #[serde(rename(deserialize = "app.bsky.embed.external#view"))]
+    External(ExternalView),
+    #[serde(rename(deserialize = "app.bsky.embed.record#view"))]
+    Record(RecordView),
+    #[serde(rename(deserialize = "app.bsky.embed.recordWithMedia#view"))]
+    RecordWithMedia(RecordWithMediaView),
+    #[serde(rename(deserialize = "app.bsky.embed.video#view"))]
+    Video(VideoView),
+    /// An embed of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
@@ -1,20 +1,23 @@
+pub use super::actor::ProfileViewBasic;
 use super::{
-    actor::ProfileView,
-    embed::{External, Image},
+    actor::{Label, ProfileView, ViewerState as ActorViewerState},
+    embed::{EmbedView, External, Image, Record as RecordEmbed, RecordWithMedia, Video},
+    graph::ListViewBasic,
+    richtext::Facet,
 };
+use crate::lexicon::com::atproto::label::SelfLabelsUnion;
 use crate::lexicon::com::atproto::repo::StrongRef;
 use chrono::{DateTime, Utc};
+use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ImagesEmbed {
     pub images: Vec<Image>,
 }
 
-// "app.bsky.embed.images",
-// "app.bsky.embed.external",
-// "app.bsky.embed.record",
-// "app.bsky.embed.recordWithMedia"
+///the embed union of app.bsky.feed.post
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "$type")]
 pub enum Embeds {
@@ -28,20 +31,24 @@ pub enum Embeds {
         serialize = "app.bsky.embed.external"
     ))]
     External(External),
-    #[serde(rename(deserialize = "app.bsky.embed.record"))]
-    TodoRecord,
-    #[serde(rename(deserialize = "app.bsky.embed.recordWithMedia"))]
-    TodoRecordWithMedia,
-    // Record(Record),
-    // #[serde(alias = "app.bsky.embed.recordWithMedia")]
-    // RecordWithMedia(RecordWithMedia),
-
-    // "embed": {
-    //     "$type": "app.bsky.embed.images",
-    //     "images": [
-    //         { "image": uploadresp.json()["blob"], "alt": "Alternative text" }
-    //     ]
-    // }
+    #[serde(rename(
+        deserialize = "app.bsky.embed.record",
+        serialize = "app.bsky.embed.record"
+    ))]
+    Record(RecordEmbed),
+    #[serde(rename(
+        deserialize = "app.bsky.embed.recordWithMedia",
+        serialize = "app.bsky.embed.recordWithMedia"
+    ))]
+    RecordWithMedia(RecordWithMedia),
+    #[serde(rename(
+        deserialize = "app.bsky.embed.video",
+        serialize = "app.bsky.embed.video"
+    ))]
+    Video(Video),
+    /// An embed of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -52,38 +59,238 @@ pub struct Post {
     pub rust_type: Option<String>,
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Vec<Facet>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub embed: Option<Embeds>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply: Option<ReplyRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub langs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<SelfLabelsUnion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
 }
 
+///app.bsky.feed.defs#viewerState
 #[derive(Debug, Deserialize)]
-pub struct ProfileViewBasic {
-    pub did: String,
-    pub handle: String,
+pub struct ViewerState {
+    pub repost: Option<String>,
+    pub like: Option<String>,
+    #[serde(rename(deserialize = "threadMuted"))]
+    pub thread_muted: Option<bool>,
+    #[serde(rename(deserialize = "replyDisabled"))]
+    pub reply_disabled: Option<bool>,
+    #[serde(rename(deserialize = "embeddingDisabled"))]
+    pub embedding_disabled: Option<bool>,
+    pub pinned: Option<bool>,
+}
+
+///the allow union of app.bsky.feed.threadgate
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum ThreadgateRule {
+    /// Accounts mentioned in the post can reply
+    #[serde(rename(
+        deserialize = "app.bsky.feed.threadgate#mentionRule",
+        serialize = "app.bsky.feed.threadgate#mentionRule"
+    ))]
+    Mention,
+    /// Accounts following the author can reply
+    #[serde(rename(
+        deserialize = "app.bsky.feed.threadgate#followerRule",
+        serialize = "app.bsky.feed.threadgate#followerRule"
+    ))]
+    Follower,
+    /// Accounts the author follows can reply
+    #[serde(rename(
+        deserialize = "app.bsky.feed.threadgate#followingRule",
+        serialize = "app.bsky.feed.threadgate#followingRule"
+    ))]
+    Following,
+    /// Accounts on a list can reply
+    #[serde(rename(
+        deserialize = "app.bsky.feed.threadgate#listRule",
+        serialize = "app.bsky.feed.threadgate#listRule"
+    ))]
+    List { list: String },
+    /// A rule of a type this version doesn't know about, kept as it is so that writing the
+    /// threadgate back doesn't lose it
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
 }
 
+///app.bsky.feed.threadgate
+///
+/// Fields this crate doesn't know about are kept in `extra`, so a threadgate can be read and
+/// written back without losing them
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Threadgate {
+    /// URI of the post this gates replies to. The threadgate has the same record key
+    pub post: String,
+    /// Who can reply. `None` lets everyone reply and an empty list lets no one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<ThreadgateRule>>,
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+    /// URIs of replies hidden by the author
+    #[serde(
+        rename(deserialize = "hiddenReplies", serialize = "hiddenReplies"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub hidden_replies: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///the embeddingRules union of app.bsky.feed.postgate
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum PostgateEmbeddingRule {
+    /// The post can't be quoted
+    #[serde(rename(
+        deserialize = "app.bsky.feed.postgate#disableRule",
+        serialize = "app.bsky.feed.postgate#disableRule"
+    ))]
+    Disable,
+    /// A rule of a type this version doesn't know about, kept as it is so that writing the
+    /// postgate back doesn't lose it
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.feed.postgate
+///
+/// Fields this crate doesn't know about are kept in `extra`, so a postgate can be read and
+/// written back without losing them
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Postgate {
+    /// URI of the post this gates. The postgate has the same record key
+    pub post: String,
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+    /// URIs of posts quoting this one that shouldn't show it
+    #[serde(
+        rename(
+            deserialize = "detachedEmbeddingUris",
+            serialize = "detachedEmbeddingUris"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub detached_embedding_uris: Option<Vec<String>>,
+    #[serde(
+        rename(deserialize = "embeddingRules", serialize = "embeddingRules"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub embedding_rules: Option<Vec<PostgateEmbeddingRule>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.feed.defs#threadgateView
+#[derive(Debug, Deserialize)]
+pub struct ThreadgateView {
+    pub uri: Option<String>,
+    pub cid: Option<String>,
+    pub record: Option<Threadgate>,
+    #[serde(default)]
+    pub lists: Vec<ListViewBasic>,
+}
+
+///app.bsky.feed.defs#postView
 #[derive(Debug, Deserialize)]
 pub struct PostView {
     pub uri: String,
     pub cid: String,
     pub author: ProfileViewBasic,
     pub record: Post,
+    pub embed: Option<EmbedView>,
+    #[serde(rename(deserialize = "replyCount"))]
+    pub reply_count: Option<usize>,
+    #[serde(rename(deserialize = "repostCount"))]
+    pub repost_count: Option<usize>,
+    #[serde(rename(deserialize = "likeCount"))]
+    pub like_count: Option<usize>,
+    #[serde(rename(deserialize = "quoteCount"))]
+    pub quote_count: Option<usize>,
     #[serde(rename(deserialize = "indexedAt"))]
     pub indexed_at: DateTime<Utc>,
+    pub viewer: Option<ViewerState>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    pub threadgate: Option<ThreadgateView>,
 }
 
+///app.bsky.feed.defs#blockedAuthor
+#[derive(Debug, Deserialize)]
+pub struct BlockedAuthor {
+    pub did: String,
+    pub viewer: Option<ActorViewerState>,
+}
+
+///app.bsky.feed.defs#blockedPost
+#[derive(Debug, Deserialize)]
+pub struct BlockedPost {
+    pub uri: String,
+    pub blocked: bool,
+    pub author: BlockedAuthor,
+}
+
+///the root and parent union of app.bsky.feed.defs#replyRef
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ReplyRefPost {
+    #[serde(rename(deserialize = "app.bsky.feed.defs#postView"))]
+    PostView(Box<PostView>),
+    #[serde(rename(deserialize = "app.bsky.feed.defs#notFoundPost"))]
+    NotFoundPost(NotFoundPost),
+    #[serde(rename(deserialize = "app.bsky.feed.defs#blockedPost"))]
+    BlockedPost(BlockedPost),
+    /// A post view of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.feed.defs#replyRef
+#[derive(Debug, Deserialize)]
+pub struct ReplyRefView {
+    pub root: ReplyRefPost,
+    pub parent: ReplyRefPost,
+    #[serde(rename(deserialize = "grandparentAuthor"))]
+    pub grandparent_author: Option<ProfileViewBasic>,
+}
+
+///app.bsky.feed.defs#reasonRepost
 #[derive(Debug, Deserialize)]
 pub struct ReasonRepost {
     pub by: ProfileViewBasic,
+    pub uri: Option<String>,
+    pub cid: Option<String>,
     #[serde(rename(deserialize = "indexedAt"))]
     pub indexed_at: DateTime<Utc>,
 }
 
+///the reason union of app.bsky.feed.defs#feedViewPost
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum FeedViewPostReason {
+    #[serde(rename(deserialize = "app.bsky.feed.defs#reasonRepost"))]
+    ReasonRepost(Box<ReasonRepost>),
+    #[serde(rename(deserialize = "app.bsky.feed.defs#reasonPin"))]
+    ReasonPin,
+    /// A reason of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///app.bsky.feed.defs#feedViewPost
 #[derive(Debug, Deserialize)]
 pub struct FeedViewPost {
     pub post: PostView,
-    pub reason: Option<ReasonRepost>,
+    pub reply: Option<ReplyRefView>,
+    pub reason: Option<FeedViewPostReason>,
+    #[serde(rename(deserialize = "feedContext"))]
+    pub feed_context: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,6 +299,235 @@ pub struct AuthorFeed {
     pub feed: Vec<FeedViewPost>,
 }
 
+///app.bsky.feed.getAuthorFeed#filter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorFeedFilter {
+    #[default]
+    PostsWithReplies,
+    PostsNoReplies,
+    PostsWithMedia,
+    PostsAndAuthorThreads,
+    PostsWithVideo,
+}
+
+impl AuthorFeedFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostsWithReplies => "posts_with_replies",
+            Self::PostsNoReplies => "posts_no_replies",
+            Self::PostsWithMedia => "posts_with_media",
+            Self::PostsAndAuthorThreads => "posts_and_author_threads",
+            Self::PostsWithVideo => "posts_with_video",
+        }
+    }
+}
+
+///app.bsky.feed.searchPosts#sort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchPostsSort {
+    #[default]
+    Latest,
+    Top,
+}
+
+impl SearchPostsSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Latest => "latest",
+            Self::Top => "top",
+        }
+    }
+}
+
+///app.bsky.feed.searchPosts
+///
+/// `since` and `until` take either a datetime or a `YYYY-MM-DD` date. `mentions` and `author`
+/// take a handle or a DID. Posts have to match all of `tags`
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into, strip_option))]
+pub struct SearchPosts {
+    pub q: String,
+    #[builder(default)]
+    pub sort: Option<SearchPostsSort>,
+    #[builder(default)]
+    pub since: Option<String>,
+    #[builder(default)]
+    pub until: Option<String>,
+    #[builder(default)]
+    pub mentions: Option<String>,
+    #[builder(default)]
+    pub author: Option<String>,
+    #[builder(default)]
+    pub lang: Option<String>,
+    #[builder(default)]
+    pub domain: Option<String>,
+    #[builder(default)]
+    pub url: Option<String>,
+    #[builder(default, setter(custom))]
+    pub tags: Vec<String>,
+}
+
+impl SearchPostsBuilder {
+    /// Add a tag, without `#`, that results have to carry
+    pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.tags.get_or_insert_with(Vec::new).push(tag.into());
+        self
+    }
+}
+
+impl SearchPosts {
+    pub fn new(q: impl Into<String>) -> Self {
+        Self {
+            q: q.into(),
+            sort: None,
+            since: None,
+            until: None,
+            mentions: None,
+            author: None,
+            lang: None,
+            domain: None,
+            url: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// The parameters as query string pairs, without `limit` and `cursor`
+    pub fn query(&self) -> Vec<(&str, &str)> {
+        let mut query = Vec::from([("q", self.q.as_str())]);
+        if let Some(sort) = self.sort {
+            query.push(("sort", sort.as_str()));
+        }
+        let optional = [
+            ("since", &self.since),
+            ("until", &self.until),
+            ("mentions", &self.mentions),
+            ("author", &self.author),
+            ("lang", &self.lang),
+            ("domain", &self.domain),
+            ("url", &self.url),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                query.push((key, value.as_str()));
+            }
+        }
+        for tag in &self.tags {
+            query.push(("tag", tag.as_str()));
+        }
+        query
+    }
+}
+
+///app.bsky.feed.searchPosts
+#[derive(Debug, Deserialize)]
+pub struct SearchPostsOutput {
+    pub cursor: Option<String>,
+    #[serde(rename(deserialize = "hitsTotal"))]
+    pub hits_total: Option<usize>,
+    pub posts: Vec<PostView>,
+}
+
+///app.bsky.feed.getTimeline
+#[derive(Debug, Deserialize)]
+pub struct GetTimelineOutput {
+    pub cursor: Option<String>,
+    pub feed: Vec<FeedViewPost>,
+}
+
+///app.bsky.feed.getFeed
+#[derive(Debug, Deserialize)]
+pub struct GetFeedOutput {
+    pub cursor: Option<String>,
+    pub feed: Vec<FeedViewPost>,
+}
+
+///app.bsky.feed.getListFeed
+#[derive(Debug, Deserialize)]
+pub struct GetListFeedOutput {
+    pub cursor: Option<String>,
+    pub feed: Vec<FeedViewPost>,
+}
+
+///app.bsky.feed.getActorLikes
+#[derive(Debug, Deserialize)]
+pub struct GetActorLikesOutput {
+    pub cursor: Option<String>,
+    pub feed: Vec<FeedViewPost>,
+}
+
+///app.bsky.feed.getPosts
+#[derive(Debug, Deserialize)]
+pub struct GetPostsOutput {
+    pub posts: Vec<PostView>,
+}
+
+///app.bsky.feed.getRepostedBy
+#[derive(Debug, Deserialize)]
+pub struct GetRepostedByOutput {
+    pub uri: String,
+    pub cid: Option<String>,
+    pub cursor: Option<String>,
+    #[serde(rename(deserialize = "repostedBy"))]
+    pub reposted_by: Vec<ProfileView>,
+}
+
+///app.bsky.feed.getQuotes
+#[derive(Debug, Deserialize)]
+pub struct GetQuotesOutput {
+    pub uri: String,
+    pub cid: Option<String>,
+    pub cursor: Option<String>,
+    pub posts: Vec<PostView>,
+}
+
+///app.bsky.feed.defs#generatorViewerState
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GeneratorViewerState {
+    pub like: Option<String>,
+}
+
+///app.bsky.feed.defs#generatorView
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GeneratorView {
+    pub uri: String,
+    pub cid: String,
+    pub did: String,
+    pub creator: ProfileView,
+    #[serde(rename(deserialize = "displayName", serialize = "displayName"))]
+    pub display_name: String,
+    pub description: Option<String>,
+    pub avatar: Option<String>,
+    #[serde(rename(deserialize = "likeCount", serialize = "likeCount"))]
+    pub like_count: Option<usize>,
+    #[serde(rename(deserialize = "acceptsInteractions", serialize = "acceptsInteractions"))]
+    pub accepts_interactions: Option<bool>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    pub viewer: Option<GeneratorViewerState>,
+    #[serde(rename(deserialize = "contentMode", serialize = "contentMode"))]
+    pub content_mode: Option<String>,
+    #[serde(rename(deserialize = "indexedAt", serialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+}
+
+///app.bsky.feed.getFeedGenerator
+#[derive(Debug, Deserialize)]
+pub struct GetFeedGeneratorOutput {
+    pub view: GeneratorView,
+    #[serde(rename(deserialize = "isOnline"))]
+    pub is_online: bool,
+    #[serde(rename(deserialize = "isValid"))]
+    pub is_valid: bool,
+}
+
+///app.bsky.feed.getFeedGenerators
+#[derive(Debug, Deserialize)]
+pub struct GetFeedGeneratorsOutput {
+    pub feeds: Vec<GeneratorView>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Like {
     #[serde(rename(deserialize = "createdAt"))]
@@ -120,7 +556,7 @@ pub struct Repost {
     pub subject: StrongRef,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplyRef {
     pub root: StrongRef,
     pub parent: StrongRef,
@@ -141,9 +577,22 @@ pub struct GetLikesOutput {
     pub cursor: Option<String>,
 }
 
+///app.bsky.feed.defs#threadContext
+#[derive(Debug, Deserialize)]
+pub struct ThreadContext {
+    #[serde(rename(deserialize = "rootAuthorLike"))]
+    pub root_author_like: Option<String>,
+}
+
+///app.bsky.feed.defs#threadViewPost
 #[derive(Debug, Deserialize)]
 pub struct ThreadViewPost {
     pub post: PostView,
+    pub parent: Option<ThreadViewPostEnum>,
+    #[serde(default)]
+    pub replies: Vec<ThreadViewPostEnum>,
+    #[serde(rename(deserialize = "threadContext"))]
+    pub thread_context: Option<ThreadContext>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -157,9 +606,136 @@ pub struct NotFoundPost {
 #[serde(tag = "$type")]
 pub enum ThreadViewPostEnum {
     #[serde(rename(deserialize = "app.bsky.feed.defs#threadViewPost"))]
-    ThreadViewPost(ThreadViewPost),
+    ThreadViewPost(Box<ThreadViewPost>),
     #[serde(rename(deserialize = "app.bsky.feed.defs#notFoundPost"))]
     NotFoundPost(NotFoundPost),
+    #[serde(rename(deserialize = "app.bsky.feed.defs#blockedPost"))]
+    BlockedPost(BlockedPost),
+    /// A thread node of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+impl ThreadViewPostEnum {
+    /// The URI of the post at this point in the thread. A node of an unknown type has one only
+    /// if it has a `uri` field
+    pub fn uri(&self) -> Option<&str> {
+        match self {
+            Self::ThreadViewPost(thread) => Some(&thread.post.uri),
+            Self::NotFoundPost(post) => Some(&post.uri),
+            Self::BlockedPost(post) => Some(&post.uri),
+            Self::Unknown(value) => value.get("uri").and_then(|uri| uri.as_str()),
+        }
+    }
+
+    /// The post at this point in the thread, unless it was not found or is blocked
+    pub fn as_thread_view_post(&self) -> Option<&ThreadViewPost> {
+        match self {
+            Self::ThreadViewPost(thread) => Some(thread),
+            _ => None,
+        }
+    }
+}
+
+impl ThreadViewPost {
+    /// Walk up the thread from this post's parent to the root
+    pub fn ancestors(&self) -> ThreadAncestors<'_> {
+        ThreadAncestors {
+            next: self.parent.as_ref(),
+        }
+    }
+
+    /// The top of the thread as far as it was fetched; `None` if this post has no parent
+    pub fn root(&self) -> Option<&ThreadViewPostEnum> {
+        self.ancestors().last()
+    }
+
+    /// Iterate all replies below this post, each reply followed by its own replies
+    pub fn replies_depth_first(&self) -> ThreadRepliesDepthFirst<'_> {
+        ThreadRepliesDepthFirst {
+            stack: self.replies.iter().rev().collect(),
+        }
+    }
+
+    /// Iterate all replies below this post, level by level
+    pub fn replies_breadth_first(&self) -> ThreadRepliesBreadthFirst<'_> {
+        ThreadRepliesBreadthFirst {
+            queue: self.replies.iter().collect(),
+        }
+    }
+
+    /// Every visible post in the thread (ancestors, this post and all replies) sorted by
+    /// creation time, oldest first
+    pub fn flatten(&self) -> Vec<&PostView> {
+        let mut posts = self
+            .ancestors()
+            .chain(self.replies_depth_first())
+            .filter_map(ThreadViewPostEnum::as_thread_view_post)
+            .map(|thread| &thread.post)
+            .collect::<Vec<_>>();
+        posts.push(&self.post);
+        posts.sort_by_key(|post| post.record.created_at);
+        posts
+    }
+
+    /// Find a post anywhere in the fetched thread by its URI
+    pub fn find(&self, uri: &str) -> Option<&ThreadViewPost> {
+        if self.post.uri == uri {
+            return Some(self);
+        }
+        self.ancestors()
+            .chain(self.replies_depth_first())
+            .filter_map(ThreadViewPostEnum::as_thread_view_post)
+            .find(|thread| thread.post.uri == uri)
+    }
+}
+
+pub struct ThreadAncestors<'a> {
+    next: Option<&'a ThreadViewPostEnum>,
+}
+
+impl<'a> Iterator for ThreadAncestors<'a> {
+    type Item = &'a ThreadViewPostEnum;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        if let ThreadViewPostEnum::ThreadViewPost(thread) = current {
+            self.next = thread.parent.as_ref();
+        }
+        Some(current)
+    }
+}
+
+pub struct ThreadRepliesDepthFirst<'a> {
+    stack: Vec<&'a ThreadViewPostEnum>,
+}
+
+impl<'a> Iterator for ThreadRepliesDepthFirst<'a> {
+    type Item = &'a ThreadViewPostEnum;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.stack.pop()?;
+        if let ThreadViewPostEnum::ThreadViewPost(thread) = current {
+            self.stack.extend(thread.replies.iter().rev());
+        }
+        Some(current)
+    }
+}
+
+pub struct ThreadRepliesBreadthFirst<'a> {
+    queue: VecDeque<&'a ThreadViewPostEnum>,
+}
+
+impl<'a> Iterator for ThreadRepliesBreadthFirst<'a> {
+    type Item = &'a ThreadViewPostEnum;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.queue.pop_front()?;
+        if let ThreadViewPostEnum::ThreadViewPost(thread) = current {
+            self.queue.extend(thread.replies.iter());
+        }
+        Some(current)
+    }
 }
 
 ///api.bsky.feed.getPostThread
@@ -167,8 +743,278 @@ pub enum ThreadViewPostEnum {
 pub struct GetPostThread {
     pub uri: String,
     pub depth: Option<usize>,
+    #[serde(rename(serialize = "parentHeight"))]
+    pub parent_height: Option<usize>,
 }
 #[derive(Debug, Deserialize)]
 pub struct GetPostThreadOutput {
     pub thread: ThreadViewPostEnum,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::app::bsky::embed::Media;
+
+    fn post_view(uri: &str, record: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "uri": uri,
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": "did:plc:alice", "handle": "alice.test"},
+            "record": record,
+            "indexedAt": "2024-01-01T00:00:00Z",
+        })
+    }
+
+    fn thread_node(
+        rkey: &str,
+        minute: u32,
+        parent: Option<serde_json::Value>,
+        replies: Vec<serde_json::Value>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "$type": "app.bsky.feed.defs#threadViewPost",
+            "post": post_view(
+                &format!("at://did:plc:alice/app.bsky.feed.post/{rkey}"),
+                serde_json::json!({
+                    "createdAt": format!("2024-01-01T00:{minute:02}:00Z"),
+                    "text": rkey,
+                }),
+            ),
+            "parent": parent,
+            "replies": replies,
+        })
+    }
+
+    /// root (blocked) <- parent <- focus, with focus's replies: a (with reply a1), a missing
+    /// post and b
+    fn fixture_thread() -> ThreadViewPostEnum {
+        let root = serde_json::json!({
+            "$type": "app.bsky.feed.defs#blockedPost",
+            "uri": "at://did:plc:alice/app.bsky.feed.post/root",
+            "blocked": true,
+            "author": {"did": "did:plc:alice"},
+        });
+        let parent = thread_node("parent", 1, Some(root), Vec::new());
+        let replies = Vec::from([
+            thread_node(
+                "a",
+                5,
+                None,
+                Vec::from([thread_node("a1", 3, None, Vec::new())]),
+            ),
+            serde_json::json!({
+                "$type": "app.bsky.feed.defs#notFoundPost",
+                "uri": "at://did:plc:alice/app.bsky.feed.post/gone",
+                "notFound": true,
+            }),
+            thread_node("b", 4, None, Vec::new()),
+        ]);
+        serde_json::from_value(thread_node("focus", 2, Some(parent), replies)).unwrap()
+    }
+
+    fn rkeys<'a>(nodes: impl Iterator<Item = &'a ThreadViewPostEnum>) -> Vec<&'a str> {
+        nodes
+            .map(|node| node.uri().unwrap().rsplit('/').next().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn walks_ancestors_up_to_a_blocked_root() {
+        let thread = fixture_thread();
+        let focus = thread.as_thread_view_post().unwrap();
+
+        assert_eq!(rkeys(focus.ancestors()), ["parent", "root"]);
+        let root = focus.root().unwrap();
+        assert!(matches!(root, ThreadViewPostEnum::BlockedPost(_)));
+        assert!(root.as_thread_view_post().is_none());
+
+        let parent = focus
+            .parent
+            .as_ref()
+            .unwrap()
+            .as_thread_view_post()
+            .unwrap();
+        assert_eq!(rkeys(parent.ancestors()), ["root"]);
+        assert!(parent
+            .find("at://did:plc:alice/app.bsky.feed.post/focus")
+            .is_none());
+    }
+
+    #[test]
+    fn walks_replies_depth_and_breadth_first() {
+        let thread = fixture_thread();
+        let focus = thread.as_thread_view_post().unwrap();
+
+        assert_eq!(rkeys(focus.replies_depth_first()), ["a", "a1", "gone", "b"]);
+        assert_eq!(
+            rkeys(focus.replies_breadth_first()),
+            ["a", "gone", "b", "a1"]
+        );
+
+        let a1 = focus
+            .find("at://did:plc:alice/app.bsky.feed.post/a1")
+            .unwrap();
+        assert!(a1.replies_depth_first().next().is_none());
+        assert!(a1.root().is_none());
+    }
+
+    #[test]
+    fn flattens_and_finds_only_visible_posts() {
+        let thread = fixture_thread();
+        let focus = thread.as_thread_view_post().unwrap();
+
+        let texts = focus
+            .flatten()
+            .iter()
+            .map(|post| post.record.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["parent", "focus", "a1", "b", "a"]);
+
+        for rkey in ["focus", "parent", "a", "a1", "b"] {
+            let uri = format!("at://did:plc:alice/app.bsky.feed.post/{rkey}");
+            assert_eq!(focus.find(&uri).unwrap().post.uri, uri);
+        }
+        assert!(focus
+            .find("at://did:plc:alice/app.bsky.feed.post/root")
+            .is_none());
+        assert!(focus
+            .find("at://did:plc:alice/app.bsky.feed.post/gone")
+            .is_none());
+    }
+
+    #[test]
+    fn keeps_unknown_embeds_and_labels() {
+        let record = serde_json::json!({
+            "$type": "app.bsky.feed.post",
+            "createdAt": "2024-01-01T00:00:00Z",
+            "text": "hello",
+            "embed": {"$type": "app.bsky.embed.hologram", "depth": 3},
+            "labels": {"$type": "com.example.labels", "values": ["x"]},
+        });
+        let view: PostView = serde_json::from_value(post_view(
+            "at://did:plc:alice/app.bsky.feed.post/1",
+            record.clone(),
+        ))
+        .unwrap();
+
+        assert!(matches!(view.record.embed, Some(Embeds::Unknown(_))));
+        assert!(matches!(
+            view.record.labels,
+            Some(SelfLabelsUnion::Unknown(_))
+        ));
+        assert_eq!(serde_json::to_value(&view.record).unwrap(), record);
+    }
+
+    #[test]
+    fn keeps_unknown_thread_nodes() {
+        let thread: ThreadViewPostEnum = serde_json::from_value(serde_json::json!({
+            "$type": "app.bsky.feed.defs#threadViewPost",
+            "post": post_view(
+                "at://did:plc:alice/app.bsky.feed.post/1",
+                serde_json::json!({"createdAt": "2024-01-01T00:00:00Z", "text": "root"}),
+            ),
+            "replies": [{
+                "$type": "app.bsky.feed.defs#threadNewKind",
+                "uri": "at://did:plc:bob/app.bsky.feed.post/2",
+            }],
+        }))
+        .unwrap();
+
+        let thread = thread.as_thread_view_post().unwrap();
+        assert!(matches!(thread.replies[0], ThreadViewPostEnum::Unknown(_)));
+        assert_eq!(
+            thread.replies[0].uri(),
+            Some("at://did:plc:bob/app.bsky.feed.post/2")
+        );
+    }
+
+    fn blob(mime_type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "$type": "blob",
+            "ref": {"$link": "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"},
+            "mimeType": mime_type,
+            "size": 1234,
+        })
+    }
+
+    #[test]
+    fn round_trips_every_embed_kind() {
+        let strong_ref = serde_json::json!({
+            "uri": "at://did:plc:bob/app.bsky.feed.post/3k2a",
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+        });
+        let images = serde_json::json!({
+            "$type": "app.bsky.embed.images",
+            "images": [
+                {
+                    "image": blob("image/jpeg"),
+                    "alt": "a cat",
+                    "aspectRatio": {"width": 4, "height": 3},
+                },
+                {"image": blob("image/png"), "alt": ""},
+            ],
+        });
+        let external = serde_json::json!({
+            "$type": "app.bsky.embed.external",
+            "external": {
+                "uri": "https://example.com",
+                "title": "Example",
+                "description": "An example",
+                "thumb": blob("image/jpeg"),
+            },
+        });
+        let video = serde_json::json!({
+            "$type": "app.bsky.embed.video",
+            "video": blob("video/mp4"),
+            "captions": [{"lang": "en", "file": blob("text/vtt")}],
+            "alt": "a cat video",
+            "aspectRatio": {"width": 16, "height": 9},
+        });
+        let record = serde_json::json!({
+            "$type": "app.bsky.embed.record",
+            "record": strong_ref,
+        });
+        let embeds = [
+            images.clone(),
+            external.clone(),
+            serde_json::json!({
+                "$type": "app.bsky.embed.external",
+                "external": {"uri": "https://example.com", "title": "", "description": ""},
+            }),
+            video.clone(),
+            serde_json::json!({"$type": "app.bsky.embed.video", "video": blob("video/mp4")}),
+            record.clone(),
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": record.clone(),
+                "media": images,
+            }),
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": record.clone(),
+                "media": external,
+            }),
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": record,
+                "media": video,
+            }),
+        ];
+
+        for embed in embeds {
+            let json = serde_json::json!({
+                "$type": "app.bsky.feed.post",
+                "createdAt": "2024-01-01T00:00:00.123Z",
+                "text": "hi",
+                "embed": embed,
+            });
+            let post: Post = serde_json::from_value(json.clone()).unwrap();
+            assert!(!matches!(post.embed, Some(Embeds::Unknown(_))), "{embed}");
+            if let Some(Embeds::RecordWithMedia(with_media)) = &post.embed {
+                assert!(!matches!(with_media.media, Media::Unknown(_)), "{embed}");
+            }
+            assert_eq!(serde_json::to_value(&post).unwrap(), json);
+        }
+    }
+}
@@ -1,7 +1,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::actor::ProfileView;
+use super::actor::{Label, ProfileView, ProfileViewBasic};
+use super::feed::GeneratorView;
+use super::richtext::Facet;
+use crate::lexicon::com::atproto::label::SelfLabelsUnion;
+use crate::lexicon::com::atproto::repo::Blob;
 
 ///app.bsky.graph.follow
 #[derive(Debug, Deserialize, Serialize)]
@@ -12,6 +17,27 @@ pub struct Follow {
     pub subject: String, //did
 }
 
+///app.bsky.graph.block
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Block {
+    #[serde(rename(deserialize = "createdAt"))]
+    #[serde(rename(serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+    pub subject: String, //did
+}
+
+///app.bsky.graph.muteActor
+#[derive(Debug, Serialize)]
+pub struct MuteActor<'a> {
+    pub actor: &'a str,
+}
+
+///app.bsky.graph.muteThread
+#[derive(Debug, Serialize)]
+pub struct MuteThread<'a> {
+    pub root: &'a str,
+}
+
 ///app.bsky.graph.getFollowers
 #[derive(Debug, Deserialize, Serialize)]
 pub struct GetFollowers {
@@ -43,3 +69,239 @@ pub struct GetFollowsOutput {
     pub follows: Vec<ProfileView>,
     pub cursor: Option<String>,
 }
+
+///app.bsky.graph.defs#listViewerState
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListViewerState {
+    pub muted: Option<bool>,
+    pub blocked: Option<String>,
+}
+
+///app.bsky.graph.defs#listViewBasic
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListViewBasic {
+    pub uri: String,
+    pub cid: String,
+    pub name: String,
+    pub purpose: String,
+    pub avatar: Option<String>,
+    #[serde(rename(deserialize = "listItemCount", serialize = "listItemCount"))]
+    pub list_item_count: Option<usize>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    pub viewer: Option<ListViewerState>,
+    #[serde(rename(deserialize = "indexedAt", serialize = "indexedAt"))]
+    pub indexed_at: Option<DateTime<Utc>>,
+}
+
+///app.bsky.graph.defs#listView
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListView {
+    pub uri: String,
+    pub cid: String,
+    pub creator: ProfileView,
+    pub name: String,
+    pub purpose: String,
+    pub description: Option<String>,
+    pub avatar: Option<String>,
+    #[serde(rename(deserialize = "listItemCount", serialize = "listItemCount"))]
+    pub list_item_count: Option<usize>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    pub viewer: Option<ListViewerState>,
+    #[serde(rename(deserialize = "indexedAt", serialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+}
+
+///app.bsky.graph.list
+///
+/// Fields this crate doesn't know about are kept in `extra`, so a list can be read and written
+/// back without losing them
+#[derive(Debug, Deserialize, Serialize)]
+pub struct List {
+    /// One of `List::CURATELIST`, `List::MODLIST` or `List::REFERENCELIST`
+    pub purpose: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(
+        rename(deserialize = "descriptionFacets", serialize = "descriptionFacets"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description_facets: Option<Vec<Facet>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<SelfLabelsUnion>,
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl List {
+    /// A list of accounts to mute or block together
+    pub const MODLIST: &'static str = "app.bsky.graph.defs#modlist";
+    /// A list of accounts for reading, such as in a list feed
+    pub const CURATELIST: &'static str = "app.bsky.graph.defs#curatelist";
+    /// A list of accounts for use elsewhere, such as backing a starter pack
+    pub const REFERENCELIST: &'static str = "app.bsky.graph.defs#referencelist";
+
+    pub fn new(name: impl Into<String>, purpose: &str) -> Self {
+        Self {
+            purpose: purpose.to_string(),
+            name: name.into(),
+            description: None,
+            description_facets: None,
+            avatar: None,
+            labels: None,
+            created_at: Utc::now(),
+            extra: HashMap::new(),
+        }
+    }
+}
+
+///app.bsky.graph.listitem
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListItem {
+    pub subject: String, //did
+    pub list: String,    //at-uri
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+}
+
+///app.bsky.graph.listblock
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListBlock {
+    pub subject: String, //at-uri
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+}
+
+///app.bsky.graph.muteActorList
+#[derive(Debug, Serialize)]
+pub struct MuteActorList<'a> {
+    pub list: &'a str,
+}
+
+///app.bsky.graph.defs#listItemView
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListItemView {
+    pub uri: String,
+    pub subject: ProfileView,
+}
+
+///app.bsky.graph.getList
+#[derive(Debug, Deserialize)]
+pub struct GetListOutput {
+    pub cursor: Option<String>,
+    pub list: ListView,
+    pub items: Vec<ListItemView>,
+}
+
+///app.bsky.graph.getLists
+#[derive(Debug, Deserialize)]
+pub struct GetListsOutput {
+    pub cursor: Option<String>,
+    pub lists: Vec<ListView>,
+}
+
+///app.bsky.graph.getListMutes
+#[derive(Debug, Deserialize)]
+pub struct GetListMutesOutput {
+    pub cursor: Option<String>,
+    pub lists: Vec<ListView>,
+}
+
+///app.bsky.graph.getListBlocks
+#[derive(Debug, Deserialize)]
+pub struct GetListBlocksOutput {
+    pub cursor: Option<String>,
+    pub lists: Vec<ListView>,
+}
+
+///app.bsky.graph.starterpack#feedItem
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StarterPackFeedItem {
+    pub uri: String,
+}
+
+///app.bsky.graph.starterpack
+///
+/// Fields this crate doesn't know about are kept in `extra`, so a starter pack can be read and
+/// written back without losing them
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StarterPack {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(
+        rename(deserialize = "descriptionFacets", serialize = "descriptionFacets"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description_facets: Option<Vec<Facet>>,
+    /// URI of the reference list holding the accounts
+    pub list: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feeds: Option<Vec<StarterPackFeedItem>>,
+    #[serde(rename(deserialize = "createdAt", serialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+///app.bsky.graph.defs#starterPackViewBasic
+#[derive(Debug, Deserialize)]
+pub struct StarterPackViewBasic {
+    pub uri: String,
+    pub cid: String,
+    pub record: StarterPack,
+    pub creator: ProfileViewBasic,
+    #[serde(rename(deserialize = "listItemCount"))]
+    pub list_item_count: Option<usize>,
+    #[serde(rename(deserialize = "joinedWeekCount"))]
+    pub joined_week_count: Option<usize>,
+    #[serde(rename(deserialize = "joinedAllTimeCount"))]
+    pub joined_all_time_count: Option<usize>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+}
+
+///app.bsky.graph.defs#starterPackView
+#[derive(Debug, Deserialize)]
+pub struct StarterPackView {
+    pub uri: String,
+    pub cid: String,
+    pub record: StarterPack,
+    pub creator: ProfileViewBasic,
+    pub list: Option<ListViewBasic>,
+    #[serde(rename(deserialize = "listItemsSample"), default)]
+    pub list_items_sample: Vec<ListItemView>,
+    #[serde(default)]
+    pub feeds: Vec<GeneratorView>,
+    #[serde(rename(deserialize = "joinedWeekCount"))]
+    pub joined_week_count: Option<usize>,
+    #[serde(rename(deserialize = "joinedAllTimeCount"))]
+    pub joined_all_time_count: Option<usize>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(rename(deserialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+}
+
+///app.bsky.graph.getStarterPack
+#[derive(Debug, Deserialize)]
+pub struct GetStarterPackOutput {
+    #[serde(rename(deserialize = "starterPack"))]
+    pub starter_pack: StarterPackView,
+}
+
+///app.bsky.graph.getActorStarterPacks
+#[derive(Debug, Deserialize)]
+pub struct GetActorStarterPacksOutput {
+    pub cursor: Option<String>,
+    #[serde(rename(deserialize = "starterPacks"))]
+    pub starter_packs: Vec<StarterPackViewBasic>,
+}
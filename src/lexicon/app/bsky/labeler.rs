@@ -0,0 +1,43 @@
+use super::actor::{Label, ProfileView};
+use crate::lexicon::com::atproto::label::LabelValueDefinition;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+///app.bsky.labeler.defs#labelerPolicies
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabelerPolicies {
+    /// Every label value the labeler may apply, including the global ones such as `porn`
+    #[serde(rename(deserialize = "labelValues"))]
+    pub label_values: Vec<String>,
+    /// Definitions of the labeler's own label values
+    #[serde(rename(deserialize = "labelValueDefinitions"), default)]
+    pub label_value_definitions: Vec<LabelValueDefinition>,
+}
+
+///app.bsky.labeler.defs#labelerViewerState
+#[derive(Debug, Deserialize)]
+pub struct LabelerViewerState {
+    pub like: Option<String>,
+}
+
+///app.bsky.labeler.defs#labelerViewDetailed
+#[derive(Debug, Deserialize)]
+pub struct LabelerViewDetailed {
+    pub uri: String,
+    pub cid: String,
+    pub creator: ProfileView,
+    pub policies: LabelerPolicies,
+    #[serde(rename(deserialize = "likeCount"))]
+    pub like_count: Option<usize>,
+    pub viewer: Option<LabelerViewerState>,
+    #[serde(rename(deserialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+///app.bsky.labeler.getServices
+#[derive(Debug, Deserialize)]
+pub struct GetServicesOutput<T> {
+    pub views: Vec<T>,
+}
@@ -2,4 +2,7 @@ pub mod actor;
 pub mod embed;
 pub mod feed;
 pub mod graph;
+pub mod labeler;
 pub mod notification;
+pub mod richtext;
+pub mod video;
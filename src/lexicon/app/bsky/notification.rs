@@ -1,22 +1,100 @@
-use super::actor::ProfileView;
+use super::actor::{Label, ProfileView};
 use super::feed::{Like, Post, Repost};
 use super::graph::Follow;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+///the reason of app.bsky.notification.listNotifications#notification
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum NotificationReason {
+    Like,
+    Repost,
+    Follow,
+    Mention,
+    Reply,
+    Quote,
+    /// Someone followed you through one of your starter packs
+    StarterpackJoined,
+    /// A trusted verifier verified your account
+    Verified,
+    /// A verification of your account was removed
+    Unverified,
+    /// Someone liked a repost of yours
+    LikeViaRepost,
+    /// Someone reposted a repost of yours
+    RepostViaRepost,
+    /// An account you subscribed to posted
+    SubscribedPost,
+    /// A reason this version doesn't know about
+    Unknown(String),
+}
+
+impl NotificationReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            NotificationReason::Like => "like",
+            NotificationReason::Repost => "repost",
+            NotificationReason::Follow => "follow",
+            NotificationReason::Mention => "mention",
+            NotificationReason::Reply => "reply",
+            NotificationReason::Quote => "quote",
+            NotificationReason::StarterpackJoined => "starterpack-joined",
+            NotificationReason::Verified => "verified",
+            NotificationReason::Unverified => "unverified",
+            NotificationReason::LikeViaRepost => "like-via-repost",
+            NotificationReason::RepostViaRepost => "repost-via-repost",
+            NotificationReason::SubscribedPost => "subscribed-post",
+            NotificationReason::Unknown(reason) => reason,
+        }
+    }
+}
+
+impl From<String> for NotificationReason {
+    fn from(reason: String) -> Self {
+        match reason.as_str() {
+            "like" => NotificationReason::Like,
+            "repost" => NotificationReason::Repost,
+            "follow" => NotificationReason::Follow,
+            "mention" => NotificationReason::Mention,
+            "reply" => NotificationReason::Reply,
+            "quote" => NotificationReason::Quote,
+            "starterpack-joined" => NotificationReason::StarterpackJoined,
+            "verified" => NotificationReason::Verified,
+            "unverified" => NotificationReason::Unverified,
+            "like-via-repost" => NotificationReason::LikeViaRepost,
+            "repost-via-repost" => NotificationReason::RepostViaRepost,
+            "subscribed-post" => NotificationReason::SubscribedPost,
+            _ => NotificationReason::Unknown(reason),
+        }
+    }
+}
+
+impl From<NotificationReason> for String {
+    fn from(reason: NotificationReason) -> Self {
+        match reason {
+            NotificationReason::Unknown(reason) => reason,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Notification<T> {
     pub uri: String,
     pub cid: String,
     pub author: ProfileView,
-    pub reason: String,
+    pub reason: NotificationReason,
+    /// The record the notification is about, such as the post that was liked or replied to
     #[serde(rename(deserialize = "reasonSubject"))]
     pub reason_subject: Option<String>,
     pub record: T,
     #[serde(rename(deserialize = "isRead"))]
     pub is_read: bool,
-    pub indexed_at: Option<String>,
-    pub labels: Vec<String>,
+    #[serde(rename(deserialize = "indexedAt"))]
+    pub indexed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
 }
 
 pub enum Subject {
@@ -33,19 +111,24 @@ pub struct PostSubject {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ActorSubject(String);
+pub struct ActorSubject(pub String);
 
+/// The record behind a notification. Replies, mentions and quotes are all posts, told apart by
+/// `Notification::reason`
 #[derive(Debug, Deserialize)]
 #[serde(tag = "$type")]
 pub enum NotificationRecord {
     #[serde(rename(deserialize = "app.bsky.feed.like"))]
     Like(Like),
     #[serde(rename(deserialize = "app.bsky.feed.post"))]
-    Post(Post),
+    Post(Box<Post>),
     #[serde(rename(deserialize = "app.bsky.feed.repost"))]
     Repost(Repost),
     #[serde(rename(deserialize = "app.bsky.graph.follow"))]
     Follow(Follow),
+    /// A record of a type this version doesn't know about, or that didn't parse
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,3 +147,232 @@ pub struct UpdateSeen {
 pub struct NotificationCount {
     pub count: usize,
 }
+
+///app.bsky.notification.putPreferences#input
+#[derive(Debug, Serialize)]
+pub struct PutPreferences {
+    pub priority: bool,
+}
+
+///app.bsky.notification.defs#chatPreference
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChatPreference {
+    /// `all` or `accepted`
+    pub include: String,
+    pub push: bool,
+}
+
+///app.bsky.notification.defs#filterablePreference
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterablePreference {
+    /// `all`, or `follows` for only accounts you follow
+    pub include: String,
+    /// Show these notifications in the list
+    pub list: bool,
+    /// Send a push notification
+    pub push: bool,
+}
+
+///app.bsky.notification.defs#preference
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Preference {
+    /// Show these notifications in the list
+    pub list: bool,
+    /// Send a push notification
+    pub push: bool,
+}
+
+///app.bsky.notification.defs#preferences
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationPreferences {
+    pub chat: ChatPreference,
+    pub follow: FilterablePreference,
+    pub like: FilterablePreference,
+    #[serde(rename(deserialize = "likeViaRepost"))]
+    pub like_via_repost: FilterablePreference,
+    pub mention: FilterablePreference,
+    pub quote: FilterablePreference,
+    pub reply: FilterablePreference,
+    pub repost: FilterablePreference,
+    #[serde(rename(deserialize = "repostViaRepost"))]
+    pub repost_via_repost: FilterablePreference,
+    #[serde(rename(deserialize = "starterpackJoined"))]
+    pub starterpack_joined: Preference,
+    #[serde(rename(deserialize = "subscribedPost"))]
+    pub subscribed_post: Preference,
+    pub unverified: Preference,
+    pub verified: Preference,
+}
+
+///app.bsky.notification.getPreferences
+#[derive(Debug, Deserialize)]
+pub struct GetPreferencesOutput {
+    pub preferences: NotificationPreferences,
+}
+
+///app.bsky.notification.putPreferencesV2#input
+///
+/// Only the preferences that are set are changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PutPreferencesV2 {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat: Option<ChatPreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow: Option<FilterablePreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub like: Option<FilterablePreference>,
+    #[serde(
+        rename(serialize = "likeViaRepost"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub like_via_repost: Option<FilterablePreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mention: Option<FilterablePreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<FilterablePreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<FilterablePreference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repost: Option<FilterablePreference>,
+    #[serde(
+        rename(serialize = "repostViaRepost"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub repost_via_repost: Option<FilterablePreference>,
+    #[serde(
+        rename(serialize = "starterpackJoined"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub starterpack_joined: Option<Preference>,
+    #[serde(
+        rename(serialize = "subscribedPost"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub subscribed_post: Option<Preference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unverified: Option<Preference>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<Preference>,
+}
+
+///app.bsky.notification.putPreferencesV2#output
+#[derive(Debug, Deserialize)]
+pub struct PutPreferencesV2Output {
+    pub preferences: NotificationPreferences,
+}
+
+///the platform of app.bsky.notification.registerPush
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushPlatform {
+    Ios,
+    Android,
+    Web,
+}
+
+///app.bsky.notification.registerPush#input
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterPush {
+    /// The DID of the push notification service, such as `did:web:api.bsky.app`
+    #[serde(rename(serialize = "serviceDid"))]
+    pub service_did: String,
+    /// The device's push token
+    pub token: String,
+    pub platform: PushPlatform,
+    #[serde(rename(serialize = "appId"))]
+    pub app_id: String,
+    /// Whether the account is age restricted, which limits the notifications sent
+    #[serde(
+        rename(serialize = "ageRestricted"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub age_restricted: Option<bool>,
+}
+
+///app.bsky.notification.unregisterPush#input
+#[derive(Debug, Serialize)]
+pub struct UnregisterPush<'a> {
+    #[serde(rename(serialize = "serviceDid"))]
+    pub service_did: &'a str,
+    pub token: &'a str,
+    pub platform: PushPlatform,
+    #[serde(rename(serialize = "appId"))]
+    pub app_id: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(reason: &str, record: serde_json::Value) -> Notification<NotificationRecord> {
+        serde_json::from_value(serde_json::json!({
+            "uri": "at://did:plc:bob/app.bsky.feed.like/3k2a",
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": "did:plc:bob", "handle": "bob.test"},
+            "reason": reason,
+            "record": record,
+            "isRead": true,
+            "indexedAt": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trips_reasons() {
+        for (reason, text) in [
+            (NotificationReason::Like, "like"),
+            (NotificationReason::Repost, "repost"),
+            (NotificationReason::Follow, "follow"),
+            (NotificationReason::Mention, "mention"),
+            (NotificationReason::Reply, "reply"),
+            (NotificationReason::Quote, "quote"),
+            (NotificationReason::StarterpackJoined, "starterpack-joined"),
+            (NotificationReason::Verified, "verified"),
+            (NotificationReason::Unverified, "unverified"),
+            (NotificationReason::LikeViaRepost, "like-via-repost"),
+            (NotificationReason::RepostViaRepost, "repost-via-repost"),
+            (NotificationReason::SubscribedPost, "subscribed-post"),
+            (
+                NotificationReason::Unknown("contact-match".to_string()),
+                "contact-match",
+            ),
+        ] {
+            assert_eq!(NotificationReason::from(text.to_string()), reason);
+            assert_eq!(reason.as_str(), text);
+            assert_eq!(serde_json::to_value(&reason).unwrap(), text);
+            assert_eq!(String::from(reason), text);
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_records() {
+        let like = notification(
+            "like",
+            serde_json::json!({
+                "$type": "app.bsky.feed.like",
+                "subject": {
+                    "uri": "at://did:plc:alice/app.bsky.feed.post/3k2a",
+                    "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                },
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+        );
+        assert!(matches!(like.record, NotificationRecord::Like(_)));
+        assert_eq!(like.reason, NotificationReason::Like);
+
+        let verification = serde_json::json!({
+            "$type": "app.bsky.graph.verification",
+            "subject": "did:plc:alice",
+            "createdAt": "2024-01-01T00:00:00Z",
+        });
+        let verified = notification("verified", verification.clone());
+        assert!(
+            matches!(&verified.record, NotificationRecord::Unknown(record) if *record == verification)
+        );
+
+        // A known type that doesn't parse isn't lost either
+        let broken = serde_json::json!({"$type": "app.bsky.feed.post"});
+        let reply = notification("reply", broken.clone());
+        assert!(matches!(&reply.record, NotificationRecord::Unknown(record) if *record == broken));
+    }
+}
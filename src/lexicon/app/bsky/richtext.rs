@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+///app.bsky.richtext.facet#byteSlice. Offsets are into the UTF-8 encoding of the text,
+///`byte_end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ByteSlice {
+    #[serde(rename(deserialize = "byteStart", serialize = "byteStart"))]
+    pub byte_start: usize,
+    #[serde(rename(deserialize = "byteEnd", serialize = "byteEnd"))]
+    pub byte_end: usize,
+}
+
+///app.bsky.richtext.facet#mention
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Mention {
+    pub did: String,
+}
+
+///app.bsky.richtext.facet#link
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Link {
+    pub uri: String,
+}
+
+///app.bsky.richtext.facet#tag
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Tag {
+    pub tag: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum FacetFeature {
+    #[serde(rename(
+        deserialize = "app.bsky.richtext.facet#mention",
+        serialize = "app.bsky.richtext.facet#mention"
+    ))]
+    Mention(Mention),
+    #[serde(rename(
+        deserialize = "app.bsky.richtext.facet#link",
+        serialize = "app.bsky.richtext.facet#link"
+    ))]
+    Link(Link),
+    #[serde(rename(
+        deserialize = "app.bsky.richtext.facet#tag",
+        serialize = "app.bsky.richtext.facet#tag"
+    ))]
+    Tag(Tag),
+}
+
+///app.bsky.richtext.facet
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Facet {
+    pub index: ByteSlice,
+    pub features: Vec<FacetFeature>,
+}
@@ -0,0 +1,64 @@
+use crate::lexicon::com::atproto::repo::Blob;
+use serde::{Deserialize, Serialize};
+
+///app.bsky.video.defs#jobStatus
+#[derive(Debug, Deserialize, Serialize)]
+pub struct JobStatus {
+    #[serde(rename(deserialize = "jobId", serialize = "jobId"))]
+    pub job_id: String,
+    pub did: String,
+    /// `JOB_STATE_COMPLETED`, `JOB_STATE_FAILED`, or a state the video is still going through
+    pub state: String,
+    /// Percentage of processing done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob: Option<Blob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl JobStatus {
+    pub const COMPLETED: &'static str = "JOB_STATE_COMPLETED";
+    pub const FAILED: &'static str = "JOB_STATE_FAILED";
+}
+
+///app.bsky.video.getJobStatus
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetJobStatusOutput {
+    #[serde(rename(deserialize = "jobStatus", serialize = "jobStatus"))]
+    pub job_status: JobStatus,
+}
+
+///app.bsky.video.uploadVideo
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UploadVideoOutput {
+    #[serde(rename(deserialize = "jobStatus", serialize = "jobStatus"))]
+    pub job_status: JobStatus,
+}
+
+///app.bsky.video.getUploadLimits
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetUploadLimitsOutput {
+    #[serde(rename(deserialize = "canUpload", serialize = "canUpload"))]
+    pub can_upload: bool,
+    #[serde(
+        rename(
+            deserialize = "remainingDailyVideos",
+            serialize = "remainingDailyVideos"
+        ),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remaining_daily_videos: Option<u64>,
+    #[serde(
+        rename(deserialize = "remainingDailyBytes", serialize = "remainingDailyBytes"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub remaining_daily_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
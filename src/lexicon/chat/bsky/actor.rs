@@ -0,0 +1,19 @@
+use crate::lexicon::app::bsky::actor::{Label, ProfileAssociated, ViewerState};
+use serde::Deserialize;
+
+///chat.bsky.actor.defs#profileViewBasic
+#[derive(Debug, Deserialize)]
+pub struct ProfileViewBasic {
+    pub did: String,
+    pub handle: String,
+    #[serde(rename(deserialize = "displayName"))]
+    pub display_name: Option<String>,
+    pub avatar: Option<String>,
+    pub associated: Option<ProfileAssociated>,
+    pub viewer: Option<ViewerState>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// The account can't be messaged, for example because its chat was disabled by moderators
+    #[serde(rename(deserialize = "chatDisabled"))]
+    pub chat_disabled: Option<bool>,
+}
@@ -0,0 +1,318 @@
+use super::actor::ProfileViewBasic;
+use crate::lexicon::app::bsky::embed::{Record as RecordEmbed, RecordView};
+use crate::lexicon::app::bsky::richtext::Facet;
+use crate::richtext::RichText;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+///the embed union of chat.bsky.convo.defs#messageInput
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum MessageEmbed {
+    #[serde(rename(
+        deserialize = "app.bsky.embed.record",
+        serialize = "app.bsky.embed.record"
+    ))]
+    Record(RecordEmbed),
+}
+
+///chat.bsky.convo.defs#messageInput
+#[derive(Debug, Serialize)]
+pub struct MessageInput {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets: Option<Vec<Facet>>,
+    /// A post or other record to share in the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embed: Option<MessageEmbed>,
+}
+
+impl MessageInput {
+    /// A plain text message
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            facets: None,
+            embed: None,
+        }
+    }
+}
+
+impl From<RichText> for MessageInput {
+    fn from(rich_text: RichText) -> Self {
+        Self {
+            text: rich_text.text,
+            facets: match rich_text.facets.is_empty() {
+                true => None,
+                false => Some(rich_text.facets),
+            },
+            embed: None,
+        }
+    }
+}
+
+///chat.bsky.convo.defs#messageViewSender
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageViewSender {
+    pub did: String,
+}
+
+///chat.bsky.convo.defs#reactionViewSender
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionViewSender {
+    pub did: String,
+}
+
+///chat.bsky.convo.defs#reactionView
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionView {
+    /// A single emoji
+    pub value: String,
+    pub sender: ReactionViewSender,
+    #[serde(rename(deserialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+}
+
+///the embed union of chat.bsky.convo.defs#messageView
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum MessageEmbedView {
+    #[serde(rename(deserialize = "app.bsky.embed.record#view"))]
+    Record(RecordView),
+    /// An embed of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///chat.bsky.convo.defs#messageView
+#[derive(Debug, Deserialize)]
+pub struct MessageView {
+    pub id: String,
+    pub rev: String,
+    pub text: String,
+    pub facets: Option<Vec<Facet>>,
+    pub embed: Option<MessageEmbedView>,
+    #[serde(default)]
+    pub reactions: Vec<ReactionView>,
+    pub sender: MessageViewSender,
+    #[serde(rename(deserialize = "sentAt"))]
+    pub sent_at: DateTime<Utc>,
+}
+
+///chat.bsky.convo.defs#deletedMessageView
+#[derive(Debug, Deserialize)]
+pub struct DeletedMessageView {
+    pub id: String,
+    pub rev: String,
+    pub sender: MessageViewSender,
+    #[serde(rename(deserialize = "sentAt"))]
+    pub sent_at: DateTime<Utc>,
+}
+
+///a message that may have been deleted, as found in conversations and logs
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum Message {
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#messageView"))]
+    View(Box<MessageView>),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#deletedMessageView"))]
+    Deleted(DeletedMessageView),
+    /// A message of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+impl Message {
+    /// The message's ID, if it is of a known type
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Message::View(message) => Some(&message.id),
+            Message::Deleted(message) => Some(&message.id),
+            Message::Unknown(_) => None,
+        }
+    }
+
+    /// The sender's DID, if the message is of a known type
+    pub fn sender(&self) -> Option<&str> {
+        match self {
+            Message::View(message) => Some(&message.sender.did),
+            Message::Deleted(message) => Some(&message.sender.did),
+            Message::Unknown(_) => None,
+        }
+    }
+}
+
+///chat.bsky.convo.defs#messageAndReactionView
+#[derive(Debug, Deserialize)]
+pub struct MessageAndReactionView {
+    pub message: MessageView,
+    pub reaction: ReactionView,
+}
+
+///chat.bsky.convo.defs#convoView
+#[derive(Debug, Deserialize)]
+pub struct ConvoView {
+    pub id: String,
+    pub rev: String,
+    pub members: Vec<ProfileViewBasic>,
+    #[serde(rename(deserialize = "lastMessage"))]
+    pub last_message: Option<Message>,
+    #[serde(rename(deserialize = "lastReaction"))]
+    pub last_reaction: Option<MessageAndReactionView>,
+    pub muted: bool,
+    /// `request` until you reply or accept it, then `accepted`
+    pub status: Option<String>,
+    #[serde(rename(deserialize = "unreadCount"))]
+    pub unread_count: usize,
+}
+
+///chat.bsky.convo.listConvos
+#[derive(Debug, Deserialize)]
+pub struct ListConvosOutput {
+    pub cursor: Option<String>,
+    pub convos: Vec<ConvoView>,
+}
+
+///the output of chat.bsky.convo.getConvo, getConvoForMembers, updateRead, muteConvo and
+///unmuteConvo
+#[derive(Debug, Deserialize)]
+pub struct ConvoOutput {
+    pub convo: ConvoView,
+}
+
+///chat.bsky.convo.getMessages
+#[derive(Debug, Deserialize)]
+pub struct GetMessagesOutput {
+    pub cursor: Option<String>,
+    pub messages: Vec<Message>,
+}
+
+///chat.bsky.convo.sendMessage#input
+#[derive(Debug, Serialize)]
+pub struct SendMessage<'a> {
+    #[serde(rename(serialize = "convoId"))]
+    pub convo_id: &'a str,
+    pub message: &'a MessageInput,
+}
+
+///chat.bsky.convo.sendMessageBatch#input
+#[derive(Debug, Serialize)]
+pub struct SendMessageBatch<'a> {
+    pub items: &'a [SendMessage<'a>],
+}
+
+///chat.bsky.convo.sendMessageBatch#output
+#[derive(Debug, Deserialize)]
+pub struct SendMessageBatchOutput {
+    pub items: Vec<MessageView>,
+}
+
+///chat.bsky.convo.deleteMessageForSelf#input
+#[derive(Debug, Serialize)]
+pub struct MessageRef<'a> {
+    #[serde(rename(serialize = "convoId"))]
+    pub convo_id: &'a str,
+    #[serde(rename(serialize = "messageId"))]
+    pub message_id: &'a str,
+}
+
+///chat.bsky.convo.addReaction#input, and the input of removeReaction
+#[derive(Debug, Serialize)]
+pub struct Reaction<'a> {
+    #[serde(rename(serialize = "convoId"))]
+    pub convo_id: &'a str,
+    #[serde(rename(serialize = "messageId"))]
+    pub message_id: &'a str,
+    /// A single emoji
+    pub value: &'a str,
+}
+
+///chat.bsky.convo.updateRead#input
+#[derive(Debug, Serialize)]
+pub struct UpdateRead<'a> {
+    #[serde(rename(serialize = "convoId"))]
+    pub convo_id: &'a str,
+    /// Mark messages up to this one as read, or all of them if it is missing
+    #[serde(
+        rename(serialize = "messageId"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub message_id: Option<&'a str>,
+}
+
+///chat.bsky.convo.muteConvo#input, and the input of unmuteConvo
+#[derive(Debug, Serialize)]
+pub struct ConvoRef<'a> {
+    #[serde(rename(serialize = "convoId"))]
+    pub convo_id: &'a str,
+}
+
+///the output of chat.bsky.convo.addReaction and removeReaction
+#[derive(Debug, Deserialize)]
+pub struct ReactionOutput {
+    pub message: MessageView,
+}
+
+///chat.bsky.convo.defs#logBeginConvo, and the other logs about a whole conversation
+#[derive(Debug, Deserialize)]
+pub struct LogConvo {
+    pub rev: String,
+    #[serde(rename(deserialize = "convoId"))]
+    pub convo_id: String,
+}
+
+///chat.bsky.convo.defs#logCreateMessage, and the other logs about a message
+#[derive(Debug, Deserialize)]
+pub struct LogMessage {
+    pub rev: String,
+    #[serde(rename(deserialize = "convoId"))]
+    pub convo_id: String,
+    pub message: Message,
+}
+
+///chat.bsky.convo.defs#logAddReaction, and logRemoveReaction
+#[derive(Debug, Deserialize)]
+pub struct LogReaction {
+    pub rev: String,
+    #[serde(rename(deserialize = "convoId"))]
+    pub convo_id: String,
+    pub message: Message,
+    pub reaction: ReactionView,
+}
+
+///the logs union of chat.bsky.convo.getLog
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum LogEvent {
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logBeginConvo"))]
+    BeginConvo(LogConvo),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logAcceptConvo"))]
+    AcceptConvo(LogConvo),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logLeaveConvo"))]
+    LeaveConvo(LogConvo),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logMuteConvo"))]
+    MuteConvo(LogConvo),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logUnmuteConvo"))]
+    UnmuteConvo(LogConvo),
+    /// A message was sent, by you or anyone else in the conversation
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logCreateMessage"))]
+    CreateMessage(LogMessage),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logDeleteMessage"))]
+    DeleteMessage(LogMessage),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logReadMessage"))]
+    ReadMessage(LogMessage),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logAddReaction"))]
+    AddReaction(LogReaction),
+    #[serde(rename(deserialize = "chat.bsky.convo.defs#logRemoveReaction"))]
+    RemoveReaction(LogReaction),
+    /// A log of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///chat.bsky.convo.getLog
+#[derive(Debug, Deserialize)]
+pub struct GetLogOutput {
+    pub cursor: Option<String>,
+    pub logs: Vec<LogEvent>,
+}
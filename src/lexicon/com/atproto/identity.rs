@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+///com.atproto.identity.resolveHandle
+#[derive(Debug, Deserialize)]
+pub struct ResolveHandleOutput {
+    pub did: String,
+}
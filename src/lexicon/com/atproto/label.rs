@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+///com.atproto.label.defs#selfLabel
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SelfLabel {
+    pub val: String,
+}
+
+///com.atproto.label.defs#selfLabels
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SelfLabels {
+    pub values: Vec<SelfLabel>,
+}
+
+///the labels union of records that can label themselves, such as app.bsky.feed.post,
+///app.bsky.actor.profile and app.bsky.graph.list
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum SelfLabelsUnion {
+    #[serde(rename(
+        deserialize = "com.atproto.label.defs#selfLabels",
+        serialize = "com.atproto.label.defs#selfLabels"
+    ))]
+    SelfLabels(SelfLabels),
+    /// Labels of a type this version doesn't know about
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+///com.atproto.label.defs#labelValueDefinitionStrings
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LabelValueDefinitionStrings {
+    pub lang: String,
+    pub name: String,
+    pub description: String,
+}
+
+///com.atproto.label.defs#labelValueDefinition
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LabelValueDefinition {
+    /// The label value, lowercase letters and `-` only
+    pub identifier: String,
+    /// `inform`, `alert` or `none`
+    pub severity: String,
+    /// `content`, `media` or `none`
+    pub blurs: String,
+    /// `ignore`, `warn` or `hide`
+    #[serde(
+        rename(deserialize = "defaultSetting", serialize = "defaultSetting"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_setting: Option<String>,
+    #[serde(
+        rename(deserialize = "adultOnly", serialize = "adultOnly"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub adult_only: Option<bool>,
+    pub locales: Vec<LabelValueDefinitionStrings>,
+}
@@ -1,2 +1,5 @@
+pub mod identity;
+pub mod label;
+pub mod moderation;
 pub mod repo;
 pub mod server;
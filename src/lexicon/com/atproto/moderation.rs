@@ -0,0 +1,112 @@
+use crate::lexicon::com::atproto::repo::StrongRef;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+///com.atproto.moderation.defs#reasonType
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum ReasonType {
+    /// Spam: frequent unwanted promotion, replies, mentions
+    Spam,
+    /// Direct violation of server rules, laws, terms of service
+    Violation,
+    /// Misleading identity, affiliation, or content
+    Misleading,
+    /// Unwanted or mislabeled sexual content
+    Sexual,
+    /// Rude, harassing, explicit, or otherwise unwelcoming behavior
+    Rude,
+    /// Other: reports not falling under another report category
+    Other,
+    /// Appeal: appeal a previously taken moderation action
+    Appeal,
+    /// A reason this version doesn't know about
+    Unknown(String),
+}
+
+impl ReasonType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ReasonType::Spam => "com.atproto.moderation.defs#reasonSpam",
+            ReasonType::Violation => "com.atproto.moderation.defs#reasonViolation",
+            ReasonType::Misleading => "com.atproto.moderation.defs#reasonMisleading",
+            ReasonType::Sexual => "com.atproto.moderation.defs#reasonSexual",
+            ReasonType::Rude => "com.atproto.moderation.defs#reasonRude",
+            ReasonType::Other => "com.atproto.moderation.defs#reasonOther",
+            ReasonType::Appeal => "com.atproto.moderation.defs#reasonAppeal",
+            ReasonType::Unknown(reason_type) => reason_type,
+        }
+    }
+}
+
+impl From<String> for ReasonType {
+    fn from(reason_type: String) -> Self {
+        match reason_type.as_str() {
+            "com.atproto.moderation.defs#reasonSpam" => ReasonType::Spam,
+            "com.atproto.moderation.defs#reasonViolation" => ReasonType::Violation,
+            "com.atproto.moderation.defs#reasonMisleading" => ReasonType::Misleading,
+            "com.atproto.moderation.defs#reasonSexual" => ReasonType::Sexual,
+            "com.atproto.moderation.defs#reasonRude" => ReasonType::Rude,
+            "com.atproto.moderation.defs#reasonOther" => ReasonType::Other,
+            "com.atproto.moderation.defs#reasonAppeal" => ReasonType::Appeal,
+            _ => ReasonType::Unknown(reason_type),
+        }
+    }
+}
+
+impl From<ReasonType> for String {
+    fn from(reason_type: ReasonType) -> Self {
+        match reason_type {
+            ReasonType::Unknown(reason_type) => reason_type,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+///com.atproto.moderation.createReport#subject
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "$type")]
+pub enum ReportSubject {
+    /// An account, by DID
+    #[serde(rename = "com.atproto.admin.defs#repoRef")]
+    Repo { did: String },
+    /// A record, such as a post, list or feed generator
+    #[serde(rename = "com.atproto.repo.strongRef")]
+    Record(StrongRef),
+}
+
+impl ReportSubject {
+    pub fn account(did: impl Into<String>) -> Self {
+        ReportSubject::Repo { did: did.into() }
+    }
+}
+
+impl From<StrongRef> for ReportSubject {
+    fn from(record: StrongRef) -> Self {
+        ReportSubject::Record(record)
+    }
+}
+
+///com.atproto.moderation.createReport#input
+#[derive(Debug, Serialize)]
+pub struct CreateReport<'a> {
+    #[serde(rename(serialize = "reasonType"))]
+    pub reason_type: &'a ReasonType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<&'a str>,
+    pub subject: &'a ReportSubject,
+}
+
+///com.atproto.moderation.createReport#output
+#[derive(Debug, Deserialize)]
+pub struct CreateReportOutput {
+    pub id: i64,
+    #[serde(rename(deserialize = "reasonType"))]
+    pub reason_type: ReasonType,
+    pub reason: Option<String>,
+    pub subject: ReportSubject,
+    #[serde(rename(deserialize = "reportedBy"))]
+    pub reported_by: String,
+    #[serde(rename(deserialize = "createdAt"))]
+    pub created_at: DateTime<Utc>,
+}
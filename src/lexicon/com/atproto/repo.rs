@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StrongRef {
     pub uri: String,
     pub cid: String,
@@ -32,6 +32,137 @@ pub struct CreateRecordOutput {
     pub uri: String,
 }
 
+///com.atproto.repo.defs#commitMeta
+#[derive(Debug, Deserialize)]
+pub struct CommitMeta {
+    pub cid: String,
+    pub rev: String,
+}
+
+///com.atproto.repo.getRecord
+#[derive(Debug, Deserialize)]
+pub struct GetRecordOutput<T> {
+    pub uri: String,
+    pub cid: Option<String>,
+    pub value: T,
+}
+
+///com.atproto.repo.putRecord
+#[derive(Serialize)]
+pub struct PutRecord<'a, T> {
+    pub repo: &'a str,
+    pub collection: &'a str,
+    pub rkey: &'a str,
+    pub record: T,
+    /// `Some(Some(cid))` only replaces the record if it is currently `cid`, `Some(None)` only
+    /// writes it if it doesn't exist yet
+    #[serde(
+        rename(serialize = "swapRecord"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub swap_record: Option<Option<&'a str>>,
+}
+
+///com.atproto.repo.putRecord
+#[derive(Debug, Deserialize)]
+pub struct PutRecordOutput {
+    pub uri: String,
+    pub cid: String,
+    pub commit: Option<CommitMeta>,
+}
+
+///the writes union of com.atproto.repo.applyWrites
+#[derive(Debug, Serialize)]
+#[serde(tag = "$type")]
+pub enum ApplyWrite<T> {
+    #[serde(rename(serialize = "com.atproto.repo.applyWrites#create"))]
+    Create {
+        collection: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rkey: Option<String>,
+        value: T,
+    },
+    #[serde(rename(serialize = "com.atproto.repo.applyWrites#update"))]
+    Update {
+        collection: String,
+        rkey: String,
+        value: T,
+    },
+    #[serde(rename(serialize = "com.atproto.repo.applyWrites#delete"))]
+    Delete { collection: String, rkey: String },
+}
+
+///com.atproto.repo.applyWrites
+#[derive(Serialize)]
+pub struct ApplyWrites<'a, T> {
+    pub repo: &'a str,
+    pub writes: &'a [ApplyWrite<T>],
+}
+
+///the results union of com.atproto.repo.applyWrites
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$type")]
+pub enum ApplyWriteResult {
+    #[serde(rename(deserialize = "com.atproto.repo.applyWrites#createResult"))]
+    Create { uri: String, cid: String },
+    #[serde(rename(deserialize = "com.atproto.repo.applyWrites#updateResult"))]
+    Update { uri: String, cid: String },
+    #[serde(rename(deserialize = "com.atproto.repo.applyWrites#deleteResult"))]
+    Delete {},
+}
+
+///com.atproto.repo.applyWrites
+#[derive(Debug, Deserialize)]
+pub struct ApplyWritesOutput {
+    pub commit: Option<CommitMeta>,
+    #[serde(default)]
+    pub results: Vec<ApplyWriteResult>,
+}
+
+///com.atproto.repo.deleteRecord
+#[derive(Serialize)]
+pub struct DeleteRecord<'a> {
+    pub repo: &'a str,
+    pub collection: &'a str,
+    pub rkey: &'a str,
+}
+
+///com.atproto.repo.deleteRecord
+#[derive(Debug, Deserialize)]
+pub struct DeleteRecordOutput {
+    pub commit: Option<CommitMeta>,
+}
+
+///com.atproto.repo.describeRepo
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DescribeRepoOutput {
+    pub handle: String,
+    pub did: String,
+    #[serde(rename(deserialize = "didDoc", serialize = "didDoc"))]
+    pub did_doc: serde_json::Value,
+    pub collections: Vec<String>,
+    #[serde(rename(deserialize = "handleIsCorrect", serialize = "handleIsCorrect"))]
+    pub handle_is_correct: bool,
+}
+
+impl DescribeRepoOutput {
+    /// The endpoint of the PDS hosting the repo, as listed in its DID document
+    pub fn pds_endpoint(&self) -> Option<&str> {
+        self.did_doc
+            .get("service")?
+            .as_array()?
+            .iter()
+            .find(|service| {
+                service
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .is_some_and(|id| id.ends_with("#atproto_pds"))
+            })?
+            .get("serviceEndpoint")?
+            .as_str()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateUploadBlob {
     pub blob: Vec<u8>,
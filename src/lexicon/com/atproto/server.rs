@@ -20,3 +20,9 @@ pub struct RefreshUserSession {
     #[serde(rename(deserialize = "refreshJwt"))]
     pub refresh_jwt: String,
 }
+
+///com.atproto.server.getServiceAuth
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GetServiceAuthOutput {
+    pub token: String,
+}
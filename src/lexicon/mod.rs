@@ -1,2 +1,3 @@
 pub mod app;
+pub mod chat;
 pub mod com;
@@ -1,5 +1,16 @@
 pub mod atproto;
 pub mod bluesky;
 pub mod errors;
+pub mod filter;
 pub mod lexicon;
+pub mod linkcard;
+pub mod media;
+pub mod moderation;
+pub mod notifications;
+pub mod post;
+pub mod profile;
+pub mod richtext;
 pub mod storage;
+#[cfg(test)]
+mod test_server;
+pub mod validation;
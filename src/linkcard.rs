@@ -0,0 +1,449 @@
+use crate::errors::BiskyError;
+use derive_builder::Builder;
+use reqwest::Url;
+use std::time::Duration;
+
+/// The title, description and preview image a page advertises through its OpenGraph and
+/// Twitter card meta tags, falling back to `<title>` and `<meta name="description">`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute URL of the preview image
+    pub image: Option<Url>,
+}
+
+/// Fetches pages and preview images for link cards, with limits on how long a fetch may take
+/// and how much of the response is read
+#[derive(Debug, Clone, Builder)]
+pub struct LinkCardFetcher {
+    #[builder(default = "Duration::from_secs(10)")]
+    timeout: Duration,
+    /// Pages are cut off after this many bytes; the meta tags are expected in `<head>`
+    #[builder(default = "1_000_000")]
+    max_page_size: usize,
+    /// Preview images larger than this are not downloaded
+    #[builder(default = "10_000_000")]
+    max_image_size: usize,
+    #[builder(default = r#""Mozilla/5.0 (compatible; bisky)".to_string()"#)]
+    user_agent: String,
+}
+
+impl Default for LinkCardFetcher {
+    fn default() -> Self {
+        LinkCardFetcherBuilder::default().build().unwrap()
+    }
+}
+
+impl LinkCardFetcher {
+    fn http_client(&self) -> Result<reqwest::Client, BiskyError> {
+        Ok(reqwest::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(&self.user_agent)
+            .build()?)
+    }
+
+    /// Read at most `limit` bytes of `url`. If `truncate` is false a longer body is an error
+    async fn get_capped(
+        &self,
+        url: &Url,
+        limit: usize,
+        truncate: bool,
+    ) -> Result<(Url, Vec<u8>), BiskyError> {
+        let mut response = self
+            .http_client()?
+            .get(url.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+        let final_url = response.url().clone();
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > limit {
+                if !truncate {
+                    return Err(BiskyError::ResponseTooLarge(limit));
+                }
+                body.truncate(limit);
+                break;
+            }
+        }
+
+        Ok((final_url, body))
+    }
+
+    /// Fetch a page and read its link card metadata
+    pub async fn fetch_metadata(&self, url: &Url) -> Result<LinkMetadata, BiskyError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(BiskyError::InvalidUrl(url.to_string()));
+        }
+
+        let (final_url, body) = self.get_capped(url, self.max_page_size, true).await?;
+        Ok(parse_metadata(&String::from_utf8_lossy(&body), &final_url))
+    }
+
+    /// Download a preview image
+    pub async fn fetch_image(&self, url: &Url) -> Result<Vec<u8>, BiskyError> {
+        self.get_capped(url, self.max_image_size, false)
+            .await
+            .map(|(_, body)| body)
+    }
+}
+
+/// Read link card metadata from a page. Relative image URLs are resolved against `base`
+pub fn parse_metadata(html: &str, base: &Url) -> LinkMetadata {
+    let mut og = LinkMetadata::default();
+    let mut twitter = LinkMetadata::default();
+    let mut fallback = LinkMetadata::default();
+    let mut og_image = None;
+    let mut twitter_image = None;
+
+    let lowercase = html.to_ascii_lowercase();
+    let mut position = 0;
+
+    while let Some(offset) = lowercase[position..].find("<meta") {
+        let start = position + offset + "<meta".len();
+        let end = lowercase[start..]
+            .find('>')
+            .map_or(html.len(), |end| start + end);
+        position = end;
+
+        let attributes = parse_attributes(&html[start..end]);
+        let get = |name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let Some(content) = get("content").map(str::trim).filter(|c| !c.is_empty()) else {
+            continue;
+        };
+        let key = get("property")
+            .or_else(|| get("name"))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        let content = content.to_string();
+        match key.as_str() {
+            "og:title" => og.title = og.title.or(Some(content)),
+            "og:description" => og.description = og.description.or(Some(content)),
+            "og:image" | "og:image:url" | "og:image:secure_url" => {
+                og_image = og_image.or(Some(content))
+            }
+            "twitter:title" => twitter.title = twitter.title.or(Some(content)),
+            "twitter:description" => twitter.description = twitter.description.or(Some(content)),
+            "twitter:image" | "twitter:image:src" => {
+                twitter_image = twitter_image.or(Some(content))
+            }
+            "description" => fallback.description = fallback.description.or(Some(content)),
+            _ => {}
+        }
+    }
+
+    if let Some(start) = lowercase.find("<title") {
+        if let Some(open_end) = lowercase[start..].find('>') {
+            let text_start = start + open_end + 1;
+            if let Some(text_end) = lowercase[text_start..].find("</title") {
+                let title = decode_entities(html[text_start..text_start + text_end].trim());
+                if !title.is_empty() {
+                    fallback.title = Some(title);
+                }
+            }
+        }
+    }
+
+    LinkMetadata {
+        title: og.title.or(twitter.title).or(fallback.title),
+        description: og
+            .description
+            .or(twitter.description)
+            .or(fallback.description),
+        image: og_image
+            .or(twitter_image)
+            .and_then(|image| base.join(&image).ok())
+            .filter(|image| matches!(image.scheme(), "http" | "https")),
+    }
+}
+
+/// Parse the attributes of a tag, given the text between its name and the closing `>`.
+/// Names are lowercased and values have their entities decoded
+fn parse_attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = tag.trim_start();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            rest = after_equals.trim_start();
+            let (raw, remaining) = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &rest[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..end], inner.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                    (&rest[..end], &rest[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining;
+        } else if name.is_empty() {
+            // a stray `/` or similar
+            rest = &rest[1..];
+        }
+
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+
+    attributes
+}
+
+/// Decode the character references that commonly appear in titles and descriptions
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    fn base() -> Url {
+        "https://example.com/blog/post".parse().unwrap()
+    }
+
+    #[test]
+    fn opengraph_over_twitter_over_fallback() {
+        let html = r#"<html><head>
+            <title>Fallback title</title>
+            <meta name="description" content="Fallback description">
+            <meta name="twitter:title" content="Twitter title">
+            <meta name="twitter:description" content="Twitter description">
+            <meta name="twitter:image" content="https://cdn.example.com/twitter.png">
+            <meta property="og:title" content="OpenGraph title">
+            <meta property="og:image" content="https://cdn.example.com/og.png">
+            <meta property="og:title" content="A second OpenGraph title">
+        </head></html>"#;
+
+        assert_eq!(
+            parse_metadata(html, &base()),
+            LinkMetadata {
+                title: Some("OpenGraph title".to_string()),
+                description: Some("Twitter description".to_string()),
+                image: Some("https://cdn.example.com/og.png".parse().unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn falls_back_to_title_and_description() {
+        let html = r#"<title>
+            Just a page </title><meta name="description" content="  Plain  "><meta property="og:title" content="">"#;
+
+        assert_eq!(
+            parse_metadata(html, &base()),
+            LinkMetadata {
+                title: Some("Just a page".to_string()),
+                description: Some("Plain".to_string()),
+                image: None,
+            }
+        );
+    }
+
+    #[test]
+    fn resolves_relative_images() {
+        let image = |content: &str| {
+            let html = format!(r#"<meta property="og:image" content="{content}">"#);
+            parse_metadata(&html, &base()).image.map(String::from)
+        };
+
+        assert_eq!(
+            image("/img/card.png").as_deref(),
+            Some("https://example.com/img/card.png")
+        );
+        assert_eq!(
+            image("card.png").as_deref(),
+            Some("https://example.com/blog/card.png")
+        );
+        assert_eq!(
+            image("//cdn.example.com/card.png").as_deref(),
+            Some("https://cdn.example.com/card.png")
+        );
+        assert_eq!(image("data:image/png;base64,AAAA"), None);
+        assert_eq!(image("javascript:alert(1)"), None);
+    }
+
+    #[test]
+    fn decodes_entities() {
+        let cases = [
+            ("Tom &amp; Jerry", "Tom & Jerry"),
+            ("&lt;b&gt; &quot;x&quot; &apos;y&apos;", "<b> \"x\" 'y'"),
+            ("caf&#233; &#xE9;&#XE9; &#x1F980;", "café éé 🦀"),
+            ("a&nbsp;b", "a\u{a0}b"),
+            // unknown, unterminated and invalid references are left alone
+            ("&copy; AT&T &#xD800; &amp", "&copy; AT&T &#xD800; &amp"),
+            ("&", "&"),
+        ];
+
+        for (encoded, decoded) in cases {
+            assert_eq!(decode_entities(encoded), decoded);
+        }
+    }
+
+    #[test]
+    fn reads_uppercase_and_unquoted_attributes() {
+        let html = "<HEAD><TITLE>Ignored</TITLE>\
+            <META PROPERTY=OG:TITLE CONTENT='Ünïcödé &amp; more'>\
+            <Meta Name=\"Twitter:Description\" Content=\"日本語の説明\"/>\
+            <meta content=\"https://example.com/é.png\" property=\"og:image\" />";
+
+        assert_eq!(
+            parse_metadata(html, &base()),
+            LinkMetadata {
+                title: Some("Ünïcödé & more".to_string()),
+                description: Some("日本語の説明".to_string()),
+                image: Some("https://example.com/%C3%A9.png".parse().unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn non_ascii_before_meta_tags() {
+        // lowercasing must not shift byte offsets between `html` and its lowercase copy
+        let html = "<title>İstanbul ẞ</title><meta property=og:description content=\"Ǆ\">";
+
+        let metadata = parse_metadata(html, &base());
+        assert_eq!(metadata.title.as_deref(), Some("İstanbul ẞ"));
+        assert_eq!(metadata.description.as_deref(), Some("Ǆ"));
+    }
+
+    #[tokio::test]
+    async fn fetches_metadata_from_the_final_url() {
+        let server = TestServer::start(|request| match request.path() {
+            "/start" => Response::new(301, "text/plain", "").header("location", "/articles/1"),
+            _ => Response::html(r#"<meta property="og:image" content="card.png">"#),
+        });
+
+        let metadata = LinkCardFetcher::default()
+            .fetch_metadata(&server.url.join("start").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            metadata.image,
+            Some(server.url.join("articles/card.png").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn truncates_long_pages() {
+        let page = format!(
+            r#"<meta property="og:title" content="Early">{}<meta property="og:description" content="Late">"#,
+            " ".repeat(10_000)
+        );
+        let server = TestServer::start(move |_| Response::html(page.clone()));
+
+        let metadata = LinkCardFetcherBuilder::default()
+            .max_page_size(1000)
+            .build()
+            .unwrap()
+            .fetch_metadata(&server.url)
+            .await
+            .unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Early"));
+        assert_eq!(metadata.description, None);
+    }
+
+    #[tokio::test]
+    async fn rejects_large_images() {
+        let server = TestServer::start(|_| Response::new(200, "image/png", vec![0; 5000]));
+        let fetcher = LinkCardFetcherBuilder::default()
+            .max_image_size(4999)
+            .build()
+            .unwrap();
+
+        let error = fetcher.fetch_image(&server.url).await.unwrap_err();
+        assert!(matches!(error, BiskyError::ResponseTooLarge(4999)));
+
+        let fetcher = LinkCardFetcherBuilder::default()
+            .max_image_size(5000)
+            .build()
+            .unwrap();
+        assert_eq!(fetcher.fetch_image(&server.url).await.unwrap().len(), 5000);
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let server = TestServer::start(|_| {
+            Response::html("<title>Slow</title>").delay(Duration::from_secs(2))
+        });
+
+        let error = LinkCardFetcherBuilder::default()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap()
+            .fetch_metadata(&server.url)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, BiskyError::ReqwestError(e) if e.is_timeout()));
+    }
+
+    #[tokio::test]
+    async fn rejects_other_schemes() {
+        let error = LinkCardFetcher::default()
+            .fetch_metadata(&"ftp://example.com/".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, BiskyError::InvalidUrl(_)));
+    }
+}
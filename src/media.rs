@@ -0,0 +1,229 @@
+use crate::errors::BiskyError;
+use crate::lexicon::app::bsky::embed::AspectRatio;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::io::Cursor;
+
+/// The longest side the official clients scale images down to before uploading
+pub const MAX_IMAGE_DIMENSION: u32 = 2000;
+
+/// JPEG qualities tried, best first, before an image is scaled down further
+const JPEG_QUALITIES: [u8; 4] = [90, 80, 70, 60];
+
+/// An image re-encoded without metadata and small enough to upload
+#[derive(Debug)]
+pub struct PreparedImage {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+    pub aspect_ratio: AspectRatio,
+}
+
+/// Decode an image of any supported format (JPEG, PNG, GIF or WebP, sniffed from its content),
+/// rotate it according to its EXIF orientation, and re-encode it until it is at most
+/// `max_size` bytes. Re-encoding drops all metadata, including EXIF and GPS data. Images with
+/// transparency are kept as PNG, everything else becomes JPEG; animated images keep only their
+/// first frame
+pub fn prepare_image(data: &[u8], max_size: usize) -> Result<PreparedImage, BiskyError> {
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let aspect_ratio = AspectRatio {
+        width: image.width(),
+        height: image.height(),
+    };
+
+    if image.width() > MAX_IMAGE_DIMENSION || image.height() > MAX_IMAGE_DIMENSION {
+        image = image.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            FilterType::Lanczos3,
+        );
+    }
+
+    loop {
+        if image.has_alpha() {
+            let mut data = Vec::new();
+            image.write_with_encoder(PngEncoder::new_with_quality(
+                &mut data,
+                CompressionType::Best,
+                PngFilterType::Adaptive,
+            ))?;
+            if data.len() <= max_size {
+                return Ok(PreparedImage {
+                    data,
+                    mime_type: "image/png",
+                    aspect_ratio,
+                });
+            }
+        } else {
+            let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+            for quality in JPEG_QUALITIES {
+                let mut data = Vec::new();
+                rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut data, quality))?;
+                if data.len() <= max_size {
+                    return Ok(PreparedImage {
+                        data,
+                        mime_type: "image/jpeg",
+                        aspect_ratio,
+                    });
+                }
+            }
+        }
+
+        if image.width() <= 1 && image.height() <= 1 {
+            return Err(BiskyError::ImageTooLarge(max_size));
+        }
+        let (width, height) = (image.width() * 3 / 4, image.height() * 3 / 4);
+        image = image.resize(width.max(1), height.max(1), FilterType::Lanczos3);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn encode(image: impl Into<DynamicImage>, format: ImageFormat) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        image.into().write_to(&mut data, format).unwrap();
+        data.into_inner()
+    }
+
+    fn decode(prepared: &PreparedImage) -> DynamicImage {
+        image::load_from_memory(&prepared.data).unwrap()
+    }
+
+    /// Colour noise, which JPEG can't compress well
+    fn noise(width: u32, height: u32) -> RgbImage {
+        let mut state = 1u32;
+        RgbImage::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let [r, g, b, _] = state.to_be_bytes();
+            Rgb([r, g, b])
+        })
+    }
+
+    /// Insert an EXIF segment with the given orientation straight after the JPEG start marker
+    fn with_orientation(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        // Big endian header with the first IFD at offset 8, then an IFD with a single SHORT
+        // Orientation entry and no next IFD
+        let tiff: Vec<u8> = [
+            &b"MM\0\x2a\0\0\0\x08"[..],
+            &[0, 1],
+            &[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation, 0, 0],
+            &[0, 0, 0, 0],
+        ]
+        .concat();
+        let length = (2 + 6 + tiff.len()) as u16;
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xff, 0xe1]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(b"Exif\0\0");
+        data.extend_from_slice(&tiff);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    #[test]
+    fn applies_exif_orientation() {
+        // Red on the left, blue on the right
+        let image = RgbImage::from_fn(40, 20, |x, _| {
+            if x < 20 {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 255])
+            }
+        });
+        // 6 means the image has to be turned 90° clockwise to display it
+        let data = with_orientation(&encode(image, ImageFormat::Jpeg), 6);
+
+        let prepared = prepare_image(&data, usize::MAX).unwrap();
+        assert_eq!(
+            prepared.aspect_ratio,
+            AspectRatio {
+                width: 20,
+                height: 40
+            }
+        );
+        let rotated = decode(&prepared).to_rgb8();
+        assert_eq!(rotated.dimensions(), (20, 40));
+        let [r, _, b] = rotated.get_pixel(10, 5).0;
+        assert!(r > 200 && b < 50, "top should be red");
+        let [r, _, b] = rotated.get_pixel(10, 35).0;
+        assert!(r < 50 && b > 200, "bottom should be blue");
+    }
+
+    #[test]
+    fn scales_down_to_the_maximum_dimension() {
+        let data = encode(RgbImage::new(2400, 600), ImageFormat::Png);
+
+        let prepared = prepare_image(&data, usize::MAX).unwrap();
+        assert_eq!(prepared.mime_type, "image/jpeg");
+        // The aspect ratio is of the original, which has the same proportions
+        assert_eq!(
+            prepared.aspect_ratio,
+            AspectRatio {
+                width: 2400,
+                height: 600
+            }
+        );
+        let image = decode(&prepared);
+        assert_eq!((image.width(), image.height()), (MAX_IMAGE_DIMENSION, 500));
+
+        let small = encode(RgbImage::new(300, 200), ImageFormat::Png);
+        let image = decode(&prepare_image(&small, usize::MAX).unwrap());
+        assert_eq!((image.width(), image.height()), (300, 200));
+    }
+
+    #[test]
+    fn keeps_transparent_images_as_png() {
+        let image = RgbaImage::from_fn(30, 30, |x, _| Rgba([255, 0, 0, (x * 8) as u8]));
+        let data = encode(image, ImageFormat::Png);
+
+        let prepared = prepare_image(&data, usize::MAX).unwrap();
+        assert_eq!(prepared.mime_type, "image/png");
+        assert!(decode(&prepared).has_alpha());
+
+        let opaque = encode(RgbImage::new(30, 30), ImageFormat::Png);
+        let prepared = prepare_image(&opaque, usize::MAX).unwrap();
+        assert_eq!(prepared.mime_type, "image/jpeg");
+    }
+
+    #[test]
+    fn shrinks_until_it_fits() {
+        let data = encode(noise(200, 200), ImageFormat::Png);
+        let max_size = 5000;
+
+        let prepared = prepare_image(&data, max_size).unwrap();
+        assert!(prepared.data.len() <= max_size);
+        let image = decode(&prepared);
+        assert!(image.width() < 200 && image.width() == image.height());
+        assert_eq!(
+            prepared.aspect_ratio,
+            AspectRatio {
+                width: 200,
+                height: 200
+            }
+        );
+    }
+
+    #[test]
+    fn fails_when_it_cannot_fit() {
+        let data = encode(noise(50, 50), ImageFormat::Png);
+        assert!(matches!(
+            prepare_image(&data, 10),
+            Err(BiskyError::ImageTooLarge(10))
+        ));
+
+        assert!(matches!(
+            prepare_image(b"not an image", usize::MAX),
+            Err(BiskyError::ImageError(_))
+        ));
+    }
+}
@@ -0,0 +1,1290 @@
+use crate::lexicon::app::bsky::actor::{
+    Label, MutedWord, Preference, Preferences, ProfileView, ProfileViewBasic, ProfileViewDetailed,
+    ViewerState,
+};
+use crate::lexicon::app::bsky::embed::Media;
+use crate::lexicon::app::bsky::feed::{Embeds, Post, PostView};
+use crate::lexicon::app::bsky::labeler::LabelerViewDetailed;
+use crate::lexicon::app::bsky::richtext::FacetFeature;
+use crate::lexicon::com::atproto::label::LabelValueDefinition;
+use chrono::Utc;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The DID of Bluesky's own moderation service, which every account is subscribed to
+pub const BSKY_MODERATION_DID: &str = "did:plc:ar7c4by46qjdydhdevvrndac";
+
+/// Languages that aren't written with spaces between words, so muted words are matched anywhere
+/// in the text rather than as whole words
+const MUTED_WORD_LANGUAGE_EXCEPTIONS: [&str; 5] = ["ja", "zh", "ko", "th", "vi"];
+
+/// Where a post or profile is being shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModerationContext {
+    /// In a list of accounts, such as followers or search results
+    ProfileList,
+    /// On the account's profile page
+    ProfileView,
+    Avatar,
+    Banner,
+    DisplayName,
+    /// In a feed or other list of posts
+    ContentList,
+    /// A post shown on its own, such as the focused post of a thread
+    ContentView,
+    /// The images, video or link card of a post
+    ContentMedia,
+}
+
+/// How severe each cause is, most severe first, as the official clients rank them
+const PRIORITY_NO_OVERRIDE_LABEL: u8 = 1;
+const PRIORITY_HIDE_LABEL: u8 = 2;
+const PRIORITY_BLOCKING: u8 = 3;
+const PRIORITY_BLOCKED_BY: u8 = 4;
+/// Labels that blur a profile or post shown on its own
+const PRIORITY_BLUR_VIEW_LABEL: u8 = 5;
+/// Muted accounts and words, and hidden posts
+const PRIORITY_MUTED: u8 = 6;
+/// Labels that blur only in lists, or only media
+const PRIORITY_BLUR_LIST_LABEL: u8 = 7;
+/// Labels that only alert or inform
+const PRIORITY_OTHER_LABEL: u8 = 8;
+
+const CONTEXTS: [ModerationContext; 8] = [
+    ModerationContext::ProfileList,
+    ModerationContext::ProfileView,
+    ModerationContext::Avatar,
+    ModerationContext::Banner,
+    ModerationContext::DisplayName,
+    ModerationContext::ContentList,
+    ModerationContext::ContentView,
+    ModerationContext::ContentMedia,
+];
+
+/// What the viewer wants done with a label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelVisibility {
+    Ignore,
+    Warn,
+    Hide,
+}
+
+impl LabelVisibility {
+    /// Parse the visibility of a `ContentLabelPref` or the default setting of a label definition
+    pub fn parse(visibility: &str) -> Option<Self> {
+        match visibility {
+            "ignore" | "show" => Some(LabelVisibility::Ignore),
+            "warn" => Some(LabelVisibility::Warn),
+            "hide" => Some(LabelVisibility::Hide),
+            _ => None,
+        }
+    }
+}
+
+/// What a label was applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelTarget {
+    /// The account as a whole, labelled by DID
+    Account,
+    /// The account's profile record, affecting only its avatar, banner and display name
+    Profile,
+    /// A post or other record
+    Content,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Behavior {
+    Blur,
+    Alert,
+    Inform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LabelBehaviors {
+    /// `!hide` and `!no-unauthenticated`, which blur everything about what they're on
+    Hide,
+    /// `!warn`, which is `Hide` except for an account's display name
+    Warn,
+    /// A label described by its definition: the severity gives an alert or inform, if any, and
+    /// the blurs whether content, media or nothing is blurred
+    Defined {
+        severity: Option<Behavior>,
+        blurs: Option<LabelBlurs>,
+        adult_only: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LabelBlurs {
+    Content,
+    Media,
+}
+
+/// How a label value is treated, from a labeler's definition or one of the global labels
+#[derive(Debug, Clone)]
+struct LabelDefinition {
+    configurable: bool,
+    default_setting: LabelVisibility,
+    adult: bool,
+    no_override: bool,
+    no_self: bool,
+    unauthed: bool,
+    behaviors: LabelBehaviors,
+}
+
+impl LabelDefinition {
+    /// The labels every labeler may apply, with the meaning the official clients give them
+    fn global(value: &str) -> Option<Self> {
+        let media = |default_setting, adult| LabelDefinition {
+            configurable: true,
+            default_setting,
+            adult,
+            no_override: false,
+            no_self: false,
+            unauthed: false,
+            behaviors: LabelBehaviors::Defined {
+                severity: None,
+                blurs: Some(LabelBlurs::Media),
+                adult_only: adult,
+            },
+        };
+        let system = |default_setting, behaviors, no_override, no_self, unauthed| LabelDefinition {
+            configurable: false,
+            default_setting,
+            adult: false,
+            no_override,
+            no_self,
+            unauthed,
+            behaviors,
+        };
+
+        Some(match value {
+            "!hide" => system(
+                LabelVisibility::Hide,
+                LabelBehaviors::Hide,
+                true,
+                true,
+                false,
+            ),
+            "!warn" => system(
+                LabelVisibility::Warn,
+                LabelBehaviors::Warn,
+                false,
+                true,
+                false,
+            ),
+            "!no-unauthenticated" => system(
+                LabelVisibility::Hide,
+                LabelBehaviors::Hide,
+                true,
+                false,
+                true,
+            ),
+            "porn" => media(LabelVisibility::Hide, true),
+            "sexual" => media(LabelVisibility::Warn, true),
+            "graphic-media" | "gore" => media(LabelVisibility::Warn, true),
+            "nudity" => media(LabelVisibility::Ignore, false),
+            _ => return None,
+        })
+    }
+
+    fn from_definition(definition: &LabelValueDefinition) -> Self {
+        let adult_only = definition.adult_only.unwrap_or(false);
+        LabelDefinition {
+            configurable: true,
+            default_setting: definition
+                .default_setting
+                .as_deref()
+                .and_then(LabelVisibility::parse)
+                .unwrap_or(LabelVisibility::Warn),
+            adult: adult_only,
+            no_override: false,
+            no_self: false,
+            unauthed: false,
+            behaviors: LabelBehaviors::Defined {
+                severity: match definition.severity.as_str() {
+                    "alert" => Some(Behavior::Alert),
+                    "inform" => Some(Behavior::Inform),
+                    _ => None,
+                },
+                blurs: match definition.blurs.as_str() {
+                    "content" => Some(LabelBlurs::Content),
+                    "media" => Some(LabelBlurs::Media),
+                    _ => None,
+                },
+                adult_only,
+            },
+        }
+    }
+
+    fn behavior(&self, target: LabelTarget, context: ModerationContext) -> Option<Behavior> {
+        use LabelTarget::{Account, Content, Profile};
+        use ModerationContext::*;
+
+        match self.behaviors {
+            LabelBehaviors::Hide | LabelBehaviors::Warn => {
+                let blurs = match target {
+                    Account => {
+                        context != ContentMedia
+                            && !(self.behaviors == LabelBehaviors::Warn && context == DisplayName)
+                    }
+                    Profile => matches!(context, Avatar | Banner | DisplayName),
+                    Content => matches!(context, ContentList | ContentView),
+                };
+                blurs.then_some(Behavior::Blur)
+            }
+            LabelBehaviors::Defined {
+                severity,
+                blurs,
+                adult_only,
+            } => match (blurs, target, context) {
+                (_, Account | Profile, ProfileList | ProfileView) => severity,
+                (Some(LabelBlurs::Content), Account | Content, ContentList) => Some(Behavior::Blur),
+                (Some(LabelBlurs::Content), Account | Content, ContentView) => match adult_only {
+                    true => Some(Behavior::Blur),
+                    false => severity,
+                },
+                (Some(LabelBlurs::Media), Account | Profile, Avatar | Banner) => {
+                    Some(Behavior::Blur)
+                }
+                (Some(LabelBlurs::Media), Content, ContentMedia) => Some(Behavior::Blur),
+                (None, Account | Content, ContentList | ContentView) => severity,
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A labeler the viewer is subscribed to
+#[derive(Debug, Clone)]
+pub struct ModerationLabeler {
+    pub did: String,
+    /// The viewer's settings for this labeler's own labels, by label value
+    pub labels: HashMap<String, LabelVisibility>,
+    /// The labeler's own label values, see `Bluesky::bsky_get_labeler_services`
+    pub definitions: Vec<LabelValueDefinition>,
+}
+
+/// Everything the decisions depend on besides the post or profile itself. Blocks and mutes of
+/// accounts come from the viewer state in the views instead
+#[derive(Debug, Clone, Default)]
+pub struct ModerationOptions {
+    /// The viewer, whose own posts and profile are never filtered. `None` when logged out
+    pub user_did: Option<String>,
+    pub adult_content_enabled: bool,
+    /// The viewer's settings for the global labels such as `porn`, by label value
+    pub labels: HashMap<String, LabelVisibility>,
+    /// Labels from labelers other than these are ignored
+    pub labelers: Vec<ModerationLabeler>,
+    pub muted_words: Vec<MutedWord>,
+    /// URIs of posts the viewer has hidden
+    pub hidden_posts: Vec<String>,
+}
+
+impl ModerationOptions {
+    /// Gather the options from an account's preferences and the detailed views of the labelers it
+    /// is subscribed to. Bluesky's moderation service is always included
+    pub fn from_preferences(
+        user_did: &str,
+        preferences: &Preferences,
+        labelers: &[LabelerViewDetailed],
+    ) -> Self {
+        let mut options = ModerationOptions {
+            user_did: Some(user_did.to_string()),
+            ..Default::default()
+        };
+        let mut labeler_dids = Vec::from([BSKY_MODERATION_DID.to_string()]);
+        let mut labeler_labels = Vec::new();
+
+        for preference in &preferences.preferences {
+            match preference {
+                Preference::AdultContent(adult_content) => {
+                    options.adult_content_enabled = adult_content.enabled
+                }
+                Preference::ContentLabel(content_label) => {
+                    let Some(visibility) = LabelVisibility::parse(&content_label.visibility) else {
+                        continue;
+                    };
+                    match &content_label.labeler_did {
+                        Some(did) => labeler_labels.push((
+                            did.clone(),
+                            content_label.label.clone(),
+                            visibility,
+                        )),
+                        None => {
+                            options
+                                .labels
+                                .insert(content_label.label.clone(), visibility);
+                        }
+                    }
+                }
+                Preference::MutedWords(muted_words) => options
+                    .muted_words
+                    .extend(muted_words.items.iter().cloned()),
+                Preference::HiddenPosts(hidden_posts) => options
+                    .hidden_posts
+                    .extend(hidden_posts.items.iter().cloned()),
+                Preference::Labelers(labelers) => {
+                    for labeler in &labelers.labelers {
+                        if !labeler_dids.contains(&labeler.did) {
+                            labeler_dids.push(labeler.did.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        options.labelers = labeler_dids
+            .into_iter()
+            .map(|did| ModerationLabeler {
+                labels: labeler_labels
+                    .iter()
+                    .filter(|(labeler, _, _)| *labeler == did)
+                    .map(|(_, label, visibility)| (label.clone(), *visibility))
+                    .collect(),
+                definitions: labelers
+                    .iter()
+                    .find(|view| view.creator.did == did)
+                    .map(|view| view.policies.label_value_definitions.clone())
+                    .unwrap_or_default(),
+                did,
+            })
+            .collect();
+
+        options
+    }
+
+    /// DIDs of the subscribed labelers, for `Bluesky::set_labelers`
+    pub fn labeler_dids(&self) -> Vec<String> {
+        self.labelers
+            .iter()
+            .map(|labeler| labeler.did.clone())
+            .collect()
+    }
+
+    /// Decide how to show a post, taking in its author's account as well
+    pub fn decide_post(&self, post: &PostView) -> ModerationDecision {
+        let mut decision = self.decide_account(&post.author.did, post.author.viewer.as_ref());
+        self.add_labels(&mut decision, &post.author.did, &post.author.labels);
+        self.add_labels(&mut decision, &post.author.did, &post.labels);
+
+        let following = post
+            .author
+            .viewer
+            .as_ref()
+            .is_some_and(|viewer| viewer.following.is_some());
+        if let Some(word) = find_muted_word(&self.muted_words, &post.record, following) {
+            decision.add(
+                ModerationCause::MutedWord(word.value.clone()),
+                PRIORITY_MUTED,
+                Vec::new(),
+            );
+        }
+        if self.hidden_posts.contains(&post.uri) {
+            decision.add(ModerationCause::Hidden, PRIORITY_MUTED, Vec::new());
+        }
+
+        decision
+    }
+
+    /// Decide how to show an account's profile
+    pub fn decide_profile(&self, profile: &impl ModeratedProfile) -> ModerationDecision {
+        let mut decision = self.decide_account(profile.did(), profile.viewer());
+        self.add_labels(&mut decision, profile.did(), profile.labels());
+        decision
+    }
+
+    fn decide_account(&self, did: &str, viewer: Option<&ViewerState>) -> ModerationDecision {
+        let mut decision = ModerationDecision {
+            is_me: self.user_did.as_deref() == Some(did),
+            causes: Vec::new(),
+        };
+
+        if let Some(viewer) = viewer {
+            if viewer.blocking.is_some() || viewer.blocking_by_list.is_some() {
+                let by_list = viewer
+                    .blocking_by_list
+                    .as_ref()
+                    .map(|list| list.uri.clone());
+                decision.add(
+                    ModerationCause::Blocking { by_list },
+                    PRIORITY_BLOCKING,
+                    Vec::new(),
+                );
+            }
+            if viewer.blocked_by == Some(true) {
+                decision.add(ModerationCause::BlockedBy, PRIORITY_BLOCKED_BY, Vec::new());
+            }
+            if viewer.muted == Some(true) {
+                let by_list = viewer.muted_by_list.as_ref().map(|list| list.uri.clone());
+                decision.add(
+                    ModerationCause::Muted { by_list },
+                    PRIORITY_MUTED,
+                    Vec::new(),
+                );
+            }
+        }
+
+        decision
+    }
+
+    fn add_labels(&self, decision: &mut ModerationDecision, did: &str, labels: &[Label]) {
+        let now = Utc::now();
+        for label in labels {
+            if label.neg || label.exp.is_some_and(|exp| exp <= now) {
+                continue;
+            }
+            self.add_label(decision, did, label);
+        }
+    }
+
+    fn add_label(&self, decision: &mut ModerationDecision, did: &str, label: &Label) {
+        let target = if label.uri.starts_with("did:") {
+            LabelTarget::Account
+        } else if label.uri.ends_with("/app.bsky.actor.profile/self") {
+            LabelTarget::Profile
+        } else {
+            LabelTarget::Content
+        };
+
+        // self-labels such as `porn` on your own post always count, but other labels only
+        // count when they come from a labeler the viewer is subscribed to
+        let labeler = self
+            .labelers
+            .iter()
+            .find(|labeler| labeler.did == label.src);
+        if labeler.is_none() && label.src != did {
+            return;
+        }
+
+        let global = LabelDefinition::global(&label.val);
+        let is_global = global.is_some();
+        let Some(definition) = global.or_else(|| {
+            labeler?
+                .definitions
+                .iter()
+                .find(|definition| definition.identifier == label.val)
+                .map(LabelDefinition::from_definition)
+        }) else {
+            return;
+        };
+
+        if definition.no_self && label.src == did {
+            return;
+        }
+        if definition.unauthed && self.user_did.is_some() {
+            return;
+        }
+
+        let adult_hidden = definition.adult && !self.adult_content_enabled;
+        let visibility = if !definition.configurable {
+            definition.default_setting
+        } else if adult_hidden {
+            LabelVisibility::Hide
+        } else if is_global {
+            self.labels
+                .get(&label.val)
+                .copied()
+                .unwrap_or(definition.default_setting)
+        } else {
+            labeler
+                .and_then(|labeler| labeler.labels.get(&label.val))
+                .copied()
+                .unwrap_or(definition.default_setting)
+        };
+        if visibility == LabelVisibility::Ignore {
+            return;
+        }
+
+        let behaviors = CONTEXTS
+            .into_iter()
+            .filter_map(|context| Some((context, definition.behavior(target, context)?)))
+            .collect::<Vec<_>>();
+        let blurs_in = |contexts: &[ModerationContext]| {
+            behaviors.iter().any(|(context, behavior)| {
+                *behavior == Behavior::Blur && contexts.contains(context)
+            })
+        };
+        let no_override = definition.no_override || adult_hidden;
+        let priority = if no_override {
+            PRIORITY_NO_OVERRIDE_LABEL
+        } else if visibility == LabelVisibility::Hide {
+            PRIORITY_HIDE_LABEL
+        } else if blurs_in(&[
+            ModerationContext::ProfileView,
+            ModerationContext::ContentView,
+        ]) {
+            PRIORITY_BLUR_VIEW_LABEL
+        } else if blurs_in(&[
+            ModerationContext::ContentList,
+            ModerationContext::ContentMedia,
+        ]) {
+            PRIORITY_BLUR_LIST_LABEL
+        } else {
+            PRIORITY_OTHER_LABEL
+        };
+
+        decision.add(
+            ModerationCause::Label {
+                label: label.clone(),
+                target,
+                visibility,
+                no_override,
+            },
+            priority,
+            behaviors,
+        );
+    }
+}
+
+/// Why a post or profile is being moderated
+#[derive(Debug, Clone)]
+pub enum ModerationCause {
+    /// The viewer blocks the account, directly or through the moderation list given
+    Blocking { by_list: Option<String> },
+    /// The account blocks the viewer
+    BlockedBy,
+    /// The viewer muted the account, directly or through the moderation list given
+    Muted { by_list: Option<String> },
+    /// The post contains this muted word
+    MutedWord(String),
+    /// The viewer hid the post
+    Hidden,
+    Label {
+        label: Label,
+        target: LabelTarget,
+        visibility: LabelVisibility,
+        /// The viewer may not click through the warning
+        no_override: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct DecisionCause {
+    cause: ModerationCause,
+    priority: u8,
+    /// How a label acts in each context, empty for other causes
+    behaviors: Vec<(ModerationContext, Behavior)>,
+}
+
+/// Every reason to moderate a post or profile, from `ModerationOptions::decide_post` or
+/// `ModerationOptions::decide_profile`. See `ui` for what to do in a given context
+#[derive(Debug, Clone)]
+pub struct ModerationDecision {
+    /// The post or profile is the viewer's own
+    pub is_me: bool,
+    causes: Vec<DecisionCause>,
+}
+
+impl ModerationDecision {
+    fn add(
+        &mut self,
+        cause: ModerationCause,
+        priority: u8,
+        behaviors: Vec<(ModerationContext, Behavior)>,
+    ) {
+        self.causes.push(DecisionCause {
+            cause,
+            priority,
+            behaviors,
+        });
+    }
+
+    /// All the causes, most severe first
+    pub fn causes(&self) -> impl Iterator<Item = &ModerationCause> {
+        let mut causes = self.causes.iter().collect::<Vec<_>>();
+        causes.sort_by_key(|cause| cause.priority);
+        causes.into_iter().map(|cause| &cause.cause)
+    }
+
+    /// What to do when showing the post or profile in `context`
+    pub fn ui(&self, context: ModerationContext) -> ModerationUi {
+        use ModerationContext::*;
+
+        let mut causes = self.causes.iter().collect::<Vec<_>>();
+        causes.sort_by_key(|cause| cause.priority);
+
+        let mut ui = ModerationUi::default();
+        for DecisionCause {
+            cause, behaviors, ..
+        } in causes
+        {
+            let (filter, behavior) = match cause {
+                ModerationCause::Label {
+                    target,
+                    visibility,
+                    no_override,
+                    ..
+                } => {
+                    let behavior = behaviors
+                        .iter()
+                        .find(|(behavior_context, _)| *behavior_context == context)
+                        .map(|(_, behavior)| *behavior);
+                    if behavior == Some(Behavior::Blur) && *no_override && !self.is_me {
+                        ui.no_override = true;
+                    }
+                    let listed = match target {
+                        LabelTarget::Account => matches!(context, ProfileList | ContentList),
+                        LabelTarget::Profile => false,
+                        LabelTarget::Content => context == ContentList,
+                    };
+                    let filter = listed && *visibility == LabelVisibility::Hide && !self.is_me;
+                    (filter, behavior)
+                }
+                // your own account can't be blocked, muted or hidden
+                _ if self.is_me => continue,
+                ModerationCause::Blocking { .. } | ModerationCause::BlockedBy => {
+                    let behavior = match context {
+                        ProfileView => Some(Behavior::Alert),
+                        ProfileList | Avatar | Banner | ContentList | ContentView => {
+                            Some(Behavior::Blur)
+                        }
+                        _ => None,
+                    };
+                    if behavior == Some(Behavior::Blur) {
+                        ui.no_override = true;
+                    }
+                    (matches!(context, ProfileList | ContentList), behavior)
+                }
+                ModerationCause::Muted { .. } => {
+                    let behavior = match context {
+                        ProfileList | ContentView => Some(Behavior::Inform),
+                        ProfileView => Some(Behavior::Alert),
+                        ContentList => Some(Behavior::Blur),
+                        _ => None,
+                    };
+                    (matches!(context, ProfileList | ContentList), behavior)
+                }
+                ModerationCause::MutedWord(_) | ModerationCause::Hidden => {
+                    let behavior = match context {
+                        ContentList | ContentView => Some(Behavior::Blur),
+                        _ => None,
+                    };
+                    (context == ContentList, behavior)
+                }
+            };
+
+            if filter {
+                ui.filters.push(cause.clone());
+            }
+            match behavior {
+                Some(Behavior::Blur) => ui.blurs.push(cause.clone()),
+                Some(Behavior::Alert) => ui.alerts.push(cause.clone()),
+                Some(Behavior::Inform) => ui.informs.push(cause.clone()),
+                None => {}
+            }
+        }
+
+        ui
+    }
+}
+
+/// What to do with a post or profile in one context, with the causes of each, most severe first
+#[derive(Debug, Clone, Default)]
+pub struct ModerationUi {
+    /// Leave it out of lists and feeds entirely
+    pub filters: Vec<ModerationCause>,
+    /// Hide it behind a warning
+    pub blurs: Vec<ModerationCause>,
+    /// Show it with a warning badge
+    pub alerts: Vec<ModerationCause>,
+    /// Show it with an informational badge
+    pub informs: Vec<ModerationCause>,
+    /// The blur warning can't be clicked through
+    pub no_override: bool,
+}
+
+impl ModerationUi {
+    pub fn filter(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    pub fn blur(&self) -> bool {
+        !self.blurs.is_empty()
+    }
+
+    pub fn alert(&self) -> bool {
+        !self.alerts.is_empty()
+    }
+
+    pub fn inform(&self) -> bool {
+        !self.informs.is_empty()
+    }
+}
+
+/// The parts of a profile view moderation looks at
+pub trait ModeratedProfile {
+    fn did(&self) -> &str;
+    fn viewer(&self) -> Option<&ViewerState>;
+    fn labels(&self) -> &[Label];
+}
+
+macro_rules! moderated_profile {
+    ($($profile:ty),*) => {$(
+        impl ModeratedProfile for $profile {
+            fn did(&self) -> &str {
+                &self.did
+            }
+
+            fn viewer(&self) -> Option<&ViewerState> {
+                self.viewer.as_ref()
+            }
+
+            fn labels(&self) -> &[Label] {
+                &self.labels
+            }
+        }
+    )*};
+}
+
+moderated_profile!(ProfileViewBasic, ProfileView, ProfileViewDetailed);
+
+/// The first of `words` that `post` contains, looking at its text, tags, image and video alt text
+/// and link card. Expired words are skipped, as are words that exclude followed accounts when
+/// `author_followed` is set
+pub fn find_muted_word<'w>(
+    words: &'w [MutedWord],
+    post: &Post,
+    author_followed: bool,
+) -> Option<&'w MutedWord> {
+    let post_text = PostText::new(post);
+    let now = Utc::now();
+
+    words.iter().find(|word| {
+        if word.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return false;
+        }
+        if author_followed && word.actor_target.as_deref() == Some("exclude-following") {
+            return false;
+        }
+        matches_muted_word(word, post_text.text, &post_text.tags, post_text.langs)
+            || post_text
+                .media_texts
+                .iter()
+                .any(|text| matches_muted_word(word, text, &[], post_text.langs))
+    })
+}
+
+/// Whether `text` or `tags` contain a muted word, as the official clients match them: tags
+/// match exactly, and text matches on whole words or phrases, ignoring case and surrounding
+/// punctuation. Expiry and the actor target aren't checked
+pub fn matches_muted_word(word: &MutedWord, text: &str, tags: &[String], langs: &[String]) -> bool {
+    let muted = word.value.to_lowercase();
+    if tags.iter().any(|tag| tag.to_lowercase() == muted) {
+        return true;
+    }
+    word.targets.iter().any(|target| target == "content") && contains_word(text, &muted, langs)
+}
+
+/// The parts of a post that muted words are matched against
+pub(crate) struct PostText<'p> {
+    pub text: &'p str,
+    /// Image and video alt text, and the link card's title and description
+    pub media_texts: Vec<&'p str>,
+    /// The post's tags along with the hashtags in its text
+    pub tags: Vec<String>,
+    pub langs: &'p [String],
+}
+
+impl<'p> PostText<'p> {
+    pub fn new(post: &'p Post) -> Self {
+        let (images, external, video) = match &post.embed {
+            Some(Embeds::Images(images)) => (Some(images), None, None),
+            Some(Embeds::External(external)) => (None, Some(&external.external), None),
+            Some(Embeds::Video(video)) => (None, None, Some(video)),
+            Some(Embeds::RecordWithMedia(record_with_media)) => match &record_with_media.media {
+                Media::Images(images) => (Some(images), None, None),
+                Media::External(external) => (None, Some(&external.external), None),
+                Media::Video(video) => (None, None, Some(video)),
+                Media::Unknown(_) => (None, None, None),
+            },
+            _ => (None, None, None),
+        };
+        let mut media_texts = Vec::new();
+        media_texts.extend(
+            images
+                .iter()
+                .flat_map(|images| &images.images)
+                .map(|image| image.alt.as_str()),
+        );
+        media_texts.extend(video.and_then(|video| video.alt.as_deref()));
+        if let Some(external) = external {
+            media_texts.push(external.title.as_str());
+            media_texts.push(external.description.as_str());
+        }
+
+        let mut tags = post.tags.clone().unwrap_or_default();
+        for facet in post.facets.iter().flatten() {
+            for feature in &facet.features {
+                if let FacetFeature::Tag(tag) = feature {
+                    tags.push(tag.tag.clone());
+                }
+            }
+        }
+
+        PostText {
+            text: &post.text,
+            media_texts,
+            tags,
+            langs: post.langs.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+/// Whether `text` contains `word` (already lowercase) as a whole word or phrase, ignoring case
+/// and surrounding punctuation. Single characters, and any word in languages written without
+/// spaces, match anywhere
+pub(crate) fn contains_word(text: &str, word: &str, langs: &[String]) -> bool {
+    let text = text.to_lowercase();
+    let exception = langs
+        .first()
+        .is_some_and(|lang| MUTED_WORD_LANGUAGE_EXCEPTIONS.contains(&lang.as_str()));
+    if (word.graphemes(true).count() == 1 || exception) && text.contains(word) {
+        return true;
+    }
+    if word.len() > text.len() {
+        return false;
+    }
+    if word == text {
+        return true;
+    }
+    // phrases, and words with punctuation in them, match anywhere
+    if word.chars().any(|c| c.is_whitespace() || is_punctuation(c)) && text.contains(word) {
+        return true;
+    }
+
+    for text_word in text.split_whitespace() {
+        if text_word == word {
+            return true;
+        }
+        let trimmed = text_word.trim_matches(is_punctuation);
+        if trimmed == word {
+            return true;
+        }
+        if word.len() > trimmed.len() || !trimmed.contains(is_punctuation) {
+            continue;
+        }
+
+        // `foo-bar`, matched as `foo bar`, `foobar`, `foo` and `bar`
+        let parts = trimmed
+            .split(is_punctuation)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>();
+        if parts.join(" ") == word || parts.concat() == word || parts.contains(&word) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Anything that isn't a letter, digit or whitespace, which stands in for Unicode punctuation
+fn is_punctuation(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use ModerationContext::*;
+
+    const ME: &str = "did:plc:me";
+    const BOB: &str = "did:plc:bob";
+    const LABELER: &str = "did:plc:labeler";
+    const POST: &str = "at://did:plc:bob/app.bsky.feed.post/3k2a";
+
+    fn options(preferences: Value, labelers: &[Value]) -> ModerationOptions {
+        let preferences: Preferences =
+            serde_json::from_value(json!({ "preferences": preferences })).unwrap();
+        let labelers = labelers
+            .iter()
+            .map(|labeler| serde_json::from_value(labeler.clone()).unwrap())
+            .collect::<Vec<LabelerViewDetailed>>();
+        ModerationOptions::from_preferences(ME, &preferences, &labelers)
+    }
+
+    fn label(src: &str, uri: &str, val: &str) -> Value {
+        json!({"src": src, "uri": uri, "val": val, "cts": "2024-01-01T00:00:00Z"})
+    }
+
+    fn post(author: &str, viewer: Value, labels: Vec<Value>, text: &str) -> PostView {
+        serde_json::from_value(json!({
+            "uri": POST.replace(BOB, author),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": author, "handle": "someone.test", "viewer": viewer},
+            "record": {"createdAt": "2024-01-01T00:00:00Z", "text": text},
+            "indexedAt": "2024-01-01T00:00:00Z",
+            "labels": labels,
+        }))
+        .unwrap()
+    }
+
+    fn labelled_post(labels: Vec<Value>) -> PostView {
+        post(BOB, Value::Null, labels, "hi")
+    }
+
+    fn profile(viewer: Value, labels: Vec<Value>) -> ProfileViewBasic {
+        serde_json::from_value(json!({
+            "did": BOB,
+            "handle": "bob.test",
+            "viewer": viewer,
+            "labels": labels,
+        }))
+        .unwrap()
+    }
+
+    /// The UI in `context` in short: `f`ilter, `b`lur, `a`lert, `i`nform and `n`o override
+    fn ui(decision: &ModerationDecision, context: ModerationContext) -> String {
+        let ui = decision.ui(context);
+        [
+            (ui.filter(), 'f'),
+            (ui.blur(), 'b'),
+            (ui.alert(), 'a'),
+            (ui.inform(), 'i'),
+            (ui.no_override, 'n'),
+        ]
+        .into_iter()
+        .filter_map(|(set, letter)| set.then_some(letter))
+        .collect()
+    }
+
+    /// `ui` in every context, in the order of `CONTEXTS`
+    fn uis(decision: &ModerationDecision) -> [String; 8] {
+        CONTEXTS.map(|context| ui(decision, context))
+    }
+
+    #[test]
+    fn hides_adult_labels_unless_adult_content_is_on() {
+        let post = labelled_post(vec![label(BSKY_MODERATION_DID, POST, "sexual")]);
+
+        let off = options(json!([]), &[]);
+        let decision = off.decide_post(&post);
+        assert_eq!(ui(&decision, ContentList), "f");
+        assert_eq!(ui(&decision, ContentMedia), "bn");
+
+        let on = options(
+            json!([{"$type": "app.bsky.actor.defs#adultContentPref", "enabled": true}]),
+            &[],
+        );
+        let decision = on.decide_post(&post);
+        assert_eq!(ui(&decision, ContentList), "");
+        assert_eq!(ui(&decision, ContentMedia), "b");
+
+        let ignored = options(
+            json!([
+                {"$type": "app.bsky.actor.defs#adultContentPref", "enabled": true},
+                {"$type": "app.bsky.actor.defs#contentLabelPref", "label": "sexual", "visibility": "ignore"},
+            ]),
+            &[],
+        );
+        assert_eq!(ignored.decide_post(&post).causes().count(), 0);
+
+        // nudity isn't adult-only, and is shown by default
+        let nudity = labelled_post(vec![label(BSKY_MODERATION_DID, POST, "nudity")]);
+        assert_eq!(off.decide_post(&nudity).causes().count(), 0);
+    }
+
+    #[test]
+    fn applies_system_labels() {
+        let options = options(json!([]), &[]);
+
+        let hidden = options.decide_post(&labelled_post(vec![label(
+            BSKY_MODERATION_DID,
+            POST,
+            "!hide",
+        )]));
+        assert_eq!(
+            uis(&hidden),
+            ["", "", "", "", "", "fbn", "bn", ""].map(String::from)
+        );
+
+        // `!warn` on an account blurs everything but its name, and can be clicked through
+        let warned = options.decide_profile(&profile(
+            Value::Null,
+            vec![label(BSKY_MODERATION_DID, BOB, "!warn")],
+        ));
+        assert_eq!(
+            uis(&warned),
+            ["b", "b", "b", "b", "", "b", "b", ""].map(String::from)
+        );
+
+        // `!no-unauthenticated` only matters when logged out
+        let account = profile(Value::Null, vec![label(BOB, BOB, "!no-unauthenticated")]);
+        assert_eq!(options.decide_profile(&account).causes().count(), 0);
+        let logged_out = ModerationOptions {
+            user_did: None,
+            ..options.clone()
+        };
+        assert_eq!(
+            uis(&logged_out.decide_profile(&account)),
+            ["fbn", "bn", "bn", "bn", "bn", "fbn", "bn", ""].map(String::from)
+        );
+    }
+
+    #[test]
+    fn ignores_system_labels_on_yourself() {
+        let options = options(json!([]), &[]);
+
+        // `!hide` and `!warn` can't be self-applied, but other labels can
+        for val in ["!hide", "!warn"] {
+            let post = labelled_post(vec![label(BOB, POST, val)]);
+            assert_eq!(options.decide_post(&post).causes().count(), 0, "{val}");
+        }
+        let post = labelled_post(vec![label(BOB, POST, "porn")]);
+        assert_eq!(ui(&options.decide_post(&post), ContentMedia), "bn");
+    }
+
+    #[test]
+    fn only_counts_labels_from_subscribed_labelers() {
+        let labelers = [json!({
+            "uri": format!("at://{LABELER}/app.bsky.labeler.service/self"),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "creator": {"did": LABELER, "handle": "labeler.test"},
+            "policies": {
+                "labelValues": ["rude"],
+                "labelValueDefinitions": [{
+                    "identifier": "rude",
+                    "severity": "alert",
+                    "blurs": "content",
+                    "defaultSetting": "warn",
+                    "locales": [],
+                }],
+            },
+            "indexedAt": "2024-01-01T00:00:00Z",
+        })];
+        let post = labelled_post(vec![
+            label(LABELER, POST, "rude"),
+            label("did:plc:stranger", POST, "porn"),
+        ]);
+
+        let unsubscribed = options(json!([]), &labelers);
+        assert_eq!(unsubscribed.decide_post(&post).causes().count(), 0);
+
+        let subscribed = options(
+            json!([{
+                "$type": "app.bsky.actor.defs#labelersPref",
+                "labelers": [{"did": LABELER}],
+            }]),
+            &labelers,
+        );
+        let decision = subscribed.decide_post(&post);
+        assert_eq!(decision.causes().count(), 1);
+        assert_eq!(ui(&decision, ContentList), "b");
+        assert_eq!(ui(&decision, ContentView), "a");
+
+        // The viewer's setting for the labeler's own label
+        let hidden = options(
+            json!([
+                {"$type": "app.bsky.actor.defs#labelersPref", "labelers": [{"did": LABELER}]},
+                {
+                    "$type": "app.bsky.actor.defs#contentLabelPref",
+                    "labelerDid": LABELER,
+                    "label": "rude",
+                    "visibility": "hide",
+                },
+            ]),
+            &labelers,
+        );
+        assert_eq!(ui(&hidden.decide_post(&post), ContentList), "fb");
+    }
+
+    #[test]
+    fn exempts_your_own_posts_and_profile() {
+        let options = options(
+            json!([{
+                "$type": "app.bsky.actor.defs#mutedWordsPref",
+                "items": [{"value": "cat", "targets": ["content"]}],
+            }]),
+            &[],
+        );
+        let post = post(
+            ME,
+            json!({"muted": true}),
+            vec![label(BSKY_MODERATION_DID, &POST.replace(BOB, ME), "!hide")],
+            "my cat",
+        );
+
+        let decision = options.decide_post(&post);
+        assert!(decision.is_me);
+        // Still blurred, but never filtered, and the warning can be clicked through
+        assert_eq!(
+            uis(&decision),
+            ["", "", "", "", "", "b", "b", ""].map(String::from)
+        );
+    }
+
+    #[test]
+    fn treats_blocks_mutes_muted_words_and_hidden_posts_differently() {
+        let list = json!({
+            "uri": "at://did:plc:me/app.bsky.graph.list/mods",
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "name": "mods",
+            "purpose": "app.bsky.graph.defs#modlist",
+        });
+        let options = options(
+            json!([
+                {
+                    "$type": "app.bsky.actor.defs#mutedWordsPref",
+                    "items": [{"value": "cat", "targets": ["content"]}],
+                },
+                {"$type": "app.bsky.actor.defs#hiddenPostsPref", "items": [POST]},
+            ]),
+            &[],
+        );
+
+        let block = ["fbn", "a", "bn", "bn", "", "fbn", "bn", ""];
+        let mute = ["fi", "a", "", "", "", "fb", "i", ""];
+        for (viewer, expected) in [
+            (
+                json!({"blocking": "at://did:plc:me/app.bsky.graph.block/1"}),
+                block,
+            ),
+            (json!({"blockingByList": list}), block),
+            (json!({"blockedBy": true}), block),
+            (json!({"muted": true}), mute),
+            (json!({"muted": true, "mutedByList": list}), mute),
+        ] {
+            let decision = options.decide_profile(&profile(viewer.clone(), vec![]));
+            let profile_contexts = &uis(&decision)[..5];
+            assert_eq!(profile_contexts, &expected[..5], "{viewer}");
+
+            let post = post("did:plc:carol", viewer.clone(), vec![], "hi");
+            let post_contexts = &uis(&options.decide_post(&post))[5..];
+            assert_eq!(post_contexts, &expected[5..], "{viewer}");
+        }
+
+        let muted_word = options.decide_post(&post("did:plc:carol", Value::Null, vec![], "a cat"));
+        let hidden = options.decide_post(&post(BOB, Value::Null, vec![], "hi"));
+        for decision in [muted_word, hidden] {
+            assert_eq!(
+                uis(&decision),
+                ["", "", "", "", "", "fb", "b", ""].map(String::from)
+            );
+        }
+    }
+
+    #[test]
+    fn orders_causes_by_severity() {
+        let options = options(json!([]), &[]);
+        let post = post(
+            BOB,
+            json!({"muted": true, "blocking": "at://did:plc:me/app.bsky.graph.block/1"}),
+            vec![
+                label(BSKY_MODERATION_DID, POST, "!warn"),
+                label(BSKY_MODERATION_DID, POST, "!hide"),
+            ],
+            "hi",
+        );
+
+        let causes = options
+            .decide_post(&post)
+            .causes()
+            .map(|cause| match cause {
+                ModerationCause::Label { label, .. } => label.val.clone(),
+                ModerationCause::Blocking { .. } => "blocking".to_string(),
+                ModerationCause::Muted { .. } => "muted".to_string(),
+                other => panic!("{other:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(causes, ["!hide", "blocking", "!warn", "muted"]);
+    }
+
+    #[test]
+    fn matches_words_like_the_official_clients() {
+        let cases: &[(&str, &str, &[&str], bool)] = &[
+            // single graphemes match anywhere
+            ("a🦋b", "🦋", &[], true),
+            ("cat", "a", &[], true),
+            ("bär", "ä", &[], true),
+            // languages without spaces match anywhere, going by the first language only
+            ("私は猫好きです", "猫好き", &["ja"], true),
+            ("私は猫好きです", "猫好き", &[], false),
+            ("私は猫好きです", "猫好き", &["en", "ja"], false),
+            ("我喜欢猫咪", "猫咪", &["zh"], true),
+            ("나는고양이를좋아해", "고양이", &["ko"], true),
+            ("ฉันชอบแมวมาก", "แมว", &["th"], true),
+            ("tôi thíchmèo", "mèo", &["vi"], true),
+            ("tôi thíchmèo", "mèo", &[], false),
+            // whole words
+            ("rust", "rust", &[], true),
+            ("I like Rust", "rust", &[], true),
+            ("trust me", "rust", &[], false),
+            ("rusty", "rust", &[], false),
+            ("rust", "rusty", &[], false),
+            // phrases
+            ("I love hot dogs.", "hot dogs", &[], true),
+            ("hotdogs", "hot dogs", &[], false),
+            ("not hot. dogs", "hot dogs", &[], false),
+            // words with punctuation in them
+            ("a foo-bar here", "foo bar", &[], true),
+            ("a foo-bar here", "foobar", &[], true),
+            ("a foo-bar here", "foo", &[], true),
+            ("a foo-bar here", "bar", &[], true),
+            ("a foo-bar here", "foo-bar", &[], true),
+            ("a foo-bar here", "oob", &[], false),
+            ("a foo bar here", "foo-bar", &[], false),
+            // surrounding punctuation
+            ("Rust!", "rust", &[], true),
+            ("(rust)", "rust", &[], true),
+            ("#rust, again", "rust", &[], true),
+            ("...rust...", "rust", &[], true),
+            ("rust's", "rust", &[], true),
+        ];
+        for (text, word, langs, expected) in cases {
+            let langs = langs
+                .iter()
+                .map(|lang| lang.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                contains_word(text, word, &langs),
+                *expected,
+                "{word:?} in {text:?} ({langs:?})"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_alt_text_and_link_cards() {
+        let blob = json!({
+            "$type": "blob",
+            "ref": {"$link": "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"},
+            "mimeType": "image/jpeg",
+            "size": 1,
+        });
+        let images = json!({
+            "$type": "app.bsky.embed.images",
+            "images": [{"image": blob, "alt": "image alt"}],
+        });
+        let external = json!({
+            "$type": "app.bsky.embed.external",
+            "external": {"uri": "https://example.com", "title": "title", "description": "description"},
+        });
+        let video = json!({"$type": "app.bsky.embed.video", "video": blob, "alt": "video alt"});
+        let with_media = |media: &Value| {
+            json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": {"record": {"uri": POST, "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm"}},
+                "media": media,
+            })
+        };
+
+        for (embed, expected) in [
+            (images.clone(), vec!["image alt"]),
+            (with_media(&images), vec!["image alt"]),
+            (external.clone(), vec!["title", "description"]),
+            (with_media(&external), vec!["title", "description"]),
+            (video.clone(), vec!["video alt"]),
+            (with_media(&video), vec!["video alt"]),
+        ] {
+            let post: Post = serde_json::from_value(json!({
+                "createdAt": "2024-01-01T00:00:00Z",
+                "text": "hi",
+                "embed": embed,
+            }))
+            .unwrap();
+            assert_eq!(PostText::new(&post).media_texts, expected, "{embed}");
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::lexicon::app::bsky::actor::ProfileView;
+use crate::lexicon::app::bsky::feed::PostView;
+use crate::lexicon::app::bsky::notification::{
+    Notification, NotificationReason, NotificationRecord,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+/// Notifications of the same reason about the same subject, such as everyone who liked a post,
+/// shown as one item
+#[derive(Debug)]
+pub struct NotificationGroup {
+    pub reason: NotificationReason,
+    /// The record the notifications are about, such as the liked post
+    pub reason_subject: Option<String>,
+    /// The subject post, if the subject is a post and it has been hydrated, see
+    /// `BlueskyMe::list_notification_groups`. Groups about the same post share it
+    pub subject: Option<Arc<PostView>>,
+    /// Newest first
+    pub notifications: Vec<Notification<NotificationRecord>>,
+}
+
+impl NotificationGroup {
+    pub fn count(&self) -> usize {
+        self.notifications.len()
+    }
+
+    /// The accounts behind the notifications, newest first and each only once
+    pub fn actors(&self) -> Vec<&ProfileView> {
+        let mut actors = Vec::<&ProfileView>::new();
+        for notification in &self.notifications {
+            if !actors
+                .iter()
+                .any(|actor| actor.did == notification.author.did)
+            {
+                actors.push(&notification.author);
+            }
+        }
+        actors
+    }
+
+    /// Whether every notification in the group has been read
+    pub fn is_read(&self) -> bool {
+        self.notifications
+            .iter()
+            .all(|notification| notification.is_read)
+    }
+
+    /// When the newest notification was indexed
+    pub fn indexed_at(&self) -> DateTime<Utc> {
+        self.notifications[0].indexed_at
+    }
+}
+
+/// Whether notifications with this reason are grouped. Replies, mentions and quotes each carry
+/// their own post, so they stay on their own
+fn is_groupable(reason: &NotificationReason) -> bool {
+    matches!(
+        reason,
+        NotificationReason::Like
+            | NotificationReason::Repost
+            | NotificationReason::Follow
+            | NotificationReason::LikeViaRepost
+            | NotificationReason::RepostViaRepost
+            | NotificationReason::StarterpackJoined
+    )
+}
+
+/// Group notifications, given newest first as they are listed, by reason and subject. A
+/// notification joins a group only if it is within `window` of the group's newest
+/// notification; the official app uses two days
+pub fn group_notifications(
+    notifications: Vec<Notification<NotificationRecord>>,
+    window: Duration,
+) -> Vec<NotificationGroup> {
+    let mut groups: Vec<NotificationGroup> = Vec::new();
+
+    for notification in notifications {
+        let group = match is_groupable(&notification.reason) {
+            true => groups.iter_mut().find(|group| {
+                group.reason == notification.reason
+                    && group.reason_subject == notification.reason_subject
+                    && group.indexed_at() - notification.indexed_at <= window
+            }),
+            false => None,
+        };
+
+        match group {
+            Some(group) => group.notifications.push(notification),
+            None => groups.push(NotificationGroup {
+                reason: notification.reason.clone(),
+                reason_subject: notification.reason_subject.clone(),
+                subject: None,
+                notifications: Vec::from([notification]),
+            }),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "at://did:plc:alice/app.bsky.feed.post/3k2a";
+
+    /// A notification from `author`, indexed `minutes` after the epoch
+    fn notification(
+        reason: &str,
+        subject: Option<&str>,
+        author: &str,
+        minutes: i64,
+    ) -> Notification<NotificationRecord> {
+        serde_json::from_value(serde_json::json!({
+            "uri": format!("at://{author}/app.bsky.feed.like/{minutes}"),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {"did": author, "handle": "someone.test"},
+            "reason": reason,
+            "reasonSubject": subject,
+            "record": {},
+            "isRead": minutes < 10,
+            "indexedAt": (DateTime::UNIX_EPOCH + Duration::minutes(minutes)).to_rfc3339(),
+        }))
+        .unwrap()
+    }
+
+    fn sizes(groups: &[NotificationGroup]) -> Vec<(&str, usize)> {
+        groups
+            .iter()
+            .map(|group| (group.reason.as_str(), group.count()))
+            .collect()
+    }
+
+    #[test]
+    fn groups_within_the_window() {
+        let groups = group_notifications(
+            Vec::from([
+                notification("like", Some(POST), "did:plc:bob", 100),
+                notification("repost", Some(POST), "did:plc:bob", 90),
+                notification("like", Some(POST), "did:plc:carol", 80),
+                notification(
+                    "like",
+                    Some("at://did:plc:alice/app.bsky.feed.post/3k2b"),
+                    "did:plc:bob",
+                    70,
+                ),
+                // The window is measured from the group's newest notification
+                notification("like", Some(POST), "did:plc:dave", 40),
+                notification("like", Some(POST), "did:plc:erin", 39),
+            ]),
+            Duration::minutes(60),
+        );
+        assert_eq!(
+            sizes(&groups),
+            [("like", 3), ("repost", 1), ("like", 1), ("like", 1)]
+        );
+        assert_eq!(groups[0].reason_subject.as_deref(), Some(POST));
+        assert_eq!(
+            groups[0].indexed_at(),
+            DateTime::UNIX_EPOCH + Duration::minutes(100)
+        );
+        assert_eq!(groups[3].notifications[0].author.did, "did:plc:erin");
+    }
+
+    #[test]
+    fn groups_follows_without_a_subject() {
+        let groups = group_notifications(
+            Vec::from([
+                notification("follow", None, "did:plc:bob", 30),
+                notification("follow", None, "did:plc:carol", 20),
+                notification("starterpack-joined", None, "did:plc:dave", 10),
+            ]),
+            Duration::days(2),
+        );
+        assert_eq!(sizes(&groups), [("follow", 2), ("starterpack-joined", 1)]);
+        assert_eq!(groups[0].reason_subject, None);
+    }
+
+    #[test]
+    fn keeps_posts_on_their_own() {
+        let groups = group_notifications(
+            [
+                "reply",
+                "reply",
+                "mention",
+                "mention",
+                "quote",
+                "quote",
+                "subscribed-post",
+            ]
+            .into_iter()
+            .map(|reason| notification(reason, Some(POST), "did:plc:bob", 10))
+            .collect(),
+            Duration::days(2),
+        );
+        assert_eq!(groups.len(), 7);
+        assert!(groups.iter().all(|group| group.count() == 1));
+    }
+
+    #[test]
+    fn lists_each_actor_once() {
+        let groups = group_notifications(
+            Vec::from([
+                notification("like", Some(POST), "did:plc:bob", 12),
+                notification("like", Some(POST), "did:plc:carol", 11),
+                notification("like", Some(POST), "did:plc:bob", 9),
+            ]),
+            Duration::days(2),
+        );
+        let actors = groups[0]
+            .actors()
+            .into_iter()
+            .map(|actor| actor.did.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(actors, ["did:plc:bob", "did:plc:carol"]);
+        // Only the notification from 9 minutes in has been read
+        assert!(!groups[0].is_read());
+
+        let groups = group_notifications(
+            Vec::from([
+                notification("like", Some(POST), "did:plc:bob", 9),
+                notification("like", Some(POST), "did:plc:carol", 8),
+            ]),
+            Duration::days(2),
+        );
+        assert!(groups[0].is_read());
+    }
+}
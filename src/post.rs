@@ -0,0 +1,370 @@
+use crate::errors::BiskyError;
+use crate::lexicon::app::bsky::embed::{
+    External, ExternalObject, Image, Media, Record as RecordEmbed, RecordWithMedia, Video,
+};
+use crate::lexicon::app::bsky::feed::{Embeds, ImagesEmbed, Post, PostView, ReplyRef};
+use crate::lexicon::app::bsky::notification::{
+    Notification, NotificationReason, NotificationRecord,
+};
+use crate::lexicon::app::bsky::richtext::Facet;
+use crate::lexicon::com::atproto::label::{SelfLabel, SelfLabels, SelfLabelsUnion};
+use crate::lexicon::com::atproto::repo::{Record, StrongRef};
+use crate::richtext::RichText;
+use crate::validation::{validate_post, ValidationError};
+use chrono::{DateTime, Utc};
+
+/// A post that can be replied to
+pub trait ReplyTarget {
+    /// The post itself, which becomes the parent of the reply, or an error if this isn't a post
+    fn strong_ref(&self) -> Result<StrongRef, ValidationError>;
+    /// Where the post sits in its own thread, if it is a reply
+    fn reply(&self) -> Option<&ReplyRef>;
+}
+
+impl ReplyTarget for PostView {
+    fn strong_ref(&self) -> Result<StrongRef, ValidationError> {
+        Ok(self.into())
+    }
+
+    fn reply(&self) -> Option<&ReplyRef> {
+        self.record.reply.as_ref()
+    }
+}
+
+impl ReplyTarget for Record<Post> {
+    fn strong_ref(&self) -> Result<StrongRef, ValidationError> {
+        Ok(StrongRef {
+            uri: self.uri.clone(),
+            cid: self.cid.clone(),
+        })
+    }
+
+    fn reply(&self) -> Option<&ReplyRef> {
+        self.value.reply.as_ref()
+    }
+}
+
+/// Only replies, mentions and quotes are about a post that can be replied to. A like or a
+/// follow is a record of its own, so replying to one is an error
+impl ReplyTarget for Notification<NotificationRecord> {
+    fn strong_ref(&self) -> Result<StrongRef, ValidationError> {
+        match (&self.reason, &self.record) {
+            (
+                NotificationReason::Reply | NotificationReason::Mention | NotificationReason::Quote,
+                NotificationRecord::Post(_),
+            ) => Ok(StrongRef {
+                uri: self.uri.clone(),
+                cid: self.cid.clone(),
+            }),
+            _ => Err(ValidationError::NotAReplyTarget {
+                uri: self.uri.clone(),
+            }),
+        }
+    }
+
+    fn reply(&self) -> Option<&ReplyRef> {
+        match &self.record {
+            NotificationRecord::Post(post) => post.reply.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&PostView> for StrongRef {
+    fn from(post: &PostView) -> Self {
+        StrongRef {
+            uri: post.uri.clone(),
+            cid: post.cid.clone(),
+        }
+    }
+}
+
+/// Builds an `app.bsky.feed.post` record, filling in the bookkeeping fields and checking the
+/// result with `validate_post`
+#[derive(Debug, Default)]
+pub struct PostBuilder {
+    text: String,
+    facets: Vec<Facet>,
+    created_at: Option<DateTime<Utc>>,
+    reply: Option<Result<ReplyRef, ValidationError>>,
+    quote: Option<StrongRef>,
+    images: Vec<Image>,
+    external: Option<ExternalObject>,
+    video: Option<Video>,
+    langs: Vec<String>,
+    labels: Vec<String>,
+    tags: Vec<String>,
+}
+
+impl Post {
+    pub fn builder() -> PostBuilder {
+        PostBuilder::default()
+    }
+}
+
+impl PostBuilder {
+    /// Plain text, without facets
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self.facets.clear();
+        self
+    }
+
+    /// Text along with its facets, see `RichText::detect_facets`
+    pub fn rich_text(mut self, rich_text: RichText) -> Self {
+        self.text = rich_text.text;
+        self.facets = rich_text.facets;
+        self
+    }
+
+    /// Defaults to the time `build` is called
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Make this post a reply. The target becomes the parent, and the root is taken from the
+    /// target's own reply reference, or is the target itself if it starts a thread. If the
+    /// target isn't a post, `build` fails
+    pub fn reply_to(mut self, target: &impl ReplyTarget) -> Self {
+        self.reply = Some(target.strong_ref().map(|parent| {
+            let root = match target.reply() {
+                Some(reply) => reply.root.clone(),
+                None => parent.clone(),
+            };
+            ReplyRef { root, parent }
+        }));
+        self
+    }
+
+    /// Quote another post (or any other record, such as a feed generator or list)
+    pub fn quote(mut self, record: impl Into<StrongRef>) -> Self {
+        self.quote = Some(record.into());
+        self
+    }
+
+    /// Attach images, see `BlueskyMe::upload_blob`
+    pub fn images(mut self, images: Vec<Image>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Attach a link card
+    pub fn external(mut self, external: ExternalObject) -> Self {
+        self.external = Some(external);
+        self
+    }
+
+    /// Attach a video, see `BlueskyMe::upload_video`
+    pub fn video(mut self, video: Video) -> Self {
+        self.video = Some(video);
+        self
+    }
+
+    /// BCP-47 language codes of the text
+    pub fn langs<S: Into<String>>(mut self, langs: impl IntoIterator<Item = S>) -> Self {
+        self.langs = langs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Self-applied labels such as `nudity` or `graphic-media`
+    pub fn labels<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Extra hashtags that aren't part of the text, without `#`
+    pub fn tags<S: Into<String>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn build(self) -> Result<Post, BiskyError> {
+        let mut media = Vec::new();
+        if !self.images.is_empty() {
+            media.push(Media::Images(ImagesEmbed {
+                images: self.images,
+            }));
+        }
+        if let Some(external) = self.external {
+            media.push(Media::External(External { external }));
+        }
+        if let Some(video) = self.video {
+            media.push(Media::Video(video));
+        }
+        if media.len() > 1 {
+            return Err(ValidationError::ConflictingEmbeds.into());
+        }
+        let media = media.pop();
+
+        let embed = match (self.quote, media) {
+            (None, None) => None,
+            (None, Some(Media::Images(images))) => Some(Embeds::Images(images)),
+            (None, Some(Media::External(external))) => Some(Embeds::External(external)),
+            (None, Some(Media::Video(video))) => Some(Embeds::Video(video)),
+            (None, Some(Media::Unknown(value))) => Some(Embeds::Unknown(value)),
+            (Some(record), None) => Some(Embeds::Record(RecordEmbed {
+                rust_type: None,
+                record,
+            })),
+            (Some(record), Some(media)) => Some(Embeds::RecordWithMedia(RecordWithMedia {
+                record: RecordEmbed {
+                    rust_type: Some("app.bsky.embed.record".to_string()),
+                    record,
+                },
+                media,
+            })),
+        };
+
+        let post = Post {
+            created_at: self.created_at.unwrap_or_else(Utc::now),
+            rust_type: Some("app.bsky.feed.post".to_string()),
+            text: self.text,
+            facets: non_empty(self.facets),
+            embed,
+            reply: self.reply.transpose()?,
+            langs: non_empty(self.langs),
+            labels: non_empty(self.labels).map(|labels| {
+                SelfLabelsUnion::SelfLabels(SelfLabels {
+                    values: labels.into_iter().map(|val| SelfLabel { val }).collect(),
+                })
+            }),
+            tags: non_empty(self.tags),
+        };
+
+        validate_post(&post)?;
+        Ok(post)
+    }
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    match items.is_empty() {
+        true => None,
+        false => Some(items),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn strong_ref(rkey: &str) -> serde_json::Value {
+        json!({
+            "uri": format!("at://did:plc:alice/app.bsky.feed.post/{rkey}"),
+            "cid": format!("cid-{rkey}"),
+        })
+    }
+
+    fn post(reply: Option<(&str, &str)>) -> serde_json::Value {
+        let mut post = json!({
+            "$type": "app.bsky.feed.post",
+            "text": "hi",
+            "createdAt": "2024-01-01T00:00:00Z",
+        });
+        if let Some((root, parent)) = reply {
+            post["reply"] = json!({"root": strong_ref(root), "parent": strong_ref(parent)});
+        }
+        post
+    }
+
+    fn post_view(rkey: &str, reply: Option<(&str, &str)>) -> PostView {
+        let mut view = strong_ref(rkey);
+        view["author"] = json!({"did": "did:plc:alice", "handle": "alice.test"});
+        view["record"] = post(reply);
+        view["indexedAt"] = json!("2024-01-01T00:00:00Z");
+        serde_json::from_value(view).unwrap()
+    }
+
+    fn notification(
+        rkey: &str,
+        reason: &str,
+        record: serde_json::Value,
+    ) -> Notification<NotificationRecord> {
+        let mut notification = strong_ref(rkey);
+        notification["author"] = json!({"did": "did:plc:bob", "handle": "bob.test"});
+        notification["reason"] = json!(reason);
+        notification["record"] = record;
+        notification["isRead"] = json!(false);
+        notification["indexedAt"] = json!("2024-01-01T00:00:00Z");
+        serde_json::from_value(notification).unwrap()
+    }
+
+    fn rkey(strong_ref: &StrongRef) -> &str {
+        strong_ref.uri.rsplit('/').next().unwrap()
+    }
+
+    fn reply_rkeys(post: &Post) -> (&str, &str) {
+        let reply = post.reply.as_ref().unwrap();
+        (rkey(&reply.root), rkey(&reply.parent))
+    }
+
+    fn reply_to(target: &impl ReplyTarget) -> Result<Post, BiskyError> {
+        Post::builder().text("reply").reply_to(target).build()
+    }
+
+    #[test]
+    fn replies_to_a_top_level_post() {
+        let reply = reply_to(&post_view("top", None)).unwrap();
+        assert_eq!(reply_rkeys(&reply), ("top", "top"));
+        assert_eq!(reply.reply.unwrap().parent.cid, "cid-top");
+    }
+
+    #[test]
+    fn replies_to_a_reply() {
+        let reply = reply_to(&post_view("c", Some(("a", "b")))).unwrap();
+        assert_eq!(reply_rkeys(&reply), ("a", "c"));
+
+        let record: Record<Post> = serde_json::from_value(json!({
+            "uri": "at://did:plc:alice/app.bsky.feed.post/c",
+            "cid": "cid-c",
+            "value": post(Some(("a", "b"))),
+        }))
+        .unwrap();
+        let reply = reply_to(&record).unwrap();
+        assert_eq!(reply_rkeys(&reply), ("a", "c"));
+    }
+
+    #[test]
+    fn replies_to_notifications_about_posts() {
+        let mention = notification("m", "mention", post(None));
+        assert_eq!(reply_rkeys(&reply_to(&mention).unwrap()), ("m", "m"));
+
+        let reply = notification("r", "reply", post(Some(("a", "b"))));
+        assert_eq!(reply_rkeys(&reply_to(&reply).unwrap()), ("a", "r"));
+
+        let quote = notification("q", "quote", post(None));
+        assert_eq!(reply_rkeys(&reply_to(&quote).unwrap()), ("q", "q"));
+    }
+
+    #[test]
+    fn refuses_to_reply_to_other_notifications() {
+        let like = notification(
+            "l",
+            "like",
+            json!({
+                "$type": "app.bsky.feed.like",
+                "subject": strong_ref("top"),
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+        );
+        let follow = notification(
+            "f",
+            "follow",
+            json!({
+                "$type": "app.bsky.graph.follow",
+                "subject": "did:plc:alice",
+                "createdAt": "2024-01-01T00:00:00Z",
+            }),
+        );
+        // A post record behind a reason that isn't about the post itself
+        let liked_post = notification("p", "like", post(None));
+        for notification in [like, follow, liked_post] {
+            assert!(matches!(
+                reply_to(&notification),
+                Err(BiskyError::ValidationError(ValidationError::NotAReplyTarget { uri }))
+                    if uri == notification.uri
+            ));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::lexicon::app::bsky::actor::Profile;
+use crate::lexicon::com::atproto::label::{SelfLabel, SelfLabels, SelfLabelsUnion};
+use crate::lexicon::com::atproto::repo::StrongRef;
+
+/// Changes to make to your `app.bsky.actor.profile` record, see `BlueskyMe::update_profile`.
+/// Anything that isn't set is left as it is
+#[derive(Debug, Default)]
+pub struct ProfileUpdate {
+    pub(crate) display_name: Option<Option<String>>,
+    pub(crate) description: Option<Option<String>>,
+    pub(crate) avatar: Option<Option<Vec<u8>>>,
+    pub(crate) banner: Option<Option<Vec<u8>>>,
+    pub(crate) pinned_post: Option<Option<StrongRef>>,
+    pub(crate) labels: Option<Vec<String>>,
+    pub(crate) joined_via_starter_pack: Option<Option<StrongRef>>,
+}
+
+impl ProfileUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(Some(display_name.into()));
+        self
+    }
+
+    pub fn clear_display_name(mut self) -> Self {
+        self.display_name = Some(None);
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(Some(description.into()));
+        self
+    }
+
+    pub fn clear_description(mut self) -> Self {
+        self.description = Some(None);
+        self
+    }
+
+    /// A new avatar image, in any format `prepare_image` takes. It is stripped of metadata and
+    /// recompressed to fit the blob size limit before it is uploaded
+    pub fn avatar(mut self, image: impl Into<Vec<u8>>) -> Self {
+        self.avatar = Some(Some(image.into()));
+        self
+    }
+
+    pub fn clear_avatar(mut self) -> Self {
+        self.avatar = Some(None);
+        self
+    }
+
+    /// A new banner image, prepared like `avatar`
+    pub fn banner(mut self, image: impl Into<Vec<u8>>) -> Self {
+        self.banner = Some(Some(image.into()));
+        self
+    }
+
+    pub fn clear_banner(mut self) -> Self {
+        self.banner = Some(None);
+        self
+    }
+
+    pub fn pinned_post(mut self, post: impl Into<StrongRef>) -> Self {
+        self.pinned_post = Some(Some(post.into()));
+        self
+    }
+
+    pub fn clear_pinned_post(mut self) -> Self {
+        self.pinned_post = Some(None);
+        self
+    }
+
+    /// Replace the self-applied labels, such as `!no-unauthenticated`. An empty list removes them
+    pub fn labels<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.labels = Some(labels.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// The starter pack the account signed up through
+    pub fn joined_via_starter_pack(mut self, starter_pack: StrongRef) -> Self {
+        self.joined_via_starter_pack = Some(Some(starter_pack));
+        self
+    }
+
+    pub fn clear_joined_via_starter_pack(mut self) -> Self {
+        self.joined_via_starter_pack = Some(None);
+        self
+    }
+
+    /// Apply the text and reference changes to `profile`. Images are left to the caller, as they
+    /// have to be uploaded first
+    pub(crate) fn apply(&mut self, profile: &mut Profile) {
+        if let Some(display_name) = self.display_name.take() {
+            profile.display_name = display_name;
+        }
+        if let Some(description) = self.description.take() {
+            profile.description = description;
+        }
+        if let Some(pinned_post) = self.pinned_post.take() {
+            profile.pinned_post = pinned_post;
+        }
+        if let Some(labels) = self.labels.take() {
+            profile.labels = match labels.is_empty() {
+                true => None,
+                false => Some(SelfLabelsUnion::SelfLabels(SelfLabels {
+                    values: labels.into_iter().map(|val| SelfLabel { val }).collect(),
+                })),
+            };
+        }
+        if let Some(starter_pack) = self.joined_via_starter_pack.take() {
+            profile.joined_via_starter_pack = starter_pack;
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::bluesky::Bluesky;
+use crate::errors::BiskyError;
+use crate::lexicon::app::bsky::feed::Post;
+use crate::lexicon::app::bsky::richtext::{ByteSlice, Facet, FacetFeature, Link, Mention, Tag};
+use std::collections::HashMap;
+
+/// The longest tag, in characters and without the leading `#`, that is turned into a facet
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Post text together with the facets annotating it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichText {
+    pub text: String,
+    pub facets: Vec<Facet>,
+}
+
+/// A run of text that is covered by at most one facet
+#[derive(Debug, PartialEq, Eq)]
+pub struct RichTextSegment<'a> {
+    pub text: &'a str,
+    pub facet: Option<&'a Facet>,
+}
+
+impl<'a> RichTextSegment<'a> {
+    /// The DID this segment mentions, if it is a mention
+    pub fn mention(&self) -> Option<&'a str> {
+        self.features().find_map(|feature| match feature {
+            FacetFeature::Mention(mention) => Some(mention.did.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The URI this segment links to, if it is a link
+    pub fn link(&self) -> Option<&'a str> {
+        self.features().find_map(|feature| match feature {
+            FacetFeature::Link(link) => Some(link.uri.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The tag, without `#`, if this segment is a hashtag
+    pub fn tag(&self) -> Option<&'a str> {
+        self.features().find_map(|feature| match feature {
+            FacetFeature::Tag(tag) => Some(tag.tag.as_str()),
+            _ => None,
+        })
+    }
+
+    fn features(&self) -> impl Iterator<Item = &'a FacetFeature> {
+        self.facet
+            .into_iter()
+            .flat_map(|facet| facet.features.iter())
+    }
+}
+
+/// Something found by scanning text, before mentions are resolved to DIDs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectedFeature {
+    /// A handle, without `@`
+    Mention(String),
+    Link(String),
+    /// A tag, without `#`
+    Tag(String),
+}
+
+impl RichText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            facets: Vec::new(),
+        }
+    }
+
+    /// Take the text and facets of an existing post
+    pub fn from_post(post: &Post) -> Self {
+        Self {
+            text: post.text.clone(),
+            facets: post.facets.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Replace the facets with the mentions, links and tags found in the text. Mentioned handles
+    /// are resolved to DIDs; mentions of handles that don't resolve are left as plain text
+    pub async fn detect_facets(&mut self, bsky: &mut Bluesky) -> Result<(), BiskyError> {
+        let mut dids = HashMap::new();
+        let mut facets = Vec::new();
+
+        for (index, feature) in detect(&self.text) {
+            let feature = match feature {
+                DetectedFeature::Mention(handle) => {
+                    if !dids.contains_key(&handle) {
+                        let did = match bsky.resolve_handle(&handle).await {
+                            Ok(did) => Some(did),
+                            Err(BiskyError::ApiError(_)) => None,
+                            Err(e) => return Err(e),
+                        };
+                        dids.insert(handle.clone(), did);
+                    }
+                    match &dids[&handle] {
+                        Some(did) => FacetFeature::Mention(Mention { did: did.clone() }),
+                        None => continue,
+                    }
+                }
+                DetectedFeature::Link(uri) => FacetFeature::Link(Link { uri }),
+                DetectedFeature::Tag(tag) => FacetFeature::Tag(Tag { tag }),
+            };
+            facets.push(Facet {
+                index,
+                features: Vec::from([feature]),
+            });
+        }
+
+        self.facets = facets;
+        Ok(())
+    }
+
+    /// Replace the facets with the links and tags found in the text. Mentions are skipped, as
+    /// they can't be expressed without resolving the handle
+    pub fn detect_facets_without_resolution(&mut self) {
+        self.facets = detect(&self.text)
+            .into_iter()
+            .filter_map(|(index, feature)| {
+                let feature = match feature {
+                    DetectedFeature::Mention(_) => return None,
+                    DetectedFeature::Link(uri) => FacetFeature::Link(Link { uri }),
+                    DetectedFeature::Tag(tag) => FacetFeature::Tag(Tag { tag }),
+                };
+                Some(Facet {
+                    index,
+                    features: Vec::from([feature]),
+                })
+            })
+            .collect();
+    }
+
+    /// Split the text into runs that are either plain or covered by one facet, in order. Facets
+    /// that are out of bounds, not on character boundaries or overlap an earlier facet are
+    /// ignored
+    pub fn segments(&self) -> Vec<RichTextSegment<'_>> {
+        let mut facets = self.facets.iter().collect::<Vec<_>>();
+        facets.sort_by_key(|facet| facet.index.byte_start);
+
+        let mut segments = Vec::new();
+        let mut position = 0;
+
+        for facet in facets {
+            let ByteSlice {
+                byte_start,
+                byte_end,
+            } = facet.index;
+
+            if byte_start < position || byte_start >= byte_end {
+                continue;
+            }
+            let Some(facet_text) = self.text.get(byte_start..byte_end) else {
+                continue;
+            };
+
+            if byte_start > position {
+                segments.push(RichTextSegment {
+                    text: &self.text[position..byte_start],
+                    facet: None,
+                });
+            }
+            segments.push(RichTextSegment {
+                text: facet_text,
+                facet: Some(facet),
+            });
+            position = byte_end;
+        }
+
+        if position < self.text.len() {
+            segments.push(RichTextSegment {
+                text: &self.text[position..],
+                facet: None,
+            });
+        }
+
+        segments
+    }
+
+    /// Set the text and facets of `post` to this rich text
+    pub fn attach(self, post: &mut Post) {
+        post.text = self.text;
+        post.facets = match self.facets.is_empty() {
+            true => None,
+            false => Some(self.facets),
+        };
+    }
+}
+
+/// Scan text for `@handle` mentions, `http(s)://` links and `#tags`, returning their byte
+/// ranges in order of appearance
+pub fn detect(text: &str) -> Vec<(ByteSlice, DetectedFeature)> {
+    let mut found = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut skip_to = 0;
+
+    for (start, c) in text.char_indices() {
+        let before = previous.replace(c);
+        // `is_none_or` would need Rust 1.82
+        #[allow(clippy::unnecessary_map_or)]
+        let at_word_start = before.map_or(true, char::is_whitespace);
+        let after_paren = before == Some('(');
+
+        if start < skip_to {
+            continue;
+        }
+
+        let rest = &text[start..];
+        let detected = match c {
+            '@' if at_word_start || after_paren => detect_mention(rest),
+            'h' | 'H' if at_word_start || after_paren => detect_link(rest),
+            '#' | '＃' if at_word_start => detect_tag(rest),
+            _ => None,
+        };
+
+        if let Some((length, feature)) = detected {
+            found.push((
+                ByteSlice {
+                    byte_start: start,
+                    byte_end: start + length,
+                },
+                feature,
+            ));
+            skip_to = start + length;
+        }
+    }
+
+    found
+}
+
+fn detect_mention(rest: &str) -> Option<(usize, DetectedFeature)> {
+    let handle = &rest[1..];
+    let end = handle
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+        .unwrap_or(handle.len());
+    let handle = handle[..end].trim_end_matches(['.', '-']);
+
+    // the handle has to end at a word boundary
+    if rest[1 + handle.len()..].starts_with('_') || !is_valid_handle(handle) {
+        return None;
+    }
+    Some((
+        1 + handle.len(),
+        DetectedFeature::Mention(handle.to_lowercase()),
+    ))
+}
+
+fn is_valid_handle(handle: &str) -> bool {
+    let labels = handle.split('.').collect::<Vec<_>>();
+
+    handle.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && labels
+            .last()
+            .and_then(|tld| tld.chars().next())
+            .is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+fn detect_link(rest: &str) -> Option<(usize, DetectedFeature)> {
+    let lowercase = rest.get(..8).unwrap_or(rest).to_ascii_lowercase();
+    let scheme_length = if lowercase.starts_with("https://") {
+        8
+    } else if lowercase.starts_with("http://") {
+        7
+    } else {
+        return None;
+    };
+
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let mut uri = rest[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+    if uri.ends_with(')') && !uri.contains('(') {
+        uri = &uri[..uri.len() - 1];
+    }
+
+    if uri.len() <= scheme_length {
+        return None;
+    }
+    Some((uri.len(), DetectedFeature::Link(uri.to_string())))
+}
+
+fn detect_tag(rest: &str) -> Option<(usize, DetectedFeature)> {
+    let hash_length = rest.chars().next()?.len_utf8();
+    let tag = &rest[hash_length..];
+
+    if tag.starts_with('\u{fe0f}') {
+        // keycap emoji, #️⃣
+        return None;
+    }
+
+    let end = tag
+        .find(|c: char| c.is_whitespace() || is_invisible(c))
+        .unwrap_or(tag.len());
+    let tag = tag[..end].trim_end_matches(is_punctuation);
+
+    if tag.is_empty()
+        || tag.chars().count() > MAX_TAG_LENGTH
+        || tag.chars().all(|c| c.is_numeric() || is_punctuation(c))
+    {
+        return None;
+    }
+    Some((
+        hash_length + tag.len(),
+        DetectedFeature::Tag(tag.to_string()),
+    ))
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00ad}' | '\u{2060}' | '\u{200a}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{20e2}'
+    )
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '…' | '、'
+                | '。'
+                | '，'
+                | '！'
+                | '？'
+                | '“'
+                | '”'
+                | '‘'
+                | '’'
+                | '«'
+                | '»'
+                | '¡'
+                | '¿'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text each detected feature covers, checked against its byte range
+    fn detected(text: &str) -> Vec<(&str, DetectedFeature)> {
+        detect(text)
+            .into_iter()
+            .map(|(index, feature)| (&text[index.byte_start..index.byte_end], feature))
+            .collect()
+    }
+
+    #[test]
+    fn mention_after_emoji() {
+        let text = "👋 @alice.bsky.social hi";
+        assert_eq!(
+            detect(text),
+            [(
+                ByteSlice {
+                    byte_start: 5,
+                    byte_end: 23,
+                },
+                DetectedFeature::Mention("alice.bsky.social".to_string()),
+            )]
+        );
+    }
+
+    #[test]
+    fn link_with_multibyte_path() {
+        let text = "日本語 https://example.com/パス です";
+        assert_eq!(
+            detect(text),
+            [(
+                ByteSlice {
+                    byte_start: 10,
+                    byte_end: 36,
+                },
+                DetectedFeature::Link("https://example.com/パス".to_string()),
+            )]
+        );
+    }
+
+    #[test]
+    fn tags_with_multibyte_characters() {
+        assert_eq!(
+            detected("＃タグ and #rust🦀 or #café!"),
+            [
+                ("＃タグ", DetectedFeature::Tag("タグ".to_string())),
+                ("#rust🦀", DetectedFeature::Tag("rust🦀".to_string())),
+                ("#café", DetectedFeature::Tag("café".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_ends_at_zero_width_joiner() {
+        assert_eq!(
+            detected("#family\u{200d}👨‍👩‍👧 yes"),
+            [("#family", DetectedFeature::Tag("family".to_string()))]
+        );
+    }
+
+    #[test]
+    fn trailing_punctuation_is_excluded() {
+        assert_eq!(
+            detected("(see https://example.com/ü), @bob.test. ¿#qué?"),
+            [
+                (
+                    "https://example.com/ü",
+                    DetectedFeature::Link("https://example.com/ü".to_string())
+                ),
+                (
+                    "@bob.test",
+                    DetectedFeature::Mention("bob.test".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_detected_mid_word() {
+        assert!(detect("email@example.com x#tag #123 #️⃣").is_empty());
+    }
+
+    #[test]
+    fn without_resolution_skips_mentions() {
+        let mut rich_text = RichText::new("héllo @alice.test #tag");
+        rich_text.detect_facets_without_resolution();
+        assert_eq!(
+            rich_text.facets,
+            [Facet {
+                index: ByteSlice {
+                    byte_start: 19,
+                    byte_end: 23,
+                },
+                features: Vec::from([FacetFeature::Tag(Tag {
+                    tag: "tag".to_string()
+                })]),
+            }]
+        );
+    }
+
+    #[test]
+    fn segments_cover_the_text() {
+        let mut rich_text = RichText::new("✨ https://example.com ✨ #tag ✨");
+        rich_text.detect_facets_without_resolution();
+
+        let segments = rich_text.segments();
+        let texts = segments.iter().map(|s| s.text).collect::<Vec<_>>();
+        assert_eq!(texts, ["✨ ", "https://example.com", " ✨ ", "#tag", " ✨"]);
+        assert_eq!(segments[1].link(), Some("https://example.com"));
+        assert_eq!(segments[3].tag(), Some("tag"));
+        assert_eq!(texts.concat(), rich_text.text);
+    }
+
+    #[test]
+    fn segments_skip_invalid_facets() {
+        let facet = |byte_start, byte_end| Facet {
+            index: ByteSlice {
+                byte_start,
+                byte_end,
+            },
+            features: Vec::from([FacetFeature::Tag(Tag {
+                tag: "x".to_string(),
+            })]),
+        };
+        let rich_text = RichText {
+            text: "é#x".to_string(),
+            // splits `é`, empty, out of bounds, valid, then overlapping the valid one
+            facets: Vec::from([
+                facet(1, 3),
+                facet(0, 0),
+                facet(2, 9),
+                facet(2, 4),
+                facet(3, 4),
+            ]),
+        };
+
+        let segments = rich_text.segments();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "é");
+        assert!(segments[0].facet.is_none());
+        assert_eq!(segments[1].text, "#x");
+        assert_eq!(segments[1].tag(), Some("x"));
+    }
+}
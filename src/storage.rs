@@ -15,6 +15,13 @@ pub trait Storage<T: DeserializeOwned + Serialize + Sync> {
 
     async fn set(&self, data: Option<&T>) -> Result<(), Self::Error>;
     async fn get(&self) -> Result<T, Self::Error>;
+
+    /// Whether `error`, as returned by `get`, means nothing has been stored yet rather than that
+    /// reading failed. Storages that can't tell the two apart treat every error as a failure
+    fn is_not_found(&self, error: &Self::Error) -> bool {
+        let _ = error;
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +59,10 @@ impl<'a, T: DeserializeOwned + Serialize + Sync> Storage<T> for File<'a, T> {
     async fn get(&self) -> Result<T, Self::Error> {
         Ok(serde_json::from_slice(&tokio::fs::read(&self.path).await?)?)
     }
+
+    fn is_not_found(&self, error: &Self::Error) -> bool {
+        matches!(error, BiskyError::IoError(error) if error.kind() == std::io::ErrorKind::NotFound)
+    }
 }
 
 impl<'a> StorableSession for File<'a, UserSession> {}
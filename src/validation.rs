@@ -0,0 +1,581 @@
+use crate::lexicon::app::bsky::actor::Profile;
+use crate::lexicon::app::bsky::embed::{ExternalObject, Image, Media, RecordWithMedia, Video};
+use crate::lexicon::app::bsky::feed::{Embeds, Post};
+use crate::lexicon::app::bsky::richtext::Facet;
+use crate::lexicon::chat::bsky::convo::MessageInput;
+use crate::lexicon::com::atproto::repo::Blob;
+use miette::Diagnostic;
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// app.bsky.feed.post#text maxGraphemes
+pub const MAX_POST_GRAPHEMES: usize = 300;
+/// app.bsky.feed.post#text maxLength
+pub const MAX_POST_BYTES: usize = 3000;
+/// app.bsky.embed.images#images maxLength
+pub const MAX_IMAGES: usize = 4;
+/// The alt text limit the official clients enforce
+pub const MAX_ALT_TEXT_GRAPHEMES: usize = 2000;
+/// app.bsky.feed.post#langs maxLength
+pub const MAX_LANGS: usize = 3;
+/// app.bsky.feed.post#tags maxLength
+pub const MAX_TAGS: usize = 8;
+/// app.bsky.feed.post#tags maxGraphemes of each tag
+pub const MAX_TAG_GRAPHEMES: usize = 64;
+/// app.bsky.feed.post#tags maxLength of each tag
+pub const MAX_TAG_BYTES: usize = 640;
+/// app.bsky.embed.images#image maxSize
+pub const MAX_IMAGE_BLOB_SIZE: usize = 1_000_000;
+/// app.bsky.embed.external#external thumb maxSize
+pub const MAX_EXTERNAL_THUMB_SIZE: usize = 1_000_000;
+/// app.bsky.embed.video#video maxSize
+pub const MAX_VIDEO_BLOB_SIZE: usize = 100_000_000;
+/// app.bsky.embed.video#alt maxGraphemes
+pub const MAX_VIDEO_ALT_GRAPHEMES: usize = 1000;
+/// app.bsky.embed.video#captions maxLength
+pub const MAX_CAPTIONS: usize = 20;
+/// app.bsky.embed.video#caption file maxSize
+pub const MAX_CAPTION_BLOB_SIZE: usize = 20_000;
+/// app.bsky.actor.profile#displayName maxGraphemes
+pub const MAX_DISPLAY_NAME_GRAPHEMES: usize = 64;
+/// app.bsky.actor.profile#displayName maxLength
+pub const MAX_DISPLAY_NAME_BYTES: usize = 640;
+/// app.bsky.actor.profile#description maxGraphemes
+pub const MAX_DESCRIPTION_GRAPHEMES: usize = 256;
+/// app.bsky.actor.profile#description maxLength
+pub const MAX_DESCRIPTION_BYTES: usize = 2560;
+/// app.bsky.graph.starterpack#feeds maxLength
+pub const MAX_STARTER_PACK_FEEDS: usize = 3;
+/// app.bsky.actor.profile#avatar maxSize
+pub const MAX_AVATAR_SIZE: usize = 1_000_000;
+/// app.bsky.actor.profile#banner maxSize
+pub const MAX_BANNER_SIZE: usize = 1_000_000;
+/// com.atproto.moderation.createReport#reason maxGraphemes
+pub const MAX_REPORT_REASON_GRAPHEMES: usize = 2000;
+/// com.atproto.moderation.createReport#reason maxLength
+pub const MAX_REPORT_REASON_BYTES: usize = 20000;
+/// chat.bsky.convo.defs#messageInput text maxGraphemes
+pub const MAX_MESSAGE_GRAPHEMES: usize = 1000;
+/// chat.bsky.convo.defs#messageInput text maxLength
+pub const MAX_MESSAGE_BYTES: usize = 10000;
+
+#[derive(Debug, Error, Diagnostic, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("Post text is {graphemes} graphemes long, the limit is {max}")]
+    TextTooManyGraphemes { graphemes: usize, max: usize },
+    #[error("Post text is {bytes} bytes long, the limit is {max}")]
+    TextTooManyBytes { bytes: usize, max: usize },
+    #[error("Post has {count} images, the limit is {max}")]
+    TooManyImages { count: usize, max: usize },
+    #[error("Alt text of image {index} is {graphemes} graphemes long, the limit is {max}")]
+    AltTextTooLong {
+        index: usize,
+        graphemes: usize,
+        max: usize,
+    },
+    #[error("Post has {count} languages, the limit is {max}")]
+    TooManyLangs { count: usize, max: usize },
+    #[error("Post has {count} tags, the limit is {max}")]
+    TooManyTags { count: usize, max: usize },
+    #[error(
+        "Tag {index} is too long, the limit is {max_graphemes} graphemes and {max_bytes} bytes"
+    )]
+    TagTooLong {
+        index: usize,
+        max_graphemes: usize,
+        max_bytes: usize,
+    },
+    #[error("A post can only embed one of images, an external link or a video")]
+    ConflictingEmbeds,
+    #[error("Video alt text is {graphemes} graphemes long, the limit is {max}")]
+    VideoAltTextTooLong { graphemes: usize, max: usize },
+    #[error("Video has {count} captions, the limit is {max}")]
+    TooManyCaptions { count: usize, max: usize },
+    #[error("Facet {index} covers bytes {byte_start}..{byte_end} of a {text_length} byte text")]
+    FacetOutOfBounds {
+        index: usize,
+        byte_start: usize,
+        byte_end: usize,
+        text_length: usize,
+    },
+    #[error("Facet {index} covers bytes {byte_start}..{byte_end}, which splits a character")]
+    FacetNotOnCharBoundary {
+        index: usize,
+        byte_start: usize,
+        byte_end: usize,
+    },
+    #[error("Blob is {size} bytes, the limit is {max}")]
+    BlobTooLarge { size: usize, max: usize },
+    #[error("Blob has MIME type {mime_type}, expected {expected}")]
+    BlobWrongMimeType {
+        mime_type: String,
+        expected: &'static str,
+    },
+    #[error(
+        "Profile {field} is too long, the limit is {max_graphemes} graphemes and {max_bytes} bytes"
+    )]
+    ProfileFieldTooLong {
+        field: &'static str,
+        max_graphemes: usize,
+        max_bytes: usize,
+    },
+    #[error("Starter pack has {count} feeds, the limit is {max}")]
+    TooManyStarterPackFeeds { count: usize, max: usize },
+    #[error(
+        "Report reason is too long, the limit is {max_graphemes} graphemes and {max_bytes} bytes"
+    )]
+    ReportReasonTooLong {
+        max_graphemes: usize,
+        max_bytes: usize,
+    },
+    #[error(
+        "Message text is too long, the limit is {max_graphemes} graphemes and {max_bytes} bytes"
+    )]
+    MessageTooLong {
+        max_graphemes: usize,
+        max_bytes: usize,
+    },
+    #[error("{uri} is not a post that can be replied to")]
+    NotAReplyTarget { uri: String },
+}
+
+/// Check a post against the constraints of the app.bsky.feed.post lexicon
+pub fn validate_post(post: &Post) -> Result<(), ValidationError> {
+    validate_text(&post.text)?;
+
+    if let Some(facets) = &post.facets {
+        validate_facets(&post.text, facets)?;
+    }
+
+    if let Some(langs) = &post.langs {
+        if langs.len() > MAX_LANGS {
+            return Err(ValidationError::TooManyLangs {
+                count: langs.len(),
+                max: MAX_LANGS,
+            });
+        }
+    }
+
+    if let Some(tags) = &post.tags {
+        if tags.len() > MAX_TAGS {
+            return Err(ValidationError::TooManyTags {
+                count: tags.len(),
+                max: MAX_TAGS,
+            });
+        }
+        for (index, tag) in tags.iter().enumerate() {
+            if tag.len() > MAX_TAG_BYTES || tag.graphemes(true).count() > MAX_TAG_GRAPHEMES {
+                return Err(ValidationError::TagTooLong {
+                    index,
+                    max_graphemes: MAX_TAG_GRAPHEMES,
+                    max_bytes: MAX_TAG_BYTES,
+                });
+            }
+        }
+    }
+
+    match &post.embed {
+        Some(Embeds::Images(images)) => validate_images(&images.images)?,
+        Some(Embeds::External(external)) => validate_external(&external.external)?,
+        Some(Embeds::Video(video)) => validate_video(video)?,
+        Some(Embeds::RecordWithMedia(RecordWithMedia { media, .. })) => match media {
+            Media::Images(images) => validate_images(&images.images)?,
+            Media::External(external) => validate_external(&external.external)?,
+            Media::Video(video) => validate_video(video)?,
+            Media::Unknown(_) => {}
+        },
+        Some(Embeds::Record(_)) | Some(Embeds::Unknown(_)) | None => {}
+    }
+
+    Ok(())
+}
+
+/// Check a profile record against the constraints of the app.bsky.actor.profile lexicon
+pub fn validate_profile(profile: &Profile) -> Result<(), ValidationError> {
+    let fields = [
+        (
+            "display name",
+            &profile.display_name,
+            MAX_DISPLAY_NAME_GRAPHEMES,
+            MAX_DISPLAY_NAME_BYTES,
+        ),
+        (
+            "description",
+            &profile.description,
+            MAX_DESCRIPTION_GRAPHEMES,
+            MAX_DESCRIPTION_BYTES,
+        ),
+    ];
+    for (field, value, max_graphemes, max_bytes) in fields {
+        if let Some(value) = value {
+            if value.len() > max_bytes || value.graphemes(true).count() > max_graphemes {
+                return Err(ValidationError::ProfileFieldTooLong {
+                    field,
+                    max_graphemes,
+                    max_bytes,
+                });
+            }
+        }
+    }
+
+    if let Some(avatar) = &profile.avatar {
+        validate_blob(avatar, "image/", MAX_AVATAR_SIZE)?;
+    }
+    if let Some(banner) = &profile.banner {
+        validate_blob(banner, "image/", MAX_BANNER_SIZE)?;
+    }
+
+    Ok(())
+}
+
+/// Check the free text reason given with a report
+pub fn validate_report_reason(reason: &str) -> Result<(), ValidationError> {
+    if reason.len() > MAX_REPORT_REASON_BYTES
+        || reason.graphemes(true).count() > MAX_REPORT_REASON_GRAPHEMES
+    {
+        return Err(ValidationError::ReportReasonTooLong {
+            max_graphemes: MAX_REPORT_REASON_GRAPHEMES,
+            max_bytes: MAX_REPORT_REASON_BYTES,
+        });
+    }
+    Ok(())
+}
+
+/// Check a direct message against the constraints of chat.bsky.convo.defs#messageInput
+pub fn validate_message(message: &MessageInput) -> Result<(), ValidationError> {
+    if message.text.len() > MAX_MESSAGE_BYTES
+        || message.text.graphemes(true).count() > MAX_MESSAGE_GRAPHEMES
+    {
+        return Err(ValidationError::MessageTooLong {
+            max_graphemes: MAX_MESSAGE_GRAPHEMES,
+            max_bytes: MAX_MESSAGE_BYTES,
+        });
+    }
+
+    match &message.facets {
+        Some(facets) => validate_facets(&message.text, facets),
+        None => Ok(()),
+    }
+}
+
+/// Check the thumbnail of a link card
+pub fn validate_external(external: &ExternalObject) -> Result<(), ValidationError> {
+    match &external.thumb {
+        Some(thumb) => validate_blob(thumb, "image/", MAX_EXTERNAL_THUMB_SIZE),
+        None => Ok(()),
+    }
+}
+
+/// Check a video embed against the blob, alt text and caption limits
+pub fn validate_video(video: &Video) -> Result<(), ValidationError> {
+    validate_blob(&video.video, "video/", MAX_VIDEO_BLOB_SIZE)?;
+
+    if let Some(alt) = &video.alt {
+        let graphemes = alt.graphemes(true).count();
+        if graphemes > MAX_VIDEO_ALT_GRAPHEMES {
+            return Err(ValidationError::VideoAltTextTooLong {
+                graphemes,
+                max: MAX_VIDEO_ALT_GRAPHEMES,
+            });
+        }
+    }
+
+    if let Some(captions) = &video.captions {
+        if captions.len() > MAX_CAPTIONS {
+            return Err(ValidationError::TooManyCaptions {
+                count: captions.len(),
+                max: MAX_CAPTIONS,
+            });
+        }
+        for caption in captions {
+            validate_blob(&caption.file, "text/vtt", MAX_CAPTION_BLOB_SIZE)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the images of an images embed against the count, alt text and blob limits
+pub fn validate_images(images: &[Image]) -> Result<(), ValidationError> {
+    if images.len() > MAX_IMAGES {
+        return Err(ValidationError::TooManyImages {
+            count: images.len(),
+            max: MAX_IMAGES,
+        });
+    }
+    for (index, image) in images.iter().enumerate() {
+        let graphemes = image.alt.graphemes(true).count();
+        if graphemes > MAX_ALT_TEXT_GRAPHEMES {
+            return Err(ValidationError::AltTextTooLong {
+                index,
+                graphemes,
+                max: MAX_ALT_TEXT_GRAPHEMES,
+            });
+        }
+        validate_blob(&image.image, "image/", MAX_IMAGE_BLOB_SIZE)?;
+    }
+
+    Ok(())
+}
+
+/// Check post text against the grapheme and byte limits
+pub fn validate_text(text: &str) -> Result<(), ValidationError> {
+    if text.len() > MAX_POST_BYTES {
+        return Err(ValidationError::TextTooManyBytes {
+            bytes: text.len(),
+            max: MAX_POST_BYTES,
+        });
+    }
+
+    let graphemes = text.graphemes(true).count();
+    if graphemes > MAX_POST_GRAPHEMES {
+        return Err(ValidationError::TextTooManyGraphemes {
+            graphemes,
+            max: MAX_POST_GRAPHEMES,
+        });
+    }
+
+    Ok(())
+}
+
+/// Check that every facet covers a non-empty range of `text` that starts and ends between
+/// characters
+pub fn validate_facets(text: &str, facets: &[Facet]) -> Result<(), ValidationError> {
+    for (index, facet) in facets.iter().enumerate() {
+        let (byte_start, byte_end) = (facet.index.byte_start, facet.index.byte_end);
+
+        if byte_start >= byte_end || byte_end > text.len() {
+            return Err(ValidationError::FacetOutOfBounds {
+                index,
+                byte_start,
+                byte_end,
+                text_length: text.len(),
+            });
+        }
+        if !text.is_char_boundary(byte_start) || !text.is_char_boundary(byte_end) {
+            return Err(ValidationError::FacetNotOnCharBoundary {
+                index,
+                byte_start,
+                byte_end,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a blob's MIME type starts with `mime_prefix` and that it is at most `max_size`
+/// bytes
+pub fn validate_blob(
+    blob: &Blob,
+    mime_prefix: &'static str,
+    max_size: usize,
+) -> Result<(), ValidationError> {
+    if !blob.mime_type.starts_with(mime_prefix) {
+        return Err(ValidationError::BlobWrongMimeType {
+            mime_type: blob.mime_type.clone(),
+            expected: mime_prefix,
+        });
+    }
+    if blob.size > max_size {
+        return Err(ValidationError::BlobTooLarge {
+            size: blob.size,
+            max: max_size,
+        });
+    }
+
+    Ok(())
+}
+
+/// Shorten `text` to at most `max_graphemes` graphemes, ending it with an ellipsis if anything
+/// was cut. Text that already fits is returned unchanged
+pub fn truncate_graphemes(text: &str, max_graphemes: usize) -> String {
+    if text.graphemes(true).nth(max_graphemes).is_none() {
+        return text.to_string();
+    }
+    if max_graphemes == 0 {
+        return String::new();
+    }
+
+    let mut truncated = text
+        .graphemes(true)
+        .take(max_graphemes.saturating_sub(1))
+        .collect::<String>();
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexicon::app::bsky::richtext::ByteSlice;
+
+    /// A man, a woman, a girl and a boy joined by zero width joiners: one grapheme, 25 bytes
+    const FAMILY: &str = "👨\u{200d}👩\u{200d}👧\u{200d}👦";
+
+    #[test]
+    fn text_limits() {
+        let cases = [
+            ("a".repeat(MAX_POST_GRAPHEMES), Ok(())),
+            (
+                "a".repeat(MAX_POST_GRAPHEMES + 1),
+                Err(ValidationError::TextTooManyGraphemes {
+                    graphemes: 301,
+                    max: MAX_POST_GRAPHEMES,
+                }),
+            ),
+            // 4 bytes each
+            ("😀".repeat(MAX_POST_GRAPHEMES), Ok(())),
+            (
+                "😀".repeat(MAX_POST_GRAPHEMES + 1),
+                Err(ValidationError::TextTooManyGraphemes {
+                    graphemes: 301,
+                    max: MAX_POST_GRAPHEMES,
+                }),
+            ),
+            // `e` and a combining acute accent
+            ("e\u{301}".repeat(MAX_POST_GRAPHEMES), Ok(())),
+            // exactly at the byte limit
+            (FAMILY.repeat(120), Ok(())),
+            (
+                FAMILY.repeat(121),
+                Err(ValidationError::TextTooManyBytes {
+                    bytes: 3025,
+                    max: MAX_POST_BYTES,
+                }),
+            ),
+            (
+                format!("{}{}", FAMILY.repeat(119), "a".repeat(26)),
+                Err(ValidationError::TextTooManyBytes {
+                    bytes: 3001,
+                    max: MAX_POST_BYTES,
+                }),
+            ),
+            (String::new(), Ok(())),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(validate_text(&text), expected, "{} bytes", text.len());
+        }
+    }
+
+    #[test]
+    fn facet_bounds() {
+        // `é` is bytes 1..3 and `👋` is bytes 6..10
+        let text = "héllo👋";
+        let cases = [
+            ((0, 10), Ok(())),
+            ((1, 3), Ok(())),
+            ((6, 10), Ok(())),
+            (
+                (0, 11),
+                Err(ValidationError::FacetOutOfBounds {
+                    index: 0,
+                    byte_start: 0,
+                    byte_end: 11,
+                    text_length: 10,
+                }),
+            ),
+            (
+                (3, 3),
+                Err(ValidationError::FacetOutOfBounds {
+                    index: 0,
+                    byte_start: 3,
+                    byte_end: 3,
+                    text_length: 10,
+                }),
+            ),
+            (
+                (4, 2),
+                Err(ValidationError::FacetOutOfBounds {
+                    index: 0,
+                    byte_start: 4,
+                    byte_end: 2,
+                    text_length: 10,
+                }),
+            ),
+            (
+                (2, 5),
+                Err(ValidationError::FacetNotOnCharBoundary {
+                    index: 0,
+                    byte_start: 2,
+                    byte_end: 5,
+                }),
+            ),
+            (
+                (6, 8),
+                Err(ValidationError::FacetNotOnCharBoundary {
+                    index: 0,
+                    byte_start: 6,
+                    byte_end: 8,
+                }),
+            ),
+        ];
+
+        for ((byte_start, byte_end), expected) in cases {
+            let facets = [Facet {
+                index: ByteSlice {
+                    byte_start,
+                    byte_end,
+                },
+                features: Vec::new(),
+            }];
+            assert_eq!(
+                validate_facets(text, &facets),
+                expected,
+                "{byte_start}..{byte_end}"
+            );
+        }
+    }
+
+    #[test]
+    fn tag_limits() {
+        let cases = [
+            ("a".repeat(MAX_TAG_GRAPHEMES), true),
+            ("a".repeat(MAX_TAG_GRAPHEMES + 1), false),
+            (FAMILY.repeat(MAX_TAG_GRAPHEMES), false),
+            (FAMILY.repeat(25), true),
+            (FAMILY.repeat(26), false),
+        ];
+
+        for (tag, valid) in cases {
+            let post = Post::builder().text("tagged").tags([tag.as_str()]).build();
+            assert_eq!(post.is_ok(), valid, "{} bytes", tag.len());
+        }
+
+        let post = Post::builder()
+            .text("tagged")
+            .tags((0..=MAX_TAGS).map(|i| i.to_string()))
+            .build();
+        assert!(post.is_err());
+    }
+
+    #[test]
+    fn truncates_by_grapheme() {
+        let cases = [
+            ("short", 10, "short"),
+            ("exact", 5, "exact"),
+            ("", 0, ""),
+            ("abcdef", 4, "abc…"),
+            ("abcdef", 1, "…"),
+            ("abcdef", 0, ""),
+            // trailing whitespace before the ellipsis is dropped
+            ("ab cdef", 4, "ab…"),
+            // a combining accent stays with its letter
+            ("e\u{301}e\u{301}e\u{301}", 3, "e\u{301}e\u{301}e\u{301}"),
+            ("e\u{301}e\u{301}e\u{301}", 2, "e\u{301}…"),
+        ];
+        for (text, max, expected) in cases {
+            assert_eq!(truncate_graphemes(text, max), expected, "{text:?} to {max}");
+        }
+
+        let family = FAMILY.repeat(3);
+        assert_eq!(truncate_graphemes(&family, 3), family);
+        assert_eq!(truncate_graphemes(&family, 2), format!("{FAMILY}…"));
+        assert_eq!(
+            truncate_graphemes(&format!("{FAMILY}{FAMILY}a"), 2),
+            format!("{FAMILY}…")
+        );
+    }
+}